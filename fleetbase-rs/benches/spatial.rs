@@ -0,0 +1,131 @@
+//! Run with `cargo bench -p fleetbase-rs --bench spatial`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::json;
+
+use fleetbase_rs::client::FleetbaseClient;
+use fleetbase_rs::driver::Driver;
+use fleetbase_rs::driver_registry::DriverRegistry;
+use fleetbase_rs::geo::{LineString, Point, Polygon};
+use fleetbase_rs::zone::{Border, ZoneManager};
+
+/// Roughly the Singapore / Johor metro area.
+const MIN_LNG: f64 = 103.5;
+const MAX_LNG: f64 = 104.1;
+const MIN_LAT: f64 = 1.2;
+const MAX_LAT: f64 = 1.6;
+
+fn random_point(rng: &mut StdRng) -> Point {
+    Point::new(
+        rng.gen_range(MIN_LNG..MAX_LNG),
+        rng.gen_range(MIN_LAT..MAX_LAT),
+    )
+    .unwrap()
+}
+
+fn hexagon(center: Point, radius: f64) -> Border {
+    let mut ring: Vec<Point> = (0..6)
+        .map(|i| {
+            let angle = (i as f64 * 60.0).to_radians();
+            Point::new(
+                center.longitude() + radius * angle.cos(),
+                center.latitude() + radius * angle.sin(),
+            )
+            .unwrap()
+        })
+        .collect();
+    ring.push(ring[0]);
+    Border::new(Polygon::new(LineString::new(ring), Vec::new()))
+}
+
+fn zones(n: usize, rng: &mut StdRng) -> ZoneManager {
+    let mut manager = ZoneManager::new();
+    for i in 0..n {
+        manager
+            .create_zone(
+                format!("zone {}", i),
+                hexagon(random_point(rng), rng.gen_range(0.002..0.02)),
+                "#00ff00".to_string(),
+                "#008800".to_string(),
+                None,
+            )
+            .unwrap();
+    }
+    manager
+}
+
+fn drivers(n: usize, rng: &mut StdRng) -> DriverRegistry {
+    let client = FleetbaseClient::new("bench".to_string());
+    let mut registry = DriverRegistry::new();
+    for i in 0..n {
+        let location = random_point(rng);
+        let attributes = json!({
+            "id": format!("driver_{}", i),
+            "location": location,
+        });
+        registry.upsert(Driver::new(attributes, client.clone()).unwrap());
+    }
+    registry
+}
+
+fn bench_zones(c: &mut Criterion) {
+    let mut group = c.benchmark_group("zones_containing");
+    for n in [1_000, 10_000] {
+        let mut rng = StdRng::seed_from_u64(7);
+        let manager = zones(n, &mut rng);
+        let probes: Vec<Point> = (0..64).map(|_| random_point(&mut rng)).collect();
+
+        group.bench_with_input(BenchmarkId::new("indexed", n), &probes, |b, probes| {
+            b.iter(|| {
+                for point in probes {
+                    black_box(manager.zones_containing(point));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("linear", n), &probes, |b, probes| {
+            b.iter(|| {
+                for point in probes {
+                    black_box(
+                        manager
+                            .list_zones(None)
+                            .into_iter()
+                            .filter(|zone| zone.contains(point))
+                            .count(),
+                    );
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_drivers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("drivers");
+    for n in [1_000, 10_000] {
+        let mut rng = StdRng::seed_from_u64(11);
+        let registry = drivers(n, &mut rng);
+        let center = random_point(&mut rng);
+
+        group.bench_with_input(BenchmarkId::new("nearest_5", n), &center, |b, center| {
+            b.iter(|| black_box(registry.nearest(center, 5)))
+        });
+        group.bench_with_input(BenchmarkId::new("within_2km", n), &center, |b, center| {
+            b.iter(|| black_box(registry.within_radius(center, 2_000.0)))
+        });
+        group.bench_function(BenchmarkId::new("update_location", n), |b| {
+            let mut registry = drivers(n, &mut rng);
+            let mut i = 0;
+            b.iter(|| {
+                let point = random_point(&mut rng);
+                registry.update_location(&format!("driver_{}", i % n), point);
+                i += 1;
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_zones, bench_drivers);
+criterion_main!(benches);
@@ -1,28 +1,188 @@
-use reqwest::{Client, Error, Method, RequestBuilder, Response};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, USER_AGENT,
+};
+use reqwest::{Certificate, Client, Method, Proxy};
 use serde::{de::DeserializeOwned, Serialize};
-use std::time::Duration;
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::contact::Contact;
+use crate::driver::Driver;
+use crate::entity::Entity;
+use crate::error::FleetbaseError;
+use crate::options::RequestOptions;
+use crate::order::Order;
+use crate::pagination::{Page, Paginator};
+use crate::place::Place;
+use crate::query::ListQuery;
+use crate::rate_limit::RateLimiter;
+use crate::retry::{parse_retry_after, RetryEvent, RetryHook, RetryPolicy, IDEMPOTENCY_KEY_HEADER};
+use crate::service::ResourceService;
+use crate::service_rate::ServiceRate;
+use crate::tracking_number::TrackingNumber;
+use crate::tracking_status::TrackingStatus;
+use crate::transport::{HttpRequest, HttpResponse, HttpTransport, ReqwestTransport};
+use crate::utils::enpdpoints::Endpoint;
+use crate::vehicle::Vehicle;
+use crate::vendor::Vendor;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.fleetbase.io";
+pub const DEFAULT_API_VERSION: &str = "v1";
 
-//BASE_URL comes from env file
-const BASE_URL = env::var("FLEETBASE_API_URL").expect("FLEETBASE_API_URL must be set");
+/// The organization requests act in and the tokens known for each
+/// organization. Shared by every clone of a client, so a switch made through
+/// one handle (e.g. a driver's) is seen by all of them.
+#[derive(Debug, Default)]
+struct OrganizationContext {
+    current: Option<String>,
+    tokens: HashMap<String, String>,
+}
 
+#[derive(Clone)]
 pub struct FleetbaseClient {
-    client: Client,
+    transport: Arc<dyn HttpTransport>,
     token: String,
+    organization: Arc<RwLock<OrganizationContext>>,
+    base_url: String,
+    default_headers: HeaderMap,
+    retry_policy: RetryPolicy,
+    on_retry: Option<RetryHook>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl fmt::Debug for FleetbaseClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FleetbaseClient")
+            .field("base_url", &self.base_url)
+            .field("organization", &self.organization())
+            .field("transport", &self.transport)
+            .field("retry_policy", &self.retry_policy)
+            .field("rate_limiter", &self.rate_limiter)
+            .finish_non_exhaustive()
+    }
 }
 
 impl FleetbaseClient {
     pub fn new(token: String) -> Self {
-        Self::new_with_base_url(token, BASE_URL.to_string())
+        Self::builder(token)
+            .build()
+            .expect("Failed to create HTTP client")
     }
 
+    /// `base_url` is used as-is, without appending an API version.
     pub fn new_with_base_url(token: String, base_url: String) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
+        Self::builder(token)
+            .base_url(base_url)
+            .no_api_version()
             .build()
-            .expect("Failed to create HTTP client");
+            .expect("Failed to create HTTP client")
+    }
+
+    pub fn builder(token: impl Into<String>) -> FleetbaseClientBuilder {
+        FleetbaseClientBuilder::new(token)
+    }
+
+    /// Reads `FLEETBASE_API_KEY` (required), `FLEETBASE_API_URL` and
+    /// `FLEETBASE_API_VERSION` (both optional).
+    pub fn from_env() -> Result<Self, FleetbaseError> {
+        let token = env::var("FLEETBASE_API_KEY")
+            .map_err(|_| FleetbaseError::Validation("FLEETBASE_API_KEY must be set".to_string()))?;
+        let mut builder = Self::builder(token);
+        if let Ok(base_url) = env::var("FLEETBASE_API_URL") {
+            builder = builder.base_url(base_url);
+        }
+        if let Ok(version) = env::var("FLEETBASE_API_VERSION") {
+            builder = builder.api_version(version);
+        }
+        builder.build()
+    }
+
+    /// A copy of this client that authenticates with `token` instead, e.g.
+    /// a driver's session token. Transport, retries and rate limiting are
+    /// shared with the original; the organization context is not.
+    pub fn with_token(&self, token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            organization: Arc::default(),
+            ..self.clone()
+        }
+    }
+
+    /// A handle pinned to `organization_id`, authenticating with the token
+    /// registered for it; switching the handle does not affect this client.
+    /// Fails unless a token is registered for `organization_id` or it is
+    /// already current, since this client's own token would act in another
+    /// organization.
+    pub fn in_organization(
+        &self,
+        organization_id: impl Into<String>,
+    ) -> Result<Self, FleetbaseError> {
+        let organization_id = organization_id.into();
+        let mut tokens = self.organization.read().unwrap().tokens.clone();
+        if !tokens.contains_key(&organization_id) {
+            if self.organization().as_deref() != Some(organization_id.as_str()) {
+                return Err(FleetbaseError::Validation(format!(
+                    "no API key is registered for organization {}",
+                    organization_id
+                )));
+            }
+            tokens.insert(organization_id.clone(), self.current_token());
+        }
+        Ok(Self {
+            organization: Arc::new(RwLock::new(OrganizationContext {
+                current: Some(organization_id),
+                tokens,
+            })),
+            ..self.clone()
+        })
+    }
+
+    pub fn organization(&self) -> Option<String> {
+        self.organization.read().unwrap().current.clone()
+    }
+
+    /// Makes `organization_id` current for this client and every clone
+    /// sharing its context. A `token` replaces the one held for that
+    /// organization, e.g. the refreshed token from a driver switch.
+    pub fn set_organization(&self, organization_id: impl Into<String>, token: Option<String>) {
+        let organization_id = organization_id.into();
+        let mut context = self.organization.write().unwrap();
+        if let Some(token) = token {
+            context.tokens.insert(organization_id.clone(), token);
+        }
+        context.current = Some(organization_id);
+    }
+
+    /// The bearer token requests are currently sent with: the current
+    /// organization's, or the client's own when none is registered, as for
+    /// a driver whose token works in each of their organizations.
+    pub(crate) fn current_token(&self) -> String {
+        let context = self.organization.read().unwrap();
+        context
+            .current
+            .as_ref()
+            .and_then(|id| context.tokens.get(id))
+            .unwrap_or(&self.token)
+            .clone()
+    }
 
-        FleetbaseClient { client, token, base_url }
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn transport(&self) -> &Arc<dyn HttpTransport> {
+        &self.transport
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    pub fn rate_limiter(&self) -> Option<&Arc<RateLimiter>> {
+        self.rate_limiter.as_ref()
     }
 
     pub async fn request<T, U>(
@@ -30,33 +190,177 @@ impl FleetbaseClient {
         method: Method,
         endpoint: Endpoint,
         body: Option<&T>,
-    ) -> Result<U, Error>
+    ) -> Result<U, FleetbaseError>
     where
         T: Serialize + ?Sized,
         U: DeserializeOwned,
     {
-        let url = format!("{}{}", BASE_URL, endpoint.to_string());
-        let mut request_builder = self
-            .client
-            .request(method, &url)
-            .header("Authorization", format!("Bearer {}", self.token));
+        self.send(method, endpoint, None, body, &RequestOptions::default())
+            .await
+    }
 
+    pub async fn request_with_query<T, U>(
+        &self,
+        method: Method,
+        endpoint: Endpoint,
+        query: &HashMap<String, String>,
+        body: Option<&T>,
+    ) -> Result<U, FleetbaseError>
+    where
+        T: Serialize + ?Sized,
+        U: DeserializeOwned,
+    {
+        self.send(
+            method,
+            endpoint,
+            Some(query),
+            body,
+            &RequestOptions::default(),
+        )
+        .await
+    }
+
+    /// Like `request`, but attaches an `Idempotency-Key` header so that
+    /// non-idempotent methods such as POST may be replayed on failure.
+    pub async fn request_idempotent<T, U>(
+        &self,
+        method: Method,
+        endpoint: Endpoint,
+        body: Option<&T>,
+        idempotency_key: &str,
+    ) -> Result<U, FleetbaseError>
+    where
+        T: Serialize + ?Sized,
+        U: DeserializeOwned,
+    {
+        let options = RequestOptions::new().idempotency_key(idempotency_key);
+        self.send(method, endpoint, None, body, &options).await
+    }
+
+    /// Applies per-request `options` on top of the client configuration.
+    pub async fn request_with_options<T, U>(
+        &self,
+        method: Method,
+        endpoint: Endpoint,
+        query: Option<&HashMap<String, String>>,
+        body: Option<&T>,
+        options: &RequestOptions,
+    ) -> Result<U, FleetbaseError>
+    where
+        T: Serialize + ?Sized,
+        U: DeserializeOwned,
+    {
+        self.send(method, endpoint, query, body, options).await
+    }
+
+    async fn send<T, U>(
+        &self,
+        method: Method,
+        endpoint: Endpoint,
+        query: Option<&HashMap<String, String>>,
+        body: Option<&T>,
+        options: &RequestOptions,
+    ) -> Result<U, FleetbaseError>
+    where
+        T: Serialize + ?Sized,
+        U: DeserializeOwned,
+    {
+        let url = match query {
+            Some(query) => format!("{}/{}", self.base_url, endpoint.with_query(query)),
+            None => format!("{}/{}", self.base_url, endpoint),
+        };
+        let mut request = HttpRequest::new(method.clone(), url);
+        request.headers = self.default_headers.clone();
+        request.headers.insert(
+            AUTHORIZATION,
+            header_value(&format!("Bearer {}", self.current_token()))?,
+        );
+
+        request.timeout = options.timeout;
+        for (name, value) in &options.headers {
+            let (name, value) = parse_header(name, value)?;
+            request.headers.insert(name, value);
+        }
+        if let Some(key) = &options.idempotency_key {
+            request
+                .headers
+                .insert(IDEMPOTENCY_KEY_HEADER, header_value(key)?);
+        }
         if let Some(body) = body {
-            request_builder = request_builder.json(body);
+            let body = serde_json::to_vec(body).map_err(|e| {
+                FleetbaseError::Validation(format!("failed to encode request body: {}", e))
+            })?;
+            request
+                .headers
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            request.body = Some(body);
         }
 
-        let response = request_builder.send().await?;
-        response.json::<U>().await
+        let retryable = self
+            .retry_policy
+            .allows_method(&method, options.idempotency_key.is_some());
+        let mut attempt = 1;
+
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+
+            let result = self.transport.send(request.clone()).await;
+            if let (Some(limiter), Ok(response)) = (&self.rate_limiter, &result) {
+                limiter.update_from_headers(&response.headers);
+            }
+
+            let retry_after = match &result {
+                _ if !retryable || attempt >= self.retry_policy.max_attempts => None,
+                Ok(response) if self.retry_policy.retries_status(response.status) => {
+                    Some(parse_retry_after(&response.headers))
+                }
+                Err(error) if self.retry_policy.retries_error(error) => Some(None),
+                _ => None,
+            };
+            let Some(delay) =
+                retry_after.and_then(|after| self.retry_policy.delay_for(attempt, after))
+            else {
+                return decode_response(result?);
+            };
+
+            if let Some(hook) = &self.on_retry {
+                hook(&RetryEvent {
+                    attempt,
+                    method: &method,
+                    url: &request.url,
+                    status: result.as_ref().ok().map(|response| response.status),
+                    error: result.as_ref().err(),
+                    delay,
+                });
+            }
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
     }
 
-    pub async fn get<U>(&self, endpoint: Endpoint) -> Result<U, Error>
+    pub async fn get<U>(&self, endpoint: Endpoint) -> Result<U, FleetbaseError>
     where
         U: DeserializeOwned,
     {
         self.request::<(), U>(Method::GET, endpoint, None).await
     }
 
-    pub async fn post<T, U>(&self, endpoint: Endpoint, body: &T) -> Result<U, Error>
+    pub async fn get_with_query<U>(
+        &self,
+        endpoint: Endpoint,
+        query: &HashMap<String, String>,
+    ) -> Result<U, FleetbaseError>
+    where
+        U: DeserializeOwned,
+    {
+        self.request_with_query::<(), U>(Method::GET, endpoint, query, None)
+            .await
+    }
+
+    pub async fn post<T, U>(&self, endpoint: Endpoint, body: &T) -> Result<U, FleetbaseError>
     where
         T: Serialize + ?Sized,
         U: DeserializeOwned,
@@ -65,7 +369,7 @@ impl FleetbaseClient {
             .await
     }
 
-    pub async fn put<T, U>(&self, endpoint: Endpoint, body: &T) -> Result<U, Error>
+    pub async fn put<T, U>(&self, endpoint: Endpoint, body: &T) -> Result<U, FleetbaseError>
     where
         T: Serialize + ?Sized,
         U: DeserializeOwned,
@@ -74,19 +378,331 @@ impl FleetbaseClient {
             .await
     }
 
-    pub async fn delete<U>(&self, endpoint: Endpoint) -> Result<U, Error>
+    pub async fn delete<U>(&self, endpoint: Endpoint) -> Result<U, FleetbaseError>
     where
         U: DeserializeOwned,
     {
         self.request::<(), U>(Method::DELETE, endpoint, None).await
     }
+
+    /// Fetches a single page of a list endpoint.
+    pub async fn list<T>(
+        &self,
+        endpoint: Endpoint,
+        query: &ListQuery,
+    ) -> Result<Vec<T>, FleetbaseError>
+    where
+        T: DeserializeOwned,
+    {
+        let key = endpoint.resource_key();
+        let value: serde_json::Value = self.get_with_query(endpoint, &query.to_params()).await?;
+        Ok(Page::from_value(value, &key)?.items)
+    }
+
+    pub fn orders(&self) -> ResourceService<Order> {
+        ResourceService::new(self.clone())
+    }
+
+    pub fn drivers(&self) -> ResourceService<Driver> {
+        ResourceService::new(self.clone())
+    }
+
+    pub fn places(&self) -> ResourceService<Place> {
+        ResourceService::new(self.clone())
+    }
+
+    pub fn contacts(&self) -> ResourceService<Contact> {
+        ResourceService::new(self.clone())
+    }
+
+    pub fn vendors(&self) -> ResourceService<Vendor> {
+        ResourceService::new(self.clone())
+    }
+
+    pub fn vehicles(&self) -> ResourceService<Vehicle> {
+        ResourceService::new(self.clone())
+    }
+
+    pub fn entities(&self) -> ResourceService<Entity> {
+        ResourceService::new(self.clone())
+    }
+
+    pub fn service_rates(&self) -> ResourceService<ServiceRate> {
+        ResourceService::new(self.clone())
+    }
+
+    pub fn tracking_numbers(&self) -> ResourceService<TrackingNumber> {
+        ResourceService::new(self.clone())
+    }
+
+    pub fn tracking_statuses(&self) -> ResourceService<TrackingStatus> {
+        ResourceService::new(self.clone())
+    }
+
+    /// Lazily walks every page of a list endpoint such as
+    /// `Endpoint::Orders(Orders::Orders)`.
+    pub fn paginate<T>(&self, endpoint: Endpoint) -> Paginator<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        Paginator::new(self.clone(), endpoint)
+    }
 }
+
+pub struct FleetbaseClientBuilder {
+    token: String,
+    organization: Option<String>,
+    organization_tokens: HashMap<String, String>,
+    base_url: String,
+    api_version: Option<String>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    timeout: Duration,
+    user_agent: String,
+    headers: Vec<(String, String)>,
+    proxy: Option<String>,
+    root_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+    https_only: bool,
+    retry_policy: RetryPolicy,
+    on_retry: Option<RetryHook>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    transport: Option<Arc<dyn HttpTransport>>,
+}
+
+impl FleetbaseClientBuilder {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            organization: None,
+            organization_tokens: HashMap::new(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            api_version: Some(DEFAULT_API_VERSION.to_string()),
+            connect_timeout: None,
+            read_timeout: None,
+            timeout: Duration::from_secs(30),
+            user_agent: concat!("fleetbase-rs/", env!("CARGO_PKG_VERSION")).to_string(),
+            headers: Vec::new(),
+            proxy: None,
+            root_certificates: Vec::new(),
+            accept_invalid_certs: false,
+            https_only: false,
+            retry_policy: RetryPolicy::default(),
+            on_retry: None,
+            rate_limiter: None,
+            transport: None,
+        }
+    }
+
+    /// Starts the client in `organization_id`.
+    pub fn organization(mut self, organization_id: impl Into<String>) -> Self {
+        self.organization = Some(organization_id.into());
+        self
+    }
+
+    /// Registers the API key to use while `organization_id` is current.
+    pub fn organization_token(
+        mut self,
+        organization_id: impl Into<String>,
+        token: impl Into<String>,
+    ) -> Self {
+        self.organization_tokens
+            .insert(organization_id.into(), token.into());
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Path prefix inserted between the base URL and the endpoint, e.g. `v1`.
+    pub fn api_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = Some(version.into());
+        self
+    }
+
+    /// For servers that mount the API at the root, such as a local mock.
+    pub fn no_api_version(mut self) -> Self {
+        self.api_version = None;
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Longest wait for the next chunk of a response. Unlike `timeout`, a
+    /// slow but steady download is not cut off.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Total time allowed for a request, including reading the response body.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Adds a header sent with every request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Trusts an additional PEM-encoded root certificate.
+    pub fn add_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    pub fn https_only(mut self, enabled: bool) -> Self {
+        self.https_only = enabled;
+        self
+    }
+
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Called before sleeping ahead of each retry, e.g. for logging.
+    pub fn on_retry<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RetryEvent<'_>) + Send + Sync + 'static,
+    {
+        self.on_retry = Some(Arc::new(hook));
+        self
+    }
+
+    /// Opt-in client-side throttling; clones of the built client share the bucket.
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(requests_per_second, burst)));
+        self
+    }
+
+    /// Shares an existing limiter, e.g. between clients using the same API key.
+    pub fn rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Sends requests through `transport` instead of reqwest, e.g. a
+    /// `MemoryTransport` in tests. Timeouts, proxy and TLS settings only
+    /// apply to the default transport.
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    pub fn build(self) -> Result<FleetbaseClient, FleetbaseError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let (name, value) = parse_header(name, value)?;
+            headers.insert(name, value);
+        }
+        if !headers.contains_key(USER_AGENT) {
+            headers.insert(USER_AGENT, header_value(&self.user_agent)?);
+        }
+
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => {
+                let mut builder = Client::builder()
+                    .timeout(self.timeout)
+                    .danger_accept_invalid_certs(self.accept_invalid_certs)
+                    .https_only(self.https_only);
+
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = self.read_timeout {
+                    builder = builder.read_timeout(timeout);
+                }
+                if let Some(proxy) = &self.proxy {
+                    builder = builder.proxy(Proxy::all(proxy)?);
+                }
+                for pem in &self.root_certificates {
+                    builder = builder.add_root_certificate(Certificate::from_pem(pem)?);
+                }
+                Arc::new(ReqwestTransport::new(builder.build()?))
+            }
+        };
+
+        let base_url = self.base_url.trim_end_matches('/');
+        let base_url = match &self.api_version {
+            Some(version) => format!("{}/{}", base_url, version.trim_matches('/')),
+            None => base_url.to_string(),
+        };
+
+        Ok(FleetbaseClient {
+            transport,
+            token: self.token,
+            organization: Arc::new(RwLock::new(OrganizationContext {
+                current: self.organization,
+                tokens: self.organization_tokens,
+            })),
+            base_url,
+            default_headers: headers,
+            retry_policy: self.retry_policy,
+            on_retry: self.on_retry,
+            rate_limiter: self.rate_limiter,
+        })
+    }
+}
+
+fn parse_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue), FleetbaseError> {
+    let header = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+        FleetbaseError::Validation(format!("invalid header name {:?}: {}", name, e))
+    })?;
+    let value = HeaderValue::from_str(value).map_err(|e| {
+        FleetbaseError::Validation(format!("invalid header value for {}: {}", header, e))
+    })?;
+    Ok((header, value))
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, FleetbaseError> {
+    HeaderValue::from_str(value)
+        .map_err(|e| FleetbaseError::Validation(format!("invalid header value: {}", e)))
+}
+
+/// Checks the status before decoding so that error bodies surface as
+/// `FleetbaseError::Api`/`Http` instead of a confusing deserialization failure.
+pub(crate) fn decode_response<U>(response: HttpResponse) -> Result<U, FleetbaseError>
+where
+    U: DeserializeOwned,
+{
+    let HttpResponse { status, body, .. } = response;
+
+    if !status.is_success() {
+        return Err(FleetbaseError::from_response(status, body));
+    }
+
+    serde_json::from_str(&body).map_err(|source| FleetbaseError::Deserialize { source, body })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mockito::{mock, server_url};
+    use crate::utils::enpdpoints::{Orders, Places, ServiceQuotes};
     use serde::{Deserialize, Serialize};
-    use serde_json::json;
 
     #[derive(Debug, Serialize, Deserialize, PartialEq)]
     struct TestResponse {
@@ -98,100 +714,367 @@ mod tests {
         data: String,
     }
 
-    fn create_client() -> FleetbaseClient {
-        FleetbaseClient::new("test_token".to_string())
+    fn create_client(server: &mockito::Server) -> FleetbaseClient {
+        FleetbaseClient::new_with_base_url("test_token".to_string(), server.url())
     }
 
     #[tokio::test]
     async fn test_get_request() {
-        let mut server = mockito::Server::new();
-        let client = create_client();
+        let mut server = mockito::Server::new_async().await;
+        let client = create_client(&server);
 
-        let mock = server.mock("GET", "/service-quotes")
+        let mock = server
+            .mock("GET", "/service-quotes")
             .match_header("Authorization", "Bearer test_token")
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(r#"{"message": "Success"}"#)
-            .create();
+            .create_async()
+            .await;
 
-        let response: TestResponse = client.get(Endpoint::ServiceQuotes(ServiceQuotes::ServiceQuotes)).await.unwrap();
+        let response: TestResponse = client
+            .get(Endpoint::ServiceQuotes(ServiceQuotes::ServiceQuotes))
+            .await
+            .unwrap();
 
-        mock.assert();
-        assert_eq!(response, TestResponse { message: "Success".to_string() });
+        mock.assert_async().await;
+        assert_eq!(
+            response,
+            TestResponse {
+                message: "Success".to_string()
+            }
+        );
     }
 
     #[tokio::test]
     async fn test_post_request() {
-        let mut server = mockito::Server::new();
-        let client = create_client();
+        let mut server = mockito::Server::new_async().await;
+        let client = create_client(&server);
 
-        let request_body = TestRequest { data: "test".to_string() };
-        let mock = server.mock("POST", "/orders")
+        let request_body = TestRequest {
+            data: "test".to_string(),
+        };
+        let mock = server
+            .mock("POST", "/orders")
             .match_header("Authorization", "Bearer test_token")
             .match_body(r#"{"data":"test"}"#)
             .with_status(201)
             .with_header("content-type", "application/json")
             .with_body(r#"{"message": "Created"}"#)
-            .create();
+            .create_async()
+            .await;
 
-        let response: TestResponse = client.post(Endpoint::Orders(Orders::Orders), &request_body).await.unwrap();
+        let response: TestResponse = client
+            .post(Endpoint::Orders(Orders::Orders), &request_body)
+            .await
+            .unwrap();
 
-        mock.assert();
-        assert_eq!(response, TestResponse { message: "Created".to_string() });
+        mock.assert_async().await;
+        assert_eq!(
+            response,
+            TestResponse {
+                message: "Created".to_string()
+            }
+        );
     }
 
     #[tokio::test]
     async fn test_put_request() {
-        let mut server = mockito::Server::new();
-        let client = create_client();
+        let mut server = mockito::Server::new_async().await;
+        let client = create_client(&server);
 
-        let request_body = TestRequest { data: "updated".to_string() };
-        let mock = server.mock("PUT", "/orders/123")
+        let request_body = TestRequest {
+            data: "updated".to_string(),
+        };
+        let mock = server
+            .mock("PUT", "/orders/123")
             .match_header("Authorization", "Bearer test_token")
             .match_body(r#"{"data":"updated"}"#)
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(r#"{"message": "Updated"}"#)
-            .create();
+            .create_async()
+            .await;
 
-        let response: TestResponse = client.put(Endpoint::Orders(Orders::OrdersById("123".to_string())), &request_body).await.unwrap();
+        let response: TestResponse = client
+            .put(
+                Endpoint::Orders(Orders::OrdersById("123".to_string())),
+                &request_body,
+            )
+            .await
+            .unwrap();
 
-        mock.assert();
-        assert_eq!(response, TestResponse { message: "Updated".to_string() });
+        mock.assert_async().await;
+        assert_eq!(
+            response,
+            TestResponse {
+                message: "Updated".to_string()
+            }
+        );
     }
 
     #[tokio::test]
     async fn test_delete_request() {
-        let mut server = mockito::Server::new();
-        let client = create_client();
+        let mut server = mockito::Server::new_async().await;
+        let client = create_client(&server);
 
-        let mock = server.mock("DELETE", "/orders/123")
+        let mock = server
+            .mock("DELETE", "/orders/123")
             .match_header("Authorization", "Bearer test_token")
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(r#"{"message": "Deleted"}"#)
-            .create();
+            .create_async()
+            .await;
 
-        let response: TestResponse = client.delete(Endpoint::Orders(Orders::OrdersById("123".to_string()))).await.unwrap();
+        let response: TestResponse = client
+            .delete(Endpoint::Orders(Orders::OrdersById("123".to_string())))
+            .await
+            .unwrap();
 
-        mock.assert();
-        assert_eq!(response, TestResponse { message: "Deleted".to_string() });
+        mock.assert_async().await;
+        assert_eq!(
+            response,
+            TestResponse {
+                message: "Deleted".to_string()
+            }
+        );
     }
 
     #[tokio::test]
     async fn test_error_handling() {
-        let mut server = mockito::Server::new();
-        let client = create_client();
+        let mut server = mockito::Server::new_async().await;
+        let client = create_client(&server);
 
-        let mock = server.mock("GET", "/non-existent")
+        let mock = server
+            .mock("GET", "/places/non-existent")
             .with_status(404)
             .with_header("content-type", "application/json")
             .with_body(r#"{"error": "Not Found"}"#)
-            .create();
+            .create_async()
+            .await;
+
+        let result: Result<TestResponse, FleetbaseError> = client
+            .get(Endpoint::Places(Places::PlacesById(
+                "non-existent".to_string(),
+            )))
+            .await;
+
+        mock.assert_async().await;
+        assert!(result.unwrap_err().is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport_records_and_retries() {
+        use crate::transport::MemoryTransport;
+        use reqwest::StatusCode;
+
+        let transport = Arc::new(MemoryTransport::new());
+        transport
+            .push(HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""))
+            .push(HttpResponse::json(
+                StatusCode::OK,
+                serde_json::json!({"message": "Success"}),
+            ));
+        let client = FleetbaseClient::builder("test_token")
+            .retry_policy(RetryPolicy::default().base_delay(Duration::ZERO))
+            .header("X-Tenant", "acme")
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let response: TestResponse = client
+            .put(
+                Endpoint::Orders(Orders::OrdersById("123".to_string())),
+                &TestRequest {
+                    data: "updated".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.message, "Success");
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        let request = &requests[1];
+        assert_eq!(request.method, Method::PUT);
+        assert_eq!(request.path(), "/v1/orders/123");
+        assert_eq!(request.header("authorization"), Some("Bearer test_token"));
+        assert_eq!(request.header("x-tenant"), Some("acme"));
+        assert_eq!(request.json().unwrap()["data"], "updated");
+    }
+
+    #[tokio::test]
+    async fn test_request_options_are_applied() {
+        use crate::transport::MemoryTransport;
+        use reqwest::StatusCode;
+
+        let transport = Arc::new(MemoryTransport::new());
+        transport
+            .push(HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""))
+            .push(HttpResponse::json(
+                StatusCode::OK,
+                serde_json::json!({"message": "Dispatched"}),
+            ));
+        let client = FleetbaseClient::builder("test_token")
+            .retry_policy(RetryPolicy::default().base_delay(Duration::ZERO))
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let options = RequestOptions::new()
+            .timeout(Duration::from_secs(5))
+            .idempotency_key("dispatch-123")
+            .header("X-Request-Source", "dispatcher");
+        let response: TestResponse = client
+            .request_with_options(
+                Method::POST,
+                Endpoint::Orders(Orders::OrdersDispatch("123".to_string())),
+                None,
+                Some(&serde_json::json!({"driver": "driver_1"})),
+                &options,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.message, "Dispatched");
+
+        // The idempotency key makes the POST safe to replay after the 503.
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        let request = &requests[1];
+        assert_eq!(request.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(request.header("idempotency-key"), Some("dispatch-123"));
+        assert_eq!(request.header("x-request-source"), Some("dispatcher"));
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_stops_a_stalled_response() {
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpListener;
+
+        // Sends the headers and part of the body, then goes quiet.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 64\r\n\r\n{\"message\"")
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        });
+
+        let client = FleetbaseClient::builder("key")
+            .base_url(format!("http://{}", address))
+            .read_timeout(Duration::from_millis(200))
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+        let started = std::time::Instant::now();
+        let error = client
+            .get::<TestResponse>(Endpoint::Orders(Orders::OrdersById("123".to_string())))
+            .await
+            .unwrap_err();
+        assert!(error.is_timeout(), "{:?}", error);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_honoured() {
+        use crate::transport::MemoryTransport;
+        use reqwest::StatusCode;
+        use std::sync::Mutex;
+
+        let transport = Arc::new(MemoryTransport::new());
+        transport
+            .push(
+                HttpResponse::new(StatusCode::TOO_MANY_REQUESTS, "")
+                    .with_header("retry-after", "1"),
+            )
+            .push(HttpResponse::json(
+                StatusCode::OK,
+                serde_json::json!({"message": "ok"}),
+            ))
+            .push(
+                HttpResponse::new(StatusCode::TOO_MANY_REQUESTS, "")
+                    .with_header("retry-after", "60"),
+            );
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let client = FleetbaseClient::builder("test_token")
+            .retry_policy(RetryPolicy::default().base_delay(Duration::ZERO))
+            .on_retry(move |event| {
+                seen.lock()
+                    .unwrap()
+                    .push((event.attempt, event.status, event.delay))
+            })
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        let endpoint = || Endpoint::Orders(Orders::OrdersById("123".to_string()));
+
+        let response: TestResponse = client.get(endpoint()).await.unwrap();
+        assert_eq!(response.message, "ok");
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![(
+                1,
+                Some(StatusCode::TOO_MANY_REQUESTS),
+                Duration::from_secs(1)
+            )]
+        );
+
+        // Longer than `max_delay`: the 429 is returned rather than retried early.
+        let error = client.get::<TestResponse>(endpoint()).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        assert_eq!(transport.requests().len(), 3);
+        assert_eq!(events.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_builder_joins_base_url_and_version() {
+        let client = FleetbaseClient::builder("key")
+            .base_url("http://localhost:8000/")
+            .api_version("/v1/")
+            .header("X-Tenant", "acme")
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url(), "http://localhost:8000/v1");
+
+        let client = FleetbaseClient::builder("key").build().unwrap();
+        assert_eq!(client.base_url(), "https://api.fleetbase.io/v1");
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_header() {
+        let result = FleetbaseClient::builder("key")
+            .header("bad header", "value")
+            .build();
+        assert!(matches!(result, Err(FleetbaseError::Validation(_))));
+    }
+
+    #[test]
+    fn test_organization_context() {
+        let client = FleetbaseClient::builder("key_a")
+            .organization("org_a")
+            .organization_token("org_b", "key_b")
+            .build()
+            .unwrap();
+        assert_eq!(client.current_token(), "key_a");
+
+        assert!(client.in_organization("org_c").is_err());
+        let same = client.in_organization("org_a").unwrap();
+        assert_eq!(same.current_token(), "key_a");
 
-        let result: Result<TestResponse, _> = client.get(Endpoint::Places(Places::PlacesById("non-existent".to_string()))).await;
+        let scoped = client.in_organization("org_b").unwrap();
+        assert_eq!(scoped.organization().as_deref(), Some("org_b"));
+        assert_eq!(scoped.current_token(), "key_b");
+        assert_eq!(client.organization().as_deref(), Some("org_a"));
 
-        mock.assert();
-        assert!(result.is_err());
+        let shared = client.clone();
+        shared.set_organization("org_c", Some("key_c".to_string()));
+        assert_eq!(client.organization().as_deref(), Some("org_c"));
+        assert_eq!(client.current_token(), "key_c");
+        assert_eq!(scoped.current_token(), "key_b");
     }
 }
@@ -10,6 +10,7 @@ pub struct Contact {
     pub phone_number: String,
     pub slug: String,
     pub title: String,
+    #[serde(rename = "type")]
     pub type_: String, // Renamed to avoid conflict with Rust's `type` keyword
     pub updated_at: String,
 }
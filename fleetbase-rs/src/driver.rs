@@ -1,21 +1,22 @@
 use async_trait::async_trait;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::client::FleetbaseClient;
+use crate::error::FleetbaseError;
+use crate::geo::Point;
 use crate::organization::Organization;
 use crate::resource::Resource;
+use crate::service::ApiResource;
+use crate::session::StoredSession;
+use crate::tracker::GpsFix;
+use crate::utils::enpdpoints::{Drivers, Endpoint};
 use crate::utils::{is_phone, StoreActions};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Point {
-    coordinates: Vec<f64>,
-}
-
 pub fn serialize_organizations(
     response: serde_json::Value,
-    adapter: &Client,
-) -> Result<Vec<Organization>, Box<dyn std::error::Error>> {
+    adapter: &FleetbaseClient,
+) -> Result<Vec<Organization>, FleetbaseError> {
     if response.is_array() {
         response
             .as_array()
@@ -29,7 +30,40 @@ pub fn serialize_organizations(
 }
 
 pub struct DriverActions {
-    adapter: Client,
+    adapter: FleetbaseClient,
+}
+
+impl DriverActions {
+    pub fn new(adapter: FleetbaseClient) -> Self {
+        Self { adapter }
+    }
+
+    async fn get(
+        &self,
+        endpoint: Drivers,
+        params: &HashMap<String, String>,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.adapter
+            .get_with_query(Endpoint::Drivers(endpoint), params)
+            .await
+    }
+
+    async fn post<T: Serialize + ?Sized>(
+        &self,
+        endpoint: Drivers,
+        body: &T,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.adapter.post(Endpoint::Drivers(endpoint), body).await
+    }
+
+    /// Posts a single GPS fix to `drivers/{id}/track`.
+    pub async fn track_fix(
+        &self,
+        id: &str,
+        fix: &GpsFix,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.post(Drivers::DriversTrack(id.to_string()), fix).await
+    }
 }
 
 #[async_trait]
@@ -39,30 +73,22 @@ impl StoreActions for DriverActions {
         identity: &str,
         password: Option<&str>,
         attributes: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    ) -> Result<serde_json::Value, FleetbaseError> {
         if is_phone(identity) {
-            self.adapter
-                .post("drivers/login-with-sms")
-                .json(&serde_json::json!({"phone": identity}))
-                .send()
-                .await?
-                .json()
-                .await
-                .map_err(Into::into)
+            self.post(
+                Drivers::DriversLoginWithSms,
+                &serde_json::json!({"phone": identity}),
+            )
+            .await
         } else if let Some(pwd) = password {
             let mut payload = attributes;
             payload.insert("identity".to_string(), identity.to_string());
             payload.insert("password".to_string(), pwd.to_string());
-            self.adapter
-                .post("drivers/login")
-                .json(&payload)
-                .send()
-                .await?
-                .json()
-                .await
-                .map_err(Into::into)
+            self.post(Drivers::DriversLogin, &payload).await
         } else {
-            Err("Login requires password!".into())
+            Err(FleetbaseError::Validation(
+                "Login requires password!".to_string(),
+            ))
         }
     }
 
@@ -71,49 +97,31 @@ impl StoreActions for DriverActions {
         identity: &str,
         code: &str,
         attributes: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    ) -> Result<serde_json::Value, FleetbaseError> {
         let mut payload = attributes;
         payload.insert("identity".to_string(), identity.to_string());
         payload.insert("code".to_string(), code.to_string());
-        self.adapter
-            .post("drivers/verify-code")
-            .json(&payload)
-            .send()
-            .await?
-            .json()
-            .await
-            .map_err(Into::into)
+        self.post(Drivers::DriversVerifyCode, &payload).await
     }
 
     async fn track(
         &self,
         id: &str,
         params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        self.adapter
-            .post(&format!("drivers/{}/track", id))
-            .json(&params)
-            .send()
-            .await?
-            .json()
+        _options: HashMap<String, String>,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.post(Drivers::DriversTrack(id.to_string()), &params)
             .await
-            .map_err(Into::into)
     }
 
     async fn list_organizations(
         &self,
         id: &str,
         params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<Vec<Organization>, Box<dyn std::error::Error>> {
+        _options: HashMap<String, String>,
+    ) -> Result<Vec<Organization>, FleetbaseError> {
         let response = self
-            .adapter
-            .get(&format!("drivers/{}/organizations", id))
-            .query(&params)
-            .send()
-            .await?
-            .json()
+            .get(Drivers::DriversOrganizations(id.to_string()), &params)
             .await?;
         serialize_organizations(response, &self.adapter)
     }
@@ -122,103 +130,133 @@ impl StoreActions for DriverActions {
         &self,
         id: &str,
         params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<Organization, Box<dyn std::error::Error>> {
-        let response = self
-            .adapter
-            .post(&format!("drivers/{}/switch-organization", id))
-            .json(&params)
-            .send()
-            .await?
-            .json()
+        _options: HashMap<String, String>,
+    ) -> Result<Organization, FleetbaseError> {
+        let mut response = self
+            .post(Drivers::DriversSwitchOrganization(id.to_string()), &params)
             .await?;
-        Ok(Organization::new(response, self.adapter.clone())?)
+        // A refreshed token for the new organization may come back alongside it.
+        let token = response
+            .as_object_mut()
+            .and_then(|org| org.remove("token"))
+            .and_then(|token| token.as_str().map(str::to_string));
+        let organization = Organization::new(response, self.adapter.clone())?;
+        self.adapter.set_organization(organization.id(), token);
+        Ok(organization)
     }
 
     async fn current_organization(
         &self,
         id: &str,
         params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<Organization, Box<dyn std::error::Error>> {
+        _options: HashMap<String, String>,
+    ) -> Result<Organization, FleetbaseError> {
         let response = self
-            .adapter
-            .get(&format!("drivers/{}/current-organization", id))
-            .query(&params)
-            .send()
-            .await?
-            .json()
+            .get(Drivers::DriversCurrentOrganization(id.to_string()), &params)
             .await?;
-        Ok(Organization::new(response, self.adapter.clone())?)
+        let organization = Organization::new(response, self.adapter.clone())?;
+        if self.adapter.organization().is_none() {
+            self.adapter.set_organization(organization.id(), None);
+        }
+        Ok(organization)
     }
 
-    async fn retrieve(&self, id: &str) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    async fn retrieve(&self, id: &str) -> Result<serde_json::Value, FleetbaseError> {
         self.adapter
-            .get(&format!("drivers/{}", id))
-            .send()
-            .await?
-            .json()
+            .get(Endpoint::Drivers(Drivers::DriversById(id.to_string())))
             .await
-            .map_err(Into::into)
     }
 
     async fn sync_device(
         &self,
         id: &str,
         params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        self.adapter
-            .post(&format!("drivers/{}/register-device", id))
-            .json(&params)
-            .send()
-            .await?
-            .json()
+        _options: HashMap<String, String>,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.post(Drivers::DriversRegisterDevice(id.to_string()), &params)
             .await
-            .map_err(Into::into)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Driver {
     #[serde(flatten)]
     resource: Resource,
+    /// A bearer credential, so it is never written out.
+    #[serde(skip_serializing)]
     token: Option<String>,
     online: Option<bool>,
     location: Option<Point>,
+    #[serde(skip)]
+    session: Option<StoredSession>,
 }
 
 impl Driver {
+    /// Moves `token`, `online` and `location` out of `attributes` when
+    /// present, as in a login or verify-code response.
     pub fn new(
-        attributes: serde_json::Value,
-        adapter: Client,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+        mut attributes: serde_json::Value,
+        adapter: FleetbaseClient,
+    ) -> Result<Self, FleetbaseError> {
+        let mut take = |key: &str| {
+            attributes
+                .as_object_mut()
+                .and_then(|object| object.remove(key))
+        };
+        let token = take("token")
+            .as_ref()
+            .and_then(|t| t.as_str())
+            .map(str::to_string);
+        let online = take("online").and_then(|o| o.as_bool());
+        let location = take("location").and_then(|l| serde_json::from_value(l).ok());
         Ok(Self {
-            resource: Resource::new(attributes, adapter.clone(), "driver")?,
-            token: None,
-            online: None,
-            location: None,
+            resource: Resource::new(attributes, adapter, "driver")?,
+            token,
+            online,
+            location,
+            session: None,
         })
     }
 
+    pub fn id(&self) -> &str {
+        &self.resource.id
+    }
+
+    fn actions(&self) -> Result<DriverActions, FleetbaseError> {
+        Ok(DriverActions::new(self.resource.client()?.clone()))
+    }
+
     pub fn token(&self) -> Option<&str> {
         self.token.as_deref()
     }
 
+    pub(crate) fn set_token(&mut self, token: impl Into<String>) {
+        self.token = Some(token.into());
+    }
+
+    /// Keeps `session` up to date when the driver's token changes.
+    pub(crate) fn set_session(&mut self, session: StoredSession) {
+        self.session = Some(session);
+    }
+
     pub fn is_online(&self) -> bool {
         self.online.unwrap_or(false)
     }
 
+    pub fn location(&self) -> Option<&Point> {
+        self.location.as_ref()
+    }
+
+    pub(crate) fn set_location(&mut self, location: Point) {
+        self.location = Some(location);
+    }
+
     pub fn latitude(&self) -> Option<f64> {
-        self.location
-            .as_ref()
-            .and_then(|loc| loc.coordinates.get(1).cloned())
+        self.location.map(|loc| loc.latitude())
     }
 
     pub fn longitude(&self) -> Option<f64> {
-        self.location
-            .as_ref()
-            .and_then(|loc| loc.coordinates.get(0).cloned())
+        self.location.map(|loc| loc.longitude())
     }
 
     pub fn coordinates(&self) -> Option<(f64, f64)> {
@@ -229,61 +267,117 @@ impl Driver {
         &self,
         params: HashMap<String, String>,
         options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        DriverActions {
-            adapter: self.resource.adapter.clone(),
-        }
-        .track(&self.resource.id, params, options)
-        .await
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.actions()?
+            .track(&self.resource.id, params, options)
+            .await
+    }
+
+    /// Sends `fix` and moves `location` to where Fleetbase now has the
+    /// driver, falling back to the fix itself.
+    pub async fn track_fix(&mut self, fix: &GpsFix) -> Result<(), FleetbaseError> {
+        let response = self.actions()?.track_fix(&self.resource.id, fix).await?;
+        self.location = response
+            .get("location")
+            .and_then(|l| serde_json::from_value(l.clone()).ok())
+            .or(Point::from_lat_lng(fix.latitude, fix.longitude).ok());
+        Ok(())
     }
 
     pub async fn sync_device(
         &self,
         params: HashMap<String, String>,
         options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        DriverActions {
-            adapter: self.resource.adapter.clone(),
-        }
-        .sync_device(&self.resource.id, params, options)
-        .await
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.actions()?
+            .sync_device(&self.resource.id, params, options)
+            .await
     }
 
     pub async fn list_organizations(
         &self,
         params: HashMap<String, String>,
         options: HashMap<String, String>,
-    ) -> Result<Vec<Organization>, Box<dyn std::error::Error>> {
-        DriverActions {
-            adapter: self.resource.adapter.clone(),
-        }
-        .list_organizations(&self.resource.id, params, options)
-        .await
+    ) -> Result<Vec<Organization>, FleetbaseError> {
+        self.actions()?
+            .list_organizations(&self.resource.id, params, options)
+            .await
     }
 
+    /// Switches the driver's active organization. Every client sharing this
+    /// driver's context follows, using the refreshed token if one is issued.
+    /// A driver from a `DriverSession` also saves that token and
+    /// organization to its store.
     pub async fn switch_organization(
-        &self,
+        &mut self,
         organization_id: &str,
         options: HashMap<String, String>,
-    ) -> Result<Organization, Box<dyn std::error::Error>> {
+    ) -> Result<Organization, FleetbaseError> {
         let mut params = HashMap::new();
         params.insert("next".to_string(), organization_id.to_string());
-        DriverActions {
-            adapter: self.resource.adapter.clone(),
+        let organization = self
+            .actions()?
+            .switch_organization(&self.resource.id, params, options)
+            .await?;
+        let client = self.resource.client()?;
+        let token = client.current_token();
+        if let Some(session) = &self.session {
+            session.save(&self.resource.id, &token, client.organization())?;
         }
-        .switch_organization(&self.resource.id, params, options)
-        .await
+        self.token = Some(token);
+        Ok(organization)
     }
 
     pub async fn current_organization(
         &self,
         params: HashMap<String, String>,
         options: HashMap<String, String>,
-    ) -> Result<Organization, Box<dyn std::error::Error>> {
-        DriverActions {
-            adapter: self.resource.adapter.clone(),
-        }
-        .current_organization(&self.resource.id, params, options)
-        .await
+    ) -> Result<Organization, FleetbaseError> {
+        self.actions()?
+            .current_organization(&self.resource.id, params, options)
+            .await
+    }
+}
+
+impl ApiResource for Driver {
+    fn collection() -> Endpoint {
+        Endpoint::Drivers(Drivers::Drivers)
+    }
+
+    fn member(id: &str) -> Endpoint {
+        Endpoint::Drivers(Drivers::DriversById(id.to_string()))
+    }
+
+    fn attach(&mut self, client: &FleetbaseClient) {
+        self.resource.adapter = Some(client.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_serializing_leaves_out_the_token() {
+        let driver = Driver::new(
+            json!({
+                "id": "driver_1",
+                "name": "Ali",
+                "token": "secret",
+                "online": true,
+                "location": { "type": "Point", "coordinates": [103.8198, 1.3521] }
+            }),
+            FleetbaseClient::new("test_token".to_string()),
+        )
+        .unwrap();
+        assert_eq!(driver.token(), Some("secret"));
+        assert!(driver.is_online());
+        assert_eq!(driver.coordinates(), Some((1.3521, 103.8198)));
+
+        let serialized = serde_json::to_string(&driver).unwrap();
+        assert!(!serialized.contains("secret"), "{}", serialized);
+        assert_eq!(serialized.matches("\"online\"").count(), 1);
+        assert_eq!(serialized.matches("\"location\"").count(), 1);
     }
 }
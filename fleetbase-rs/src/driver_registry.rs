@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use crate::driver::Driver;
+use crate::error::FleetbaseError;
+use crate::geo::{BoundingBox, Point};
+use crate::place::PlaceResource;
+use crate::spatial::SpatialIndex;
+
+/// Last known positions of a fleet's drivers, indexed for proximity
+/// queries. Drivers without a location are kept but never match a query.
+#[derive(Debug, Default)]
+pub struct DriverRegistry {
+    drivers: HashMap<String, Driver>,
+    index: SpatialIndex,
+}
+
+impl DriverRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.drivers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.drivers.is_empty()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Driver> {
+        self.drivers.get(id)
+    }
+
+    /// Adds `driver` or replaces the stored copy, re-indexing its location.
+    pub fn upsert(&mut self, driver: Driver) {
+        let id = driver.id().to_string();
+        match driver.location() {
+            Some(location) => self.index.insert_point(id.clone(), location),
+            None => {
+                self.index.remove(&id);
+            }
+        }
+        self.drivers.insert(id, driver);
+    }
+
+    /// Moves a known driver, e.g. on a tracking update. Returns false when
+    /// the driver is not registered.
+    pub fn update_location(&mut self, id: &str, location: Point) -> bool {
+        match self.drivers.get_mut(id) {
+            Some(driver) => {
+                driver.set_location(location);
+                self.index.insert_point(id, &location);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<Driver> {
+        self.index.remove(id);
+        self.drivers.remove(id)
+    }
+
+    pub fn in_bbox(&self, bbox: &BoundingBox) -> Vec<&Driver> {
+        self.index
+            .intersecting(bbox)
+            .filter_map(|id| self.drivers.get(id))
+            .collect()
+    }
+
+    /// Drivers within `meters` of `center` with their distance, nearest
+    /// first.
+    pub fn within_radius(&self, center: &Point, meters: f64) -> Vec<(&Driver, f64)> {
+        let mut found = self.index.points_within_radius(center, meters);
+        found.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(b.0)));
+        self.resolve(found)
+    }
+
+    /// The `k` drivers closest to `center` with their distance, nearest
+    /// first.
+    pub fn nearest(&self, center: &Point, k: usize) -> Vec<(&Driver, f64)> {
+        self.resolve(self.index.nearest_points(center, k))
+    }
+
+    pub fn nearest_to_place(
+        &self,
+        place: &PlaceResource,
+        k: usize,
+    ) -> Result<Vec<(&Driver, f64)>, FleetbaseError> {
+        let location = place
+            .location()
+            .ok_or_else(|| FleetbaseError::Validation("place has no location".to_string()))?;
+        Ok(self.nearest(&location, k))
+    }
+
+    fn resolve(&self, found: Vec<(&str, f64)>) -> Vec<(&Driver, f64)> {
+        found
+            .into_iter()
+            .filter_map(|(id, distance)| Some((self.drivers.get(id)?, distance)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::FleetbaseClient;
+    use serde_json::json;
+
+    #[test]
+    fn test_nearest_drivers_to_place() {
+        let client = FleetbaseClient::new("test_token".to_string());
+        let mut registry = DriverRegistry::new();
+        for (id, lat, lng) in [
+            ("driver_a", 1.3000, 103.8000),
+            ("driver_b", 1.2900, 103.8500),
+            ("driver_c", 1.4400, 103.9900),
+        ] {
+            let attributes = json!({
+                "id": id,
+                "location": {"type": "Point", "coordinates": [lng, lat]}
+            });
+            registry.upsert(Driver::new(attributes, client.clone()).unwrap());
+        }
+        registry.upsert(Driver::new(json!({"id": "driver_d"}), client.clone()).unwrap());
+
+        let place = PlaceResource::new(
+            json!({
+                "id": "place_1",
+                "location": {"type": "Point", "coordinates": [103.8520, 1.2905]}
+            }),
+            client.clone(),
+            None,
+        )
+        .unwrap();
+        let nearest = registry.nearest_to_place(&place, 2).unwrap();
+        let ids: Vec<&str> = nearest.iter().map(|(d, _)| d.id()).collect();
+        assert_eq!(ids, vec!["driver_b", "driver_a"]);
+
+        assert!(registry.update_location("driver_c", Point::new(103.8521, 1.2906).unwrap()));
+        let nearest = registry.nearest_to_place(&place, 1).unwrap();
+        assert_eq!(nearest[0].0.id(), "driver_c");
+        assert_eq!(
+            registry
+                .within_radius(&place.location().unwrap(), 1_000.0)
+                .len(),
+            2
+        );
+
+        let unplaced = PlaceResource::new(json!({"id": "place_2"}), client, None).unwrap();
+        assert!(registry.nearest_to_place(&unplaced, 1).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::fmt;
+
+use crate::geo::GeometryError;
+
+#[derive(Debug)]
+pub enum FleetbaseError {
+    /// The request never produced a response (connection, TLS, timeout, ...).
+    Transport(reqwest::Error),
+    /// Non-success status whose body was not a recognised Fleetbase error.
+    Http { status: StatusCode, body: String },
+    /// Non-success status carrying Fleetbase's `{"error": ...}` or `{"errors": [...]}` body.
+    Api {
+        status: StatusCode,
+        errors: Vec<String>,
+    },
+    /// A success response whose body did not match the expected type.
+    Deserialize {
+        source: serde_json::Error,
+        body: String,
+    },
+    /// Rejected locally before any request was sent.
+    Validation(String),
+    /// Reading or writing local state such as stored credentials.
+    Io(std::io::Error),
+    /// Coordinates or shapes that are not valid GeoJSON geometry.
+    Geometry(GeometryError),
+    /// An order action that is not legal from the order's current status,
+    /// caught before any request was sent.
+    InvalidTransition {
+        order_id: String,
+        status: String,
+        action: String,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ApiErrorBody {
+    Single { error: String },
+    Many { errors: Vec<serde_json::Value> },
+}
+
+impl FleetbaseError {
+    pub fn from_response(status: StatusCode, body: String) -> Self {
+        match serde_json::from_str::<ApiErrorBody>(&body) {
+            Ok(ApiErrorBody::Single { error }) => FleetbaseError::Api {
+                status,
+                errors: vec![error],
+            },
+            Ok(ApiErrorBody::Many { errors }) => FleetbaseError::Api {
+                status,
+                errors: errors
+                    .into_iter()
+                    .map(|e| match e {
+                        serde_json::Value::String(s) => s,
+                        other => other.to_string(),
+                    })
+                    .collect(),
+            },
+            Err(_) => FleetbaseError::Http { status, body },
+        }
+    }
+
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            FleetbaseError::Transport(e) => e.status(),
+            FleetbaseError::Http { status, .. } | FleetbaseError::Api { status, .. } => {
+                Some(*status)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_unauthorized(&self) -> bool {
+        self.status() == Some(StatusCode::UNAUTHORIZED)
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND)
+    }
+
+    pub fn is_unprocessable(&self) -> bool {
+        self.status() == Some(StatusCode::UNPROCESSABLE_ENTITY)
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, FleetbaseError::Transport(e) if e.is_timeout())
+    }
+}
+
+impl fmt::Display for FleetbaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FleetbaseError::Transport(e) => write!(f, "transport error: {}", e),
+            FleetbaseError::Http { status, body } => write!(f, "HTTP {}: {}", status, body),
+            FleetbaseError::Api { status, errors } => {
+                write!(f, "API error {}: {}", status, errors.join("; "))
+            }
+            FleetbaseError::Deserialize { source, body } => {
+                write!(f, "failed to deserialize response ({}): {}", source, body)
+            }
+            FleetbaseError::Validation(msg) => write!(f, "validation error: {}", msg),
+            FleetbaseError::Io(e) => write!(f, "I/O error: {}", e),
+            FleetbaseError::Geometry(e) => write!(f, "invalid geometry: {}", e),
+            FleetbaseError::InvalidTransition {
+                order_id,
+                status,
+                action,
+            } => write!(
+                f,
+                "cannot {} order {} while it is {}",
+                action, order_id, status
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FleetbaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FleetbaseError::Transport(e) => Some(e),
+            FleetbaseError::Deserialize { source, .. } => Some(source),
+            FleetbaseError::Io(e) => Some(e),
+            FleetbaseError::Geometry(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for FleetbaseError {
+    fn from(e: reqwest::Error) -> Self {
+        FleetbaseError::Transport(e)
+    }
+}
+
+impl From<GeometryError> for FleetbaseError {
+    fn from(e: GeometryError) -> Self {
+        FleetbaseError::Geometry(e)
+    }
+}
+
+impl From<std::io::Error> for FleetbaseError {
+    fn from(e: std::io::Error) -> Self {
+        FleetbaseError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_response_shapes() {
+        let single = FleetbaseError::from_response(
+            StatusCode::NOT_FOUND,
+            r#"{"error": "Not Found"}"#.to_string(),
+        );
+        assert!(single.is_not_found());
+        assert!(
+            matches!(single, FleetbaseError::Api { ref errors, .. } if errors == &["Not Found"])
+        );
+
+        let many = FleetbaseError::from_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            r#"{"errors": ["name is required", "phone is invalid"]}"#.to_string(),
+        );
+        assert!(many.is_unprocessable());
+        assert!(matches!(many, FleetbaseError::Api { ref errors, .. } if errors.len() == 2));
+
+        let raw = FleetbaseError::from_response(
+            StatusCode::BAD_GATEWAY,
+            "<html>Bad Gateway</html>".to_string(),
+        );
+        assert!(
+            matches!(raw, FleetbaseError::Http { status, .. } if status == StatusCode::BAD_GATEWAY)
+        );
+    }
+}
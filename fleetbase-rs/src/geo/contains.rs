@@ -0,0 +1,144 @@
+//! Point-in-polygon tests. Coordinates are treated as planar, which is
+//! accurate enough for zones a few hundred kilometers across that do not
+//! cross the antimeridian. Points on a boundary count as inside.
+
+use super::{LineString, MultiPolygon, Point, Polygon};
+
+/// Tolerance for "on the boundary", in degrees (well under a millimeter).
+const EPSILON: f64 = 1e-9;
+
+/// Twice the signed area of the triangle `a b p`: positive when `p` is left
+/// of `a -> b`.
+fn orientation(a: &Point, b: &Point, p: &Point) -> f64 {
+    (b.longitude - a.longitude) * (p.latitude - a.latitude)
+        - (p.longitude - a.longitude) * (b.latitude - a.latitude)
+}
+
+fn on_segment(a: &Point, b: &Point, p: &Point) -> bool {
+    let length = ((b.longitude - a.longitude).powi(2) + (b.latitude - a.latitude).powi(2)).sqrt();
+    orientation(a, b, p).abs() <= EPSILON * length.max(1.0)
+        && p.longitude >= a.longitude.min(b.longitude) - EPSILON
+        && p.longitude <= a.longitude.max(b.longitude) + EPSILON
+        && p.latitude >= a.latitude.min(b.latitude) - EPSILON
+        && p.latitude <= a.latitude.max(b.latitude) + EPSILON
+}
+
+/// Consecutive point pairs, including the closing edge when the ring is not
+/// explicitly closed.
+fn edges(ring: &LineString) -> impl Iterator<Item = (&Point, &Point)> {
+    let points = ring.points();
+    let closing = match (points.first(), points.last()) {
+        (Some(first), Some(last)) if !ring.is_closed() && points.len() > 2 => Some((last, first)),
+        _ => None,
+    };
+    points
+        .windows(2)
+        .map(|pair| (&pair[0], &pair[1]))
+        .chain(closing)
+}
+
+impl LineString {
+    /// Whether `point` lies on or inside this ring, by winding number, so
+    /// the result does not depend on the ring's orientation.
+    pub fn ring_contains(&self, point: &Point) -> bool {
+        let mut winding = 0i32;
+        for (a, b) in edges(self) {
+            if on_segment(a, b, point) {
+                return true;
+            }
+            if a.latitude <= point.latitude {
+                if b.latitude > point.latitude && orientation(a, b, point) > 0.0 {
+                    winding += 1;
+                }
+            } else if b.latitude <= point.latitude && orientation(a, b, point) < 0.0 {
+                winding -= 1;
+            }
+        }
+        winding != 0
+    }
+
+    fn on_boundary(&self, point: &Point) -> bool {
+        edges(self).any(|(a, b)| on_segment(a, b, point))
+    }
+}
+
+impl Polygon {
+    /// Inside the exterior ring and not strictly inside any hole. A point on
+    /// a hole's edge is still inside the polygon.
+    pub fn contains(&self, point: &Point) -> bool {
+        if !self.bbox().is_some_and(|bbox| bbox.contains(point)) {
+            return false;
+        }
+        self.exterior().ring_contains(point)
+            && self
+                .interiors()
+                .iter()
+                .all(|hole| hole.on_boundary(point) || !hole.ring_contains(point))
+    }
+}
+
+impl MultiPolygon {
+    pub fn contains(&self, point: &Point) -> bool {
+        self.polygons()
+            .iter()
+            .any(|polygon| polygon.contains(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(positions: &[[f64; 2]]) -> LineString {
+        LineString::new(
+            positions
+                .iter()
+                .map(|[lng, lat]| Point::new(*lng, *lat).unwrap())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_polygon_with_hole() {
+        let polygon = Polygon::new(
+            ring(&[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]]),
+            vec![ring(&[
+                [1.0, 1.0],
+                [1.0, 3.0],
+                [3.0, 3.0],
+                [3.0, 1.0],
+                [1.0, 1.0],
+            ])],
+        );
+        let at = |lng, lat| Point::new(lng, lat).unwrap();
+
+        assert!(polygon.contains(&at(0.5, 0.5)));
+        assert!(!polygon.contains(&at(2.0, 2.0)));
+        assert!(!polygon.contains(&at(5.0, 2.0)));
+        // Edges and vertices of the exterior and the hole.
+        assert!(polygon.contains(&at(4.0, 2.0)));
+        assert!(polygon.contains(&at(0.0, 0.0)));
+        assert!(polygon.contains(&at(1.0, 2.0)));
+    }
+
+    #[test]
+    fn test_concave_ring_either_winding() {
+        // A "U" shape; the notch between the arms is outside.
+        let positions = [
+            [0.0, 0.0],
+            [3.0, 0.0],
+            [3.0, 3.0],
+            [2.0, 3.0],
+            [2.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 3.0],
+            [0.0, 3.0],
+        ];
+        let reversed: Vec<[f64; 2]> = positions.iter().rev().copied().collect();
+        for ring in [ring(&positions), ring(&reversed)] {
+            assert!(ring.ring_contains(&Point::new(0.5, 2.5).unwrap()));
+            assert!(!ring.ring_contains(&Point::new(1.5, 2.0).unwrap()));
+            assert!(ring.ring_contains(&Point::new(1.5, 0.5).unwrap()));
+        }
+    }
+}
@@ -0,0 +1,603 @@
+//! GeoJSON geometry shared by drivers, places, service areas and zones.
+//!
+//! Positions are always longitude first, as in GeoJSON. Constructors and
+//! deserializers reject coordinates outside `[-180, 180]` x `[-90, 90]`, so a
+//! swapped pair is usually caught rather than silently misplaced.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+mod contains;
+mod validate;
+
+pub use validate::PolygonProblem;
+
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryError {
+    OutOfRange {
+        longitude: f64,
+        latitude: f64,
+    },
+    /// A position with fewer than two numbers, or a non-finite one.
+    InvalidPosition(Vec<f64>),
+    /// GeoJSON `type` did not match the geometry being read.
+    UnexpectedType {
+        expected: &'static str,
+        found: String,
+    },
+    Empty(&'static str),
+    InvalidPolygon(Vec<PolygonProblem>),
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryError::OutOfRange {
+                longitude,
+                latitude,
+            } => write!(
+                f,
+                "position [{}, {}] is out of range; expected [longitude, latitude]",
+                longitude, latitude
+            ),
+            GeometryError::InvalidPosition(position) => {
+                write!(f, "invalid position {:?}", position)
+            }
+            GeometryError::UnexpectedType { expected, found } => {
+                write!(f, "expected a GeoJSON {}, found {}", expected, found)
+            }
+            GeometryError::Empty(kind) => write!(f, "{} has no positions", kind),
+            GeometryError::InvalidPolygon(problems) => {
+                write!(f, "invalid polygon: ")?;
+                for (i, problem) in problems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for GeometryError {}
+
+/// Great-circle distance in meters between two `(latitude, longitude)`
+/// pairs given in degrees.
+pub(crate) fn haversine(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = phi2 - phi1;
+    let d_lambda = (lng2 - lng1).to_radians();
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    longitude: f64,
+    latitude: f64,
+}
+
+impl Point {
+    pub fn new(longitude: f64, latitude: f64) -> Result<Self, GeometryError> {
+        if !longitude.is_finite() || !latitude.is_finite() {
+            return Err(GeometryError::InvalidPosition(vec![longitude, latitude]));
+        }
+        if !(-180.0..=180.0).contains(&longitude) || !(-90.0..=90.0).contains(&latitude) {
+            return Err(GeometryError::OutOfRange {
+                longitude,
+                latitude,
+            });
+        }
+        Ok(Self {
+            longitude,
+            latitude,
+        })
+    }
+
+    /// Same as `new` with the arguments in the order people usually say them.
+    pub fn from_lat_lng(latitude: f64, longitude: f64) -> Result<Self, GeometryError> {
+        Self::new(longitude, latitude)
+    }
+
+    pub fn longitude(&self) -> f64 {
+        self.longitude
+    }
+
+    pub fn latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    /// The GeoJSON position, `[longitude, latitude]`.
+    pub fn position(&self) -> [f64; 2] {
+        [self.longitude, self.latitude]
+    }
+
+    /// Great-circle distance in meters.
+    pub fn distance_to(&self, other: &Point) -> f64 {
+        haversine(
+            self.latitude,
+            self.longitude,
+            other.latitude,
+            other.longitude,
+        )
+    }
+
+    fn from_position(position: &[f64]) -> Result<Self, GeometryError> {
+        match position {
+            [longitude, latitude, ..] => Self::new(*longitude, *latitude),
+            _ => Err(GeometryError::InvalidPosition(position.to_vec())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineString(Vec<Point>);
+
+impl LineString {
+    pub fn new(points: Vec<Point>) -> Self {
+        Self(points)
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.0
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.0.len() > 1 && self.0.first() == self.0.last()
+    }
+
+    pub fn bbox(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(&self.0)
+    }
+
+    fn positions(&self) -> Vec<[f64; 2]> {
+        self.0.iter().map(Point::position).collect()
+    }
+
+    fn from_positions(positions: &[Vec<f64>]) -> Result<Self, GeometryError> {
+        positions
+            .iter()
+            .map(|p| Point::from_position(p))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// An exterior ring followed by any holes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    rings: Vec<LineString>,
+}
+
+impl Polygon {
+    pub fn new(exterior: LineString, interiors: Vec<LineString>) -> Self {
+        let mut rings = vec![exterior];
+        rings.extend(interiors);
+        Self { rings }
+    }
+
+    pub fn exterior(&self) -> &LineString {
+        &self.rings[0]
+    }
+
+    pub fn interiors(&self) -> &[LineString] {
+        &self.rings[1..]
+    }
+
+    pub fn rings(&self) -> &[LineString] {
+        &self.rings
+    }
+
+    pub fn bbox(&self) -> Option<BoundingBox> {
+        self.exterior().bbox()
+    }
+
+    fn positions(&self) -> Vec<Vec<[f64; 2]>> {
+        self.rings.iter().map(LineString::positions).collect()
+    }
+
+    /// A counter-clockwise ring of `segments` vertices `meters` from
+    /// `center` along great circles, approximating a circle.
+    pub fn circle(center: &Point, meters: f64, segments: usize) -> Self {
+        let angular = meters.max(0.0) / EARTH_RADIUS_METERS;
+        let (phi1, lambda1) = (center.latitude.to_radians(), center.longitude.to_radians());
+        let segments = segments.max(3);
+        let mut points: Vec<Point> = (0..segments)
+            .map(|i| {
+                // Bearings run clockwise from north, so walk them backwards.
+                let bearing = -(i as f64) * std::f64::consts::TAU / segments as f64;
+                let phi2 = (phi1.sin() * angular.cos()
+                    + phi1.cos() * angular.sin() * bearing.cos())
+                .asin();
+                let lambda2 = lambda1
+                    + (bearing.sin() * angular.sin() * phi1.cos())
+                        .atan2(angular.cos() - phi1.sin() * phi2.sin());
+                Point {
+                    longitude: (lambda2.to_degrees() + 540.0).rem_euclid(360.0) - 180.0,
+                    latitude: phi2.to_degrees(),
+                }
+            })
+            .collect();
+        points.push(points[0]);
+        Self::new(LineString::new(points), Vec::new())
+    }
+
+    fn from_positions(rings: &[Vec<Vec<f64>>]) -> Result<Self, GeometryError> {
+        let mut rings = rings
+            .iter()
+            .map(|ring| LineString::from_positions(ring))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let exterior = rings.next().ok_or(GeometryError::Empty("Polygon"))?;
+        Ok(Self::new(exterior, rings.collect()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiPolygon(Vec<Polygon>);
+
+impl MultiPolygon {
+    pub fn new(polygons: Vec<Polygon>) -> Self {
+        Self(polygons)
+    }
+
+    pub fn polygons(&self) -> &[Polygon] {
+        &self.0
+    }
+
+    pub fn bbox(&self) -> Option<BoundingBox> {
+        self.0
+            .iter()
+            .filter_map(Polygon::bbox)
+            .reduce(|a, b| a.union(&b))
+    }
+}
+
+/// `[min_longitude, min_latitude, max_longitude, max_latitude]`, the GeoJSON
+/// `bbox` member.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    min: Point,
+    max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self {
+            min: Point {
+                longitude: min.longitude.min(max.longitude),
+                latitude: min.latitude.min(max.latitude),
+            },
+            max: Point {
+                longitude: min.longitude.max(max.longitude),
+                latitude: min.latitude.max(max.latitude),
+            },
+        }
+    }
+
+    pub fn from_points(points: &[Point]) -> Option<Self> {
+        let first = points.first()?;
+        Some(
+            points
+                .iter()
+                .fold(Self::new(*first, *first), |bbox, p| bbox.extend(p)),
+        )
+    }
+
+    /// The smallest box covering every point within `meters` of `center`.
+    /// Circles reaching a pole or the antimeridian get the full longitude
+    /// range, which over-covers but never misses.
+    pub fn around(center: &Point, meters: f64) -> Self {
+        let angular = meters.max(0.0) / EARTH_RADIUS_METERS;
+        let d_lat = angular.to_degrees();
+        let min_lat = (center.latitude - d_lat).max(-90.0);
+        let max_lat = (center.latitude + d_lat).min(90.0);
+
+        let d_lng = (angular.sin() / center.latitude.to_radians().cos())
+            .asin()
+            .to_degrees();
+        let wraps = min_lat <= -90.0
+            || max_lat >= 90.0
+            || !d_lng.is_finite()
+            || center.longitude - d_lng < -180.0
+            || center.longitude + d_lng > 180.0;
+        let (min_lng, max_lng) = if wraps {
+            (-180.0, 180.0)
+        } else {
+            (center.longitude - d_lng, center.longitude + d_lng)
+        };
+
+        Self {
+            min: Point {
+                longitude: min_lng,
+                latitude: min_lat,
+            },
+            max: Point {
+                longitude: max_lng,
+                latitude: max_lat,
+            },
+        }
+    }
+
+    pub fn min(&self) -> Point {
+        self.min
+    }
+
+    pub fn max(&self) -> Point {
+        self.max
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        (self.min.longitude..=self.max.longitude).contains(&point.longitude)
+            && (self.min.latitude..=self.max.latitude).contains(&point.latitude)
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.longitude <= other.max.longitude
+            && other.min.longitude <= self.max.longitude
+            && self.min.latitude <= other.max.latitude
+            && other.min.latitude <= self.max.latitude
+    }
+
+    pub fn extend(&self, point: &Point) -> Self {
+        self.union(&Self::new(*point, *point))
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> Self {
+        Self {
+            min: Point {
+                longitude: self.min.longitude.min(other.min.longitude),
+                latitude: self.min.latitude.min(other.min.latitude),
+            },
+            max: Point {
+                longitude: self.max.longitude.max(other.max.longitude),
+                latitude: self.max.latitude.max(other.max.latitude),
+            },
+        }
+    }
+
+    pub fn to_array(&self) -> [f64; 4] {
+        [
+            self.min.longitude,
+            self.min.latitude,
+            self.max.longitude,
+            self.max.latitude,
+        ]
+    }
+}
+
+impl Serialize for BoundingBox {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_array().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BoundingBox {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let [min_lng, min_lat, max_lng, max_lat] = <[f64; 4]>::deserialize(deserializer)?;
+        let min = Point::new(min_lng, min_lat).map_err(D::Error::custom)?;
+        let max = Point::new(max_lng, max_lat).map_err(D::Error::custom)?;
+        Ok(Self::new(min, max))
+    }
+}
+
+/// The wire shape shared by every geometry.
+#[derive(Serialize, Deserialize)]
+struct GeoJson<C> {
+    #[serde(rename = "type")]
+    kind: String,
+    coordinates: C,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bbox: Option<BoundingBox>,
+}
+
+impl<C> GeoJson<C> {
+    fn new(kind: &str, coordinates: C, bbox: Option<BoundingBox>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            coordinates,
+            bbox,
+        }
+    }
+
+    fn expect(self, expected: &'static str) -> Result<Self, GeometryError> {
+        if self.kind == expected {
+            Ok(self)
+        } else {
+            Err(GeometryError::UnexpectedType {
+                expected,
+                found: self.kind,
+            })
+        }
+    }
+}
+
+impl MultiPolygon {
+    fn positions(&self) -> Vec<Vec<Vec<[f64; 2]>>> {
+        self.0.iter().map(Polygon::positions).collect()
+    }
+
+    fn from_positions(polygons: &[Vec<Vec<Vec<f64>>>]) -> Result<Self, GeometryError> {
+        polygons
+            .iter()
+            .map(|polygon| Polygon::from_positions(polygon))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+macro_rules! impl_geojson {
+    ($type:ty, $kind:literal, $coordinates:ty, $to:ident, $from:ident) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                GeoJson::new($kind, self.$to(), None).serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                GeoJson::<$coordinates>::deserialize(deserializer)?
+                    .expect($kind)
+                    .and_then(|geojson| <$type>::$from(&geojson.coordinates))
+                    .map_err(D::Error::custom)
+            }
+        }
+    };
+}
+
+impl_geojson!(Point, "Point", Vec<f64>, position, from_position);
+impl_geojson!(
+    LineString,
+    "LineString",
+    Vec<Vec<f64>>,
+    positions,
+    from_positions
+);
+impl_geojson!(
+    Polygon,
+    "Polygon",
+    Vec<Vec<Vec<f64>>>,
+    positions,
+    from_positions
+);
+impl_geojson!(
+    MultiPolygon,
+    "MultiPolygon",
+    Vec<Vec<Vec<Vec<f64>>>>,
+    positions,
+    from_positions
+);
+
+/// Serde adapter for APIs that send a point as flat `latitude` and
+/// `longitude` fields, e.g. `#[serde(flatten, with = "geo::lat_lng")]`.
+pub mod lat_lng {
+    use super::Point;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct LatLng {
+        latitude: f64,
+        longitude: f64,
+    }
+
+    pub fn serialize<S: Serializer>(point: &Point, serializer: S) -> Result<S::Ok, S::Error> {
+        LatLng {
+            latitude: point.latitude(),
+            longitude: point.longitude(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Point, D::Error> {
+        let LatLng {
+            latitude,
+            longitude,
+        } = LatLng::deserialize(deserializer)?;
+        Point::from_lat_lng(latitude, longitude).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_point_geojson_and_ranges() {
+        let point: Point = serde_json::from_value(json!({
+            "type": "Point",
+            "coordinates": [103.8198, 1.3521]
+        }))
+        .unwrap();
+        assert_eq!(point.latitude(), 1.3521);
+        assert_eq!(
+            serde_json::to_value(point).unwrap(),
+            json!({"type": "Point", "coordinates": [103.8198, 1.3521]})
+        );
+
+        // Latitude first by mistake.
+        let swapped = serde_json::from_value::<Point>(json!({
+            "type": "Point",
+            "coordinates": [40.7128, -174.0060]
+        }));
+        assert!(swapped.is_err());
+        assert!(matches!(
+            Point::new(181.0, 0.0),
+            Err(GeometryError::OutOfRange { .. })
+        ));
+        assert!(
+            serde_json::from_value::<Point>(json!({"type": "Polygon", "coordinates": [0, 0]}))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_polygon_round_trip() {
+        let value = json!({
+            "type": "Polygon",
+            "coordinates": [
+                [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]],
+                [[1.0, 1.0], [1.0, 2.0], [2.0, 2.0], [1.0, 1.0]]
+            ]
+        });
+        let polygon: Polygon = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(polygon.interiors().len(), 1);
+        assert!(polygon.exterior().is_closed());
+        assert_eq!(polygon.bbox().unwrap().to_array(), [0.0, 0.0, 4.0, 4.0]);
+        assert_eq!(serde_json::to_value(&polygon).unwrap(), value);
+
+        let multi = MultiPolygon::new(vec![polygon]);
+        let value = serde_json::to_value(&multi).unwrap();
+        assert_eq!(value["type"], "MultiPolygon");
+        assert_eq!(
+            serde_json::from_value::<MultiPolygon>(value).unwrap(),
+            multi
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        let singapore = Point::from_lat_lng(1.3521, 103.8198).unwrap();
+        let kuala_lumpur = Point::from_lat_lng(3.1390, 101.6869).unwrap();
+        let km = singapore.distance_to(&kuala_lumpur) / 1000.0;
+        assert!((km - 309.0).abs() < 5.0, "{}", km);
+
+        let bbox = BoundingBox::around(&singapore, 10_000.0);
+        for bearing in 0..8 {
+            let angle = (bearing as f64 * 45.0).to_radians();
+            // Roughly 9.9 km out in each direction.
+            let edge = Point::from_lat_lng(
+                singapore.latitude() + 0.089 * angle.cos(),
+                singapore.longitude() + 0.089 * angle.sin(),
+            )
+            .unwrap();
+            assert!(bbox.contains(&edge));
+        }
+        assert!(!bbox.contains(&kuala_lumpur));
+    }
+
+    #[test]
+    fn test_lat_lng_fields() {
+        #[derive(Serialize, Deserialize)]
+        struct Stop {
+            name: String,
+            #[serde(flatten, with = "lat_lng")]
+            location: Point,
+        }
+
+        let stop: Stop = serde_json::from_value(json!({
+            "name": "Depot",
+            "latitude": 1.3521,
+            "longitude": 103.8198
+        }))
+        .unwrap();
+        assert_eq!(stop.location.longitude(), 103.8198);
+        assert_eq!(serde_json::to_value(&stop).unwrap()["latitude"], 1.3521);
+    }
+}
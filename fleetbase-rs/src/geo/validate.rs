@@ -0,0 +1,357 @@
+//! Structural checks for polygons, following RFC 7946: rings are closed,
+//! have at least four positions, do not cross themselves, and wind
+//! counter-clockwise (exterior) or clockwise (holes).
+
+use std::fmt;
+
+use super::{GeometryError, LineString, Point, Polygon};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolygonProblem {
+    OutOfRange {
+        ring: usize,
+        position: usize,
+        coordinates: [f64; 2],
+    },
+    TooFewPositions {
+        ring: usize,
+        count: usize,
+    },
+    UnclosedRing {
+        ring: usize,
+    },
+    /// Edges are numbered from the ring's first position.
+    SelfIntersection {
+        ring: usize,
+        edges: (usize, usize),
+    },
+    WrongWinding {
+        ring: usize,
+    },
+}
+
+impl PolygonProblem {
+    /// Problems `Polygon::repair` fixes.
+    pub fn is_repairable(&self) -> bool {
+        matches!(
+            self,
+            PolygonProblem::UnclosedRing { .. } | PolygonProblem::WrongWinding { .. }
+        )
+    }
+}
+
+fn ring_name(ring: usize) -> String {
+    if ring == 0 {
+        "exterior ring".to_string()
+    } else {
+        format!("hole {}", ring)
+    }
+}
+
+impl fmt::Display for PolygonProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolygonProblem::OutOfRange {
+                ring,
+                position,
+                coordinates,
+            } => write!(
+                f,
+                "{} position {} {:?} is out of range",
+                ring_name(*ring),
+                position,
+                coordinates
+            ),
+            PolygonProblem::TooFewPositions { ring, count } => write!(
+                f,
+                "{} has {} positions; at least 4 are required",
+                ring_name(*ring),
+                count
+            ),
+            PolygonProblem::UnclosedRing { ring } => {
+                write!(f, "{} does not end where it starts", ring_name(*ring))
+            }
+            PolygonProblem::SelfIntersection { ring, edges } => write!(
+                f,
+                "{} crosses itself between edges {} and {}",
+                ring_name(*ring),
+                edges.0,
+                edges.1
+            ),
+            PolygonProblem::WrongWinding { ring } => write!(
+                f,
+                "{} must wind {}",
+                ring_name(*ring),
+                if *ring == 0 {
+                    "counter-clockwise"
+                } else {
+                    "clockwise"
+                }
+            ),
+        }
+    }
+}
+
+/// Twice the signed area; positive for counter-clockwise rings.
+fn signed_area(points: &[Point]) -> f64 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (&points[i], &points[(i + 1) % n]);
+            a.longitude * b.latitude - b.longitude * a.latitude
+        })
+        .sum()
+}
+
+fn orientation(a: &Point, b: &Point, c: &Point) -> f64 {
+    (b.longitude - a.longitude) * (c.latitude - a.latitude)
+        - (c.longitude - a.longitude) * (b.latitude - a.latitude)
+}
+
+fn within(a: &Point, b: &Point, p: &Point) -> bool {
+    p.longitude >= a.longitude.min(b.longitude)
+        && p.longitude <= a.longitude.max(b.longitude)
+        && p.latitude >= a.latitude.min(b.latitude)
+        && p.latitude <= a.latitude.max(b.latitude)
+}
+
+/// Whether segments `p1 p2` and `q1 q2` share any point, touching included.
+fn segments_intersect(p1: &Point, p2: &Point, q1: &Point, q2: &Point) -> bool {
+    let d1 = orientation(q1, q2, p1);
+    let d2 = orientation(q1, q2, p2);
+    let d3 = orientation(p1, p2, q1);
+    let d4 = orientation(p1, p2, q2);
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+    (d1 == 0.0 && within(q1, q2, p1))
+        || (d2 == 0.0 && within(q1, q2, p2))
+        || (d3 == 0.0 && within(p1, p2, q1))
+        || (d4 == 0.0 && within(p1, p2, q2))
+}
+
+/// The first pair of non-adjacent edges that touch, if any. Repeated
+/// consecutive positions, common in GIS exports, are not edges, so the
+/// edges either side of one count as adjacent. Quadratic in the number of
+/// edges, which is fine for hand-drawn zones.
+fn self_intersection(points: &[Point]) -> Option<(usize, usize)> {
+    // Where each remaining position was in `points`, so edges keep their
+    // original numbers.
+    let starts: Vec<usize> = (0..points.len())
+        .filter(|&i| i == 0 || points[i] != points[i - 1])
+        .collect();
+    let distinct: Vec<&Point> = starts.iter().map(|&i| &points[i]).collect();
+    let edges = distinct.len().saturating_sub(1);
+    for i in 0..edges {
+        for j in i + 2..edges {
+            // The first and last edges of a closed ring share its start.
+            if i == 0 && j == edges - 1 {
+                continue;
+            }
+            if segments_intersect(distinct[i], distinct[i + 1], distinct[j], distinct[j + 1]) {
+                return Some((starts[i], starts[j]));
+            }
+        }
+    }
+    None
+}
+
+fn ring_problems(index: usize, ring: &LineString, problems: &mut Vec<PolygonProblem>) {
+    let points = ring.points();
+    let closed = ring.is_closed();
+    // An unclosed ring is counted as if it were closed.
+    let count = points.len() + usize::from(!closed && !points.is_empty());
+    if count < 4 {
+        problems.push(PolygonProblem::TooFewPositions { ring: index, count });
+    }
+    if !closed {
+        problems.push(PolygonProblem::UnclosedRing { ring: index });
+    }
+    if count < 4 {
+        return;
+    }
+
+    let mut closed_points = points.to_vec();
+    if !closed {
+        closed_points.push(points[0]);
+    }
+    if let Some(edges) = self_intersection(&closed_points) {
+        problems.push(PolygonProblem::SelfIntersection { ring: index, edges });
+    }
+    let counter_clockwise = signed_area(points) > 0.0;
+    if counter_clockwise != (index == 0) {
+        problems.push(PolygonProblem::WrongWinding { ring: index });
+    }
+}
+
+impl Polygon {
+    /// Every problem with the polygon, or an empty list when it is valid.
+    pub fn problems(&self) -> Vec<PolygonProblem> {
+        let mut problems = Vec::new();
+        for (index, ring) in self.rings().iter().enumerate() {
+            ring_problems(index, ring, &mut problems);
+        }
+        problems
+    }
+
+    pub fn validate(&self) -> Result<(), GeometryError> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(GeometryError::InvalidPolygon(problems))
+        }
+    }
+
+    /// Closes open rings, drops repeated consecutive positions and reverses
+    /// rings that wind the wrong way. Self-intersections and rings that are
+    /// too short are left for `validate` to report.
+    pub fn repair(&self) -> Polygon {
+        let mut rings = self.rings().iter().enumerate().map(|(index, ring)| {
+            let mut points = ring.points().to_vec();
+            points.dedup();
+            if points.len() > 1 && points.first() != points.last() {
+                points.push(points[0]);
+            }
+            if points.len() >= 4 && (signed_area(&points) > 0.0) != (index == 0) {
+                points.reverse();
+            }
+            LineString::new(points)
+        });
+        let exterior = rings.next().unwrap_or_else(|| LineString::new(Vec::new()));
+        Polygon::new(exterior, rings.collect())
+    }
+
+    /// Builds a valid polygon from raw `[longitude, latitude]` rings,
+    /// reporting every problem at once instead of stopping at the first.
+    /// Rings with out-of-range positions report only those, since their
+    /// shape cannot be checked; the other rings get the full checks.
+    pub fn from_coordinates(rings: &[Vec<[f64; 2]>]) -> Result<Polygon, GeometryError> {
+        let mut problems = Vec::new();
+        let mut parsed = Vec::new();
+        for (ring, positions) in rings.iter().enumerate() {
+            let mut points = Vec::new();
+            let mut out_of_range = Vec::new();
+            for (position, coordinates) in positions.iter().enumerate() {
+                match Point::new(coordinates[0], coordinates[1]) {
+                    Ok(point) => points.push(point),
+                    Err(_) => out_of_range.push(PolygonProblem::OutOfRange {
+                        ring,
+                        position,
+                        coordinates: *coordinates,
+                    }),
+                }
+            }
+            let points = LineString::new(points);
+            if out_of_range.is_empty() {
+                ring_problems(ring, &points, &mut problems);
+            } else {
+                problems.extend(out_of_range);
+            }
+            parsed.push(points);
+        }
+
+        let mut parsed = parsed.into_iter();
+        let exterior = parsed.next().ok_or(GeometryError::Empty("Polygon"))?;
+        if !problems.is_empty() {
+            return Err(GeometryError::InvalidPolygon(problems));
+        }
+        Ok(Polygon::new(exterior, parsed.collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_every_problem() {
+        // Clockwise, unclosed bow-tie exterior and a three-position hole.
+        let polygon = Polygon::new(
+            LineString::new(
+                [[0.0, 0.0], [0.0, 4.0], [4.0, 0.0], [4.0, 4.0]]
+                    .iter()
+                    .map(|[lng, lat]| Point::new(*lng, *lat).unwrap())
+                    .collect(),
+            ),
+            vec![LineString::new(
+                [[1.0, 1.0], [2.0, 1.0], [1.0, 1.0]]
+                    .iter()
+                    .map(|[lng, lat]| Point::new(*lng, *lat).unwrap())
+                    .collect(),
+            )],
+        );
+        let problems = polygon.problems();
+        assert!(problems.contains(&PolygonProblem::UnclosedRing { ring: 0 }));
+        assert!(problems
+            .iter()
+            .any(|p| matches!(p, PolygonProblem::SelfIntersection { ring: 0, .. })));
+        assert!(problems.contains(&PolygonProblem::TooFewPositions { ring: 1, count: 3 }));
+
+        let message = polygon.validate().unwrap_err().to_string();
+        assert!(message.contains("exterior ring does not end where it starts"));
+        assert!(message.contains("hole 1 has 3 positions"));
+
+        let out_of_range = Polygon::from_coordinates(&[
+            vec![[0.0, 0.0], [190.0, 0.0], [0.0, 95.0], [0.0, 0.0]],
+            vec![[1.0, 1.0], [2.0, 1.0], [1.0, 1.0]],
+        ]);
+        match out_of_range {
+            Err(GeometryError::InvalidPolygon(problems)) => assert_eq!(
+                problems,
+                vec![
+                    PolygonProblem::OutOfRange {
+                        ring: 0,
+                        position: 1,
+                        coordinates: [190.0, 0.0]
+                    },
+                    PolygonProblem::OutOfRange {
+                        ring: 0,
+                        position: 2,
+                        coordinates: [0.0, 95.0]
+                    },
+                    PolygonProblem::TooFewPositions { ring: 1, count: 3 },
+                ]
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_repair_closes_and_rewinds() {
+        let polygon = Polygon::from_coordinates(&[
+            vec![[0.0, 0.0], [0.0, 4.0], [4.0, 4.0], [4.0, 0.0]],
+            vec![[1.0, 1.0], [2.0, 1.0], [2.0, 2.0], [1.0, 2.0], [1.0, 1.0]],
+        ]);
+        let Err(GeometryError::InvalidPolygon(problems)) = polygon else {
+            panic!("expected problems");
+        };
+        assert!(problems.iter().all(PolygonProblem::is_repairable));
+        assert_eq!(problems.len(), 3);
+
+        let broken = Polygon::new(
+            LineString::new(
+                [[0.0, 0.0], [0.0, 4.0], [4.0, 4.0], [4.0, 4.0], [4.0, 0.0]]
+                    .iter()
+                    .map(|[lng, lat]| Point::new(*lng, *lat).unwrap())
+                    .collect(),
+            ),
+            Vec::new(),
+        );
+        // A repeated position is not a crossing.
+        let repeated = Polygon::from_coordinates(&[vec![
+            [0.0, 0.0],
+            [4.0, 0.0],
+            [4.0, 0.0],
+            [4.0, 4.0],
+            [0.0, 4.0],
+            [0.0, 0.0],
+        ]]);
+        assert!(repeated.is_ok(), "{:?}", repeated);
+        let repaired = broken.repair();
+        assert!(repaired.validate().is_ok(), "{:?}", repaired.problems());
+        assert_eq!(repaired.exterior().points().len(), 5);
+    }
+}
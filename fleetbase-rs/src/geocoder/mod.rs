@@ -0,0 +1,54 @@
+//! Forward and reverse geocoding behind one `Geocoder` trait.
+//!
+//! `NominatimGeocoder` talks to any Nominatim-compatible server.
+//! `OfflineGeocoder` needs no network: it resolves coordinates to countries
+//! from a country-boundaries dataset, by default the coarse one bundled with
+//! the crate, which only covers Canada, Malaysia, Mexico, Singapore, the
+//! United Kingdom and the United States.
+
+use async_trait::async_trait;
+use std::fmt;
+
+use crate::error::FleetbaseError;
+use crate::geo::{BoundingBox, Point};
+
+mod nominatim;
+mod offline;
+
+pub use nominatim::NominatimGeocoder;
+pub use offline::{Country, OfflineGeocoder};
+
+/// A geocoding result. Parts the source did not resolve are `None`; the
+/// offline geocoder only ever fills in the country.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Address {
+    pub display_name: String,
+    pub location: Point,
+    /// The extent of the matched feature, e.g. a building or a country.
+    pub bbox: Option<BoundingBox>,
+    pub street1: Option<String>,
+    pub city: Option<String>,
+    pub province: Option<String>,
+    pub postal_code: Option<String>,
+    /// ISO 3166-1 alpha-2 code, upper case.
+    pub country: Option<String>,
+    pub country_name: Option<String>,
+}
+
+impl Address {
+    /// Whether `point` lies within the matched feature's extent, or within
+    /// `meters` of its location.
+    pub fn covers(&self, point: &Point, meters: f64) -> bool {
+        self.bbox.is_some_and(|bbox| bbox.contains(point))
+            || self.location.distance_to(point) <= meters
+    }
+}
+
+#[async_trait]
+pub trait Geocoder: fmt::Debug + Send + Sync {
+    /// Candidates for a free-form address, best match first.
+    async fn geocode(&self, query: &str) -> Result<Vec<Address>, FleetbaseError>;
+
+    /// The address at `point`, or `None` if there is nothing there.
+    async fn reverse(&self, point: &Point) -> Result<Option<Address>, FleetbaseError>;
+}
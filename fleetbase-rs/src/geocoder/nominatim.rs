@@ -0,0 +1,289 @@
+use async_trait::async_trait;
+use reqwest::header::{HeaderValue, ACCEPT_LANGUAGE, USER_AGENT};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{Address, Geocoder};
+use crate::error::FleetbaseError;
+use crate::geo::{BoundingBox, Point};
+use crate::rate_limit::RateLimiter;
+use crate::transport::{HttpRequest, HttpTransport, ReqwestTransport};
+
+const DEFAULT_LIMIT: usize = 5;
+
+/// Geocodes with a Nominatim-compatible `/search` and `/reverse` API.
+///
+/// The public server's usage policy asks for a `user_agent` identifying
+/// the application and at most one request per second, which `new`
+/// enforces. Self-hosted or commercial servers set their own limits.
+#[derive(Debug, Clone)]
+pub struct NominatimGeocoder {
+    base_url: String,
+    user_agent: String,
+    email: Option<String>,
+    language: Option<String>,
+    limit: usize,
+    transport: Arc<dyn HttpTransport>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+/// One `jsonv2` result. Coordinates arrive as strings.
+#[derive(Debug, Deserialize)]
+struct Place {
+    lat: String,
+    lon: String,
+    display_name: String,
+    /// `[south, north, west, east]`.
+    #[serde(default)]
+    boundingbox: Option<[String; 4]>,
+    #[serde(default)]
+    address: HashMap<String, String>,
+}
+
+/// `/reverse` answers 200 with an error body, e.g. "Unable to geocode" for
+/// the open ocean.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ReverseResponse {
+    Found(Place),
+    Error { error: String },
+}
+
+const NOTHING_THERE: &str = "Unable to geocode";
+
+fn coordinate(value: &str) -> Result<f64, FleetbaseError> {
+    value
+        .parse()
+        .map_err(|_| FleetbaseError::Validation(format!("invalid coordinate {:?}", value)))
+}
+
+impl Place {
+    fn first(&self, keys: &[&str]) -> Option<String> {
+        keys.iter().find_map(|key| self.address.get(*key)).cloned()
+    }
+
+    fn into_address(self) -> Result<Address, FleetbaseError> {
+        let location = Point::from_lat_lng(coordinate(&self.lat)?, coordinate(&self.lon)?)?;
+        let bbox = match &self.boundingbox {
+            Some([south, north, west, east]) => Some(BoundingBox::new(
+                Point::new(coordinate(west)?, coordinate(south)?)?,
+                Point::new(coordinate(east)?, coordinate(north)?)?,
+            )),
+            None => None,
+        };
+        let street1 = match (self.first(&["house_number"]), self.first(&["road"])) {
+            (Some(number), Some(road)) => Some(format!("{} {}", number, road)),
+            (None, road) => road,
+            (Some(_), None) => None,
+        };
+        Ok(Address {
+            street1,
+            city: self.first(&["city", "town", "village", "hamlet", "municipality"]),
+            province: self.first(&["state", "province", "region"]),
+            postal_code: self.first(&["postcode"]),
+            country: self
+                .first(&["country_code"])
+                .map(|code| code.to_ascii_uppercase()),
+            country_name: self.first(&["country"]),
+            display_name: self.display_name,
+            location,
+            bbox,
+        })
+    }
+}
+
+impl NominatimGeocoder {
+    /// The OpenStreetMap Foundation's public server.
+    pub const PUBLIC_URL: &'static str = "https://nominatim.openstreetmap.org";
+
+    /// Uses the public server, limited to one request per second.
+    pub fn new(user_agent: impl Into<String>) -> Self {
+        Self::with_base_url(Self::PUBLIC_URL, user_agent).rate_limit(1.0, 1)
+    }
+
+    /// Uses another Nominatim-compatible server, without rate limiting.
+    pub fn with_base_url(base_url: impl Into<String>, user_agent: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            user_agent: user_agent.into(),
+            email: None,
+            language: None,
+            limit: DEFAULT_LIMIT,
+            transport: Arc::new(ReqwestTransport::default()),
+            rate_limiter: None,
+        }
+    }
+
+    /// Sent with every request so the server operator can reach you.
+    pub fn email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// Preferred language for names, e.g. `en` or `ms,en`.
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Maximum number of forward geocoding candidates.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit.max(1);
+        self
+    }
+
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(requests_per_second, burst)));
+        self
+    }
+
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+    ) -> Result<T, FleetbaseError> {
+        let query = {
+            let mut query = url::form_urlencoded::Serializer::new(String::new());
+            query.extend_pairs(params);
+            query.append_pair("format", "jsonv2");
+            query.append_pair("addressdetails", "1");
+            if let Some(email) = &self.email {
+                query.append_pair("email", email);
+            }
+            query.finish()
+        };
+        let mut request =
+            HttpRequest::new(Method::GET, format!("{}/{}?{}", self.base_url, path, query));
+        let header = |value: &str| {
+            HeaderValue::from_str(value).map_err(|_| {
+                FleetbaseError::Validation(format!("invalid header value {:?}", value))
+            })
+        };
+        request
+            .headers
+            .insert(USER_AGENT, header(&self.user_agent)?);
+        if let Some(language) = &self.language {
+            request.headers.insert(ACCEPT_LANGUAGE, header(language)?);
+        }
+
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        let response = self.transport.send(request).await?;
+        if !response.status.is_success() {
+            return Err(FleetbaseError::from_response(
+                response.status,
+                response.body,
+            ));
+        }
+        serde_json::from_str(&response.body).map_err(|source| FleetbaseError::Deserialize {
+            source,
+            body: response.body,
+        })
+    }
+}
+
+#[async_trait]
+impl Geocoder for NominatimGeocoder {
+    async fn geocode(&self, query: &str) -> Result<Vec<Address>, FleetbaseError> {
+        let places: Vec<Place> = self
+            .get(
+                "search",
+                &[("q", query.to_string()), ("limit", self.limit.to_string())],
+            )
+            .await?;
+        places.into_iter().map(Place::into_address).collect()
+    }
+
+    async fn reverse(&self, point: &Point) -> Result<Option<Address>, FleetbaseError> {
+        let response: ReverseResponse = self
+            .get(
+                "reverse",
+                &[
+                    ("lat", point.latitude().to_string()),
+                    ("lon", point.longitude().to_string()),
+                ],
+            )
+            .await?;
+        match response {
+            ReverseResponse::Found(place) => place.into_address().map(Some),
+            ReverseResponse::Error { error } if error == NOTHING_THERE => Ok(None),
+            ReverseResponse::Error { error } => Err(FleetbaseError::Validation(format!(
+                "reverse geocoding failed: {}",
+                error
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpResponse, MemoryTransport};
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_search_and_reverse() {
+        let marina_bay_sands = json!({
+            "lat": "1.2837",
+            "lon": "103.8591",
+            "display_name": "Marina Bay Sands, 10, Bayfront Avenue, Singapore, 018956, Singapore",
+            "boundingbox": ["1.2814", "1.2862", "103.8567", "103.8615"],
+            "address": {
+                "house_number": "10",
+                "road": "Bayfront Avenue",
+                "city": "Singapore",
+                "postcode": "018956",
+                "country": "Singapore",
+                "country_code": "sg"
+            }
+        });
+        let transport = Arc::new(MemoryTransport::new());
+        transport
+            .on(
+                Method::GET,
+                "/search",
+                HttpResponse::json(StatusCode::OK, json!([marina_bay_sands])),
+            )
+            .push(HttpResponse::json(StatusCode::OK, marina_bay_sands))
+            .push(HttpResponse::json(
+                StatusCode::OK,
+                json!({"error": "Unable to geocode"}),
+            ));
+        let geocoder =
+            NominatimGeocoder::with_base_url("https://geo.example.com/", "fleet-app/1.0")
+                .email("ops@example.com")
+                .transport(transport.clone());
+
+        let found = geocoder.geocode("10 Bayfront Ave").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].street1.as_deref(), Some("10 Bayfront Avenue"));
+        assert_eq!(found[0].country.as_deref(), Some("SG"));
+        let request = transport.last_request().unwrap();
+        assert_eq!(request.header("user-agent"), Some("fleet-app/1.0"));
+        assert_eq!(
+            request.query(),
+            Some(
+                "q=10+Bayfront+Ave&limit=5&format=jsonv2&addressdetails=1&email=ops%40example.com"
+            )
+        );
+
+        let point = Point::from_lat_lng(1.2834, 103.8607).unwrap();
+        let address = geocoder.reverse(&point).await.unwrap().unwrap();
+        assert_eq!(address.postal_code.as_deref(), Some("018956"));
+        assert!(address.covers(&point, 0.0));
+        assert_eq!(transport.last_request().unwrap().path(), "/reverse");
+
+        let ocean = Point::from_lat_lng(0.0, -140.0).unwrap();
+        assert!(geocoder.reverse(&ocean).await.unwrap().is_none());
+    }
+}
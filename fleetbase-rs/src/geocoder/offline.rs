@@ -0,0 +1,228 @@
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use std::sync::OnceLock;
+
+use super::{Address, Geocoder};
+use crate::error::FleetbaseError;
+use crate::geo::{MultiPolygon, Point, Polygon};
+use crate::interchange::features;
+
+/// Simplified outlines of six countries only: CA, GB, MX, MY, SG and US,
+/// accurate to a few kilometres away from borders and coasts. Anywhere else
+/// has no country. Load a complete dataset such as Natural Earth's admin-0
+/// countries with `OfflineGeocoder::from_geojson`.
+const BUNDLED: &str = include_str!("../../data/countries.geojson");
+
+#[derive(Debug, Clone)]
+pub struct Country {
+    /// ISO 3166-1 alpha-2, upper case.
+    pub code: String,
+    /// ISO 3166-1 alpha-3, upper case.
+    pub code3: Option<String>,
+    pub name: String,
+    pub boundary: MultiPolygon,
+    /// Where forward geocoding places the country.
+    pub label: Point,
+}
+
+impl Country {
+    fn address(&self) -> Address {
+        Address {
+            display_name: self.name.clone(),
+            location: self.label,
+            bbox: self.boundary.bbox(),
+            street1: None,
+            city: None,
+            province: None,
+            postal_code: None,
+            country: Some(self.code.clone()),
+            country_name: Some(self.name.clone()),
+        }
+    }
+
+    fn matches(&self, query: &str) -> bool {
+        self.name.eq_ignore_ascii_case(query)
+            || self.code.eq_ignore_ascii_case(query)
+            || self
+                .code3
+                .as_deref()
+                .is_some_and(|code| code.eq_ignore_ascii_case(query))
+    }
+}
+
+/// Country-level geocoding without network access. Reverse lookups return
+/// the country containing the point; forward lookups match a country's name
+/// or code, either as the whole query or as its last comma-separated part.
+#[derive(Debug, Clone)]
+pub struct OfflineGeocoder {
+    countries: Vec<Country>,
+}
+
+fn string(properties: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| properties.get(*key)?.as_str())
+        .map(str::to_string)
+}
+
+impl OfflineGeocoder {
+    /// The dataset bundled with the crate, covering CA, GB, MX, MY, SG and
+    /// US only.
+    pub fn bundled() -> &'static OfflineGeocoder {
+        static GEOCODER: OnceLock<OfflineGeocoder> = OnceLock::new();
+        GEOCODER.get_or_init(|| {
+            OfflineGeocoder::from_geojson(BUNDLED).expect("bundled country boundaries are valid")
+        })
+    }
+
+    /// Reads a FeatureCollection of Polygon or MultiPolygon countries. The
+    /// code comes from `iso_a2`, the name from `name` (upper-case Natural
+    /// Earth names work too), and features without a two-letter code are
+    /// skipped. An optional `label` property holds `[longitude, latitude]`.
+    pub fn from_geojson(geojson: &str) -> Result<Self, FleetbaseError> {
+        let document: Value = serde_json::from_str(geojson)
+            .map_err(|e| FleetbaseError::Validation(format!("invalid GeoJSON: {}", e)))?;
+        let mut countries = Vec::new();
+        for (index, feature) in features(document)?.into_iter().enumerate() {
+            let invalid = |reason: String| {
+                FleetbaseError::Validation(format!("feature {}: {}", index, reason))
+            };
+            let empty = Map::new();
+            let properties = feature
+                .get("properties")
+                .and_then(Value::as_object)
+                .unwrap_or(&empty);
+            let Some(code) = string(properties, &["iso_a2", "ISO_A2"])
+                .filter(|code| code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic()))
+            else {
+                continue;
+            };
+
+            let geometry = feature.get("geometry").cloned().unwrap_or(Value::Null);
+            let boundary = match geometry.get("type").and_then(Value::as_str) {
+                Some("Polygon") => serde_json::from_value::<Polygon>(geometry)
+                    .map(|polygon| MultiPolygon::new(vec![polygon])),
+                Some("MultiPolygon") => serde_json::from_value::<MultiPolygon>(geometry),
+                other => {
+                    return Err(invalid(format!(
+                        "expected a Polygon or MultiPolygon, found {}",
+                        other.unwrap_or("no geometry")
+                    )))
+                }
+            }
+            .map_err(|e| invalid(e.to_string()))?;
+            let bbox = boundary
+                .bbox()
+                .ok_or_else(|| invalid("boundary has no positions".to_string()))?;
+
+            let label = match properties.get("label") {
+                Some(label) => {
+                    let [longitude, latitude] =
+                        serde_json::from_value::<[f64; 2]>(label.clone())
+                            .map_err(|e| invalid(format!("invalid label: {}", e)))?;
+                    Point::new(longitude, latitude)?
+                }
+                None => Point::new(
+                    (bbox.min().longitude() + bbox.max().longitude()) / 2.0,
+                    (bbox.min().latitude() + bbox.max().latitude()) / 2.0,
+                )?,
+            };
+
+            countries.push(Country {
+                name: string(properties, &["name", "NAME", "ADMIN"])
+                    .unwrap_or_else(|| code.clone()),
+                code: code.to_ascii_uppercase(),
+                code3: string(properties, &["iso_a3", "ISO_A3"])
+                    .map(|code| code.to_ascii_uppercase()),
+                boundary,
+                label,
+            });
+        }
+        Ok(Self { countries })
+    }
+
+    pub fn countries(&self) -> &[Country] {
+        &self.countries
+    }
+
+    pub fn country_at(&self, point: &Point) -> Option<&Country> {
+        self.countries
+            .iter()
+            .find(|country| country.boundary.contains(point))
+    }
+
+    pub fn country(&self, code_or_name: &str) -> Option<&Country> {
+        let query = code_or_name.trim();
+        self.countries.iter().find(|country| country.matches(query))
+    }
+}
+
+#[async_trait]
+impl Geocoder for OfflineGeocoder {
+    async fn geocode(&self, query: &str) -> Result<Vec<Address>, FleetbaseError> {
+        let last_part = query.rsplit(',').next().unwrap_or_default();
+        Ok(self
+            .country(query)
+            .or_else(|| self.country(last_part))
+            .map(Country::address)
+            .into_iter()
+            .collect())
+    }
+
+    async fn reverse(&self, point: &Point) -> Result<Option<Address>, FleetbaseError> {
+        Ok(self.country_at(point).map(Country::address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bundled_countries() {
+        let geocoder = OfflineGeocoder::bundled();
+        for country in geocoder.countries() {
+            for polygon in country.boundary.polygons() {
+                assert_eq!(polygon.problems(), Vec::new(), "{}", country.name);
+            }
+            assert_eq!(
+                geocoder.country_at(&country.label).map(|c| c.code.as_str()),
+                Some(country.code.as_str())
+            );
+        }
+
+        let places = [
+            ((1.2834, 103.8607), Some("SG")),
+            ((1.4927, 103.7414), Some("MY")),
+            ((49.2827, -123.1207), Some("CA")),
+            ((47.6062, -122.3321), Some("US")),
+            ((42.8864, -78.8784), Some("US")),
+            ((43.6532, -79.3832), Some("CA")),
+            ((32.5149, -117.0382), Some("MX")),
+            ((21.3069, -157.8583), Some("US")),
+            ((51.5074, -0.1278), Some("GB")),
+            ((48.8566, 2.3522), None),
+        ];
+        for ((latitude, longitude), expected) in places {
+            let point = Point::from_lat_lng(latitude, longitude).unwrap();
+            let address = geocoder.reverse(&point).await.unwrap();
+            assert_eq!(
+                address.and_then(|a| a.country).as_deref(),
+                expected,
+                "{}, {}",
+                latitude,
+                longitude
+            );
+        }
+
+        let found = geocoder
+            .geocode("10 Bayfront Avenue, Singapore")
+            .await
+            .unwrap();
+        assert_eq!(found[0].country.as_deref(), Some("SG"));
+        assert_eq!(
+            geocoder.geocode("usa").await.unwrap()[0].display_name,
+            "United States"
+        );
+        assert!(geocoder.geocode("Atlantis").await.unwrap().is_empty());
+    }
+}
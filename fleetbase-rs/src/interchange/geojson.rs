@@ -0,0 +1,227 @@
+use serde_json::{json, Map, Value};
+
+use super::{circle, import_zones, sorted_areas, sorted_zones, ImportReport, ZoneFeature};
+use crate::error::FleetbaseError;
+use crate::geo::{MultiPolygon, Polygon};
+use crate::service_area::{ServiceArea, ServiceAreaManager};
+use crate::zone::ZoneManager;
+
+fn string(properties: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| properties.get(*key)?.as_str())
+        .map(str::to_string)
+}
+
+/// The features of a FeatureCollection, or a lone Feature.
+pub(crate) fn features(document: Value) -> Result<Vec<Value>, FleetbaseError> {
+    match document.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => match document.get("features") {
+            Some(Value::Array(features)) => Ok(features.clone()),
+            _ => Err(FleetbaseError::Validation(
+                "FeatureCollection has no features array".to_string(),
+            )),
+        },
+        Some("Feature") => Ok(vec![document]),
+        other => Err(FleetbaseError::Validation(format!(
+            "expected a GeoJSON FeatureCollection, found {}",
+            other.unwrap_or("no type")
+        ))),
+    }
+}
+
+fn zone_feature(feature: &Value) -> (ZoneFeature, Result<Vec<Polygon>, String>) {
+    let empty = Map::new();
+    let properties = feature
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    // `fill` and `stroke` are the simplestyle names used by geojson.io.
+    let zone = ZoneFeature {
+        name: string(properties, &["name"]),
+        description: string(properties, &["description"]),
+        color: string(properties, &["color", "fill"]),
+        stroke_color: string(properties, &["stroke_color", "stroke"]),
+        status: string(properties, &["status"]),
+    };
+
+    let polygons = match feature.get("geometry") {
+        None | Some(Value::Null) => Err("feature has no geometry".to_string()),
+        Some(geometry) => match geometry.get("type").and_then(Value::as_str) {
+            Some("Polygon") => serde_json::from_value::<Polygon>(geometry.clone())
+                .map(|polygon| vec![polygon])
+                .map_err(|e| e.to_string()),
+            // QGIS and most shapefile conversions write every polygon layer
+            // as MultiPolygon, usually with a single part.
+            Some("MultiPolygon") => serde_json::from_value::<MultiPolygon>(geometry.clone())
+                .map_err(|e| e.to_string())
+                .and_then(|multi| match multi.polygons() {
+                    [] => Err("MultiPolygon has no polygons".to_string()),
+                    polygons => Ok(polygons.to_vec()),
+                }),
+            Some(other) => Err(format!("unsupported geometry type {}", other)),
+            None => Err("geometry has no type".to_string()),
+        },
+    };
+    (zone, polygons)
+}
+
+fn feature(id: &str, geometry: Value, properties: Value) -> Value {
+    json!({
+        "type": "Feature",
+        "id": id,
+        "geometry": geometry,
+        "properties": properties,
+    })
+}
+
+impl ZoneManager {
+    /// Creates a zone for each Polygon feature, and for each part of a
+    /// MultiPolygon, reading `name`, `description`, `color`,
+    /// `stroke_color` and `status` from its properties. Fails only if
+    /// `geojson` is not a FeatureCollection or Feature; per-feature
+    /// problems are reported in the `ImportReport`.
+    pub fn import_geojson(
+        &mut self,
+        geojson: &str,
+        service_area: Option<&ServiceArea>,
+    ) -> Result<ImportReport, FleetbaseError> {
+        let document: Value = serde_json::from_str(geojson)
+            .map_err(|e| FleetbaseError::Validation(format!("invalid GeoJSON: {}", e)))?;
+        let features = features(document)?.iter().map(zone_feature).collect();
+        Ok(import_zones(self, features, service_area))
+    }
+
+    pub fn export_geojson(&self) -> Value {
+        let features: Vec<Value> = sorted_zones(self)
+            .into_iter()
+            .map(|zone| {
+                feature(
+                    &zone.id,
+                    json!(zone.border),
+                    json!({
+                        "name": zone.name,
+                        "description": zone.description,
+                        "color": zone.color,
+                        "stroke_color": zone.stroke_color,
+                        "status": zone.status,
+                        "service_area": zone.service_area,
+                    }),
+                )
+            })
+            .collect();
+        json!({ "type": "FeatureCollection", "features": features })
+    }
+}
+
+impl ServiceAreaManager {
+    /// Service areas as polygons approximating their circles, with the
+    /// center and radius kept in the properties.
+    pub fn export_geojson(&self) -> Value {
+        let features: Vec<Value> = sorted_areas(self)
+            .into_iter()
+            .map(|area| {
+                feature(
+                    &area.id,
+                    json!(circle(area)),
+                    json!({
+                        "name": area.name,
+                        "country": area.country,
+                        "center": area.location.position(),
+                        "radius": area.radius,
+                        "status": area.status,
+                        "type": area.r#type,
+                    }),
+                )
+            })
+            .collect();
+        json!({ "type": "FeatureCollection", "features": features })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::Point;
+
+    #[test]
+    fn test_geojson_round_trip_and_report() {
+        let mut zones = ZoneManager::new();
+        let report = zones
+            .import_geojson(
+                &json!({
+                    "type": "FeatureCollection",
+                    "features": [
+                        {
+                            "type": "Feature",
+                            // Clockwise and unclosed, as some tools write it.
+                            "geometry": {
+                                "type": "Polygon",
+                                "coordinates": [[[103.8, 1.27], [103.8, 1.32], [103.87, 1.32], [103.87, 1.27]]]
+                            },
+                            "properties": {
+                                "name": "Central",
+                                "description": "CBD & Marina",
+                                "fill": "#ff0000",
+                                "status": "inactive"
+                            }
+                        },
+                        {
+                            "type": "Feature",
+                            "geometry": { "type": "Point", "coordinates": [103.8, 1.3] },
+                            "properties": { "name": "Depot" }
+                        },
+                        { "type": "Feature", "geometry": null, "properties": {} }
+                    ]
+                })
+                .to_string(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(report.imported.len(), 1);
+        let reasons: Vec<&str> = report.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(
+            reasons,
+            vec!["unsupported geometry type Point", "feature has no geometry"]
+        );
+        assert_eq!(report.skipped[0].name.as_deref(), Some("Depot"));
+
+        let zone = zones.get_zone(&report.imported[0]).unwrap();
+        assert_eq!(zone.stroke_color, "#ff0000");
+        assert_eq!(zone.status, "inactive");
+        assert!(zone.border.validate().is_ok());
+
+        let exported = zones.export_geojson();
+        let mut copy = ZoneManager::new();
+        let report = copy.import_geojson(&exported.to_string(), None).unwrap();
+        let imported = copy.get_zone(&report.imported[0]).unwrap();
+        assert_eq!(imported.border, zone.border);
+        assert_eq!(imported.description.as_deref(), Some("CBD & Marina"));
+
+        assert!(zones.import_geojson("{\"type\": \"Point\"}", None).is_err());
+    }
+
+    #[test]
+    fn test_service_area_circle_export() {
+        let mut areas = ServiceAreaManager::new();
+        let area = areas
+            .create_service_area(
+                "Singapore".to_string(),
+                "SG".to_string(),
+                Point::from_lat_lng(1.3521, 103.8198).unwrap(),
+                10_000.0,
+                "active".to_string(),
+                "city".to_string(),
+            )
+            .unwrap();
+        let polygon = circle(area);
+        assert!(polygon.validate().is_ok());
+        for point in polygon.exterior().points() {
+            assert!((area.location.distance_to(point) - 10_000.0).abs() < 1.0);
+        }
+
+        let exported = areas.export_geojson();
+        let feature = &exported["features"][0];
+        assert_eq!(feature["geometry"]["type"], "Polygon");
+        assert_eq!(feature["properties"]["radius"], 10_000.0);
+    }
+}
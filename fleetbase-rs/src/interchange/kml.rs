@@ -0,0 +1,503 @@
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::HashMap;
+use std::io;
+
+use super::{circle, import_zones, sorted_areas, sorted_zones, ImportReport, ZoneFeature};
+use crate::error::FleetbaseError;
+use crate::geo::{LineString, Point, Polygon};
+use crate::service_area::{ServiceArea, ServiceAreaManager};
+use crate::zone::ZoneManager;
+
+const KML_NAMESPACE: &str = "http://www.opengis.net/kml/2.2";
+
+/// Just enough of an XML tree to walk a KML document.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    text: String,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn child_text(&self, name: &str) -> Option<String> {
+        let text = self.child(name)?.text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    /// Every descendant called `name`, in document order.
+    fn descendants<'a>(&'a self, name: &str, found: &mut Vec<&'a Element>) {
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            }
+            child.descendants(name, found);
+        }
+    }
+}
+
+fn xml_error(e: impl std::fmt::Display) -> FleetbaseError {
+    FleetbaseError::Validation(format!("invalid KML: {}", e))
+}
+
+fn parse(kml: &str) -> Result<Element, FleetbaseError> {
+    let mut reader = Reader::from_str(kml);
+    let mut stack = vec![Element::default()];
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(start) => {
+                let mut element = Element {
+                    name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+                    ..Element::default()
+                };
+                for attribute in start.attributes() {
+                    let attribute = attribute.map_err(xml_error)?;
+                    element.attributes.insert(
+                        String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned(),
+                        attribute.unescape_value().map_err(xml_error)?.into_owned(),
+                    );
+                }
+                stack.push(element);
+            }
+            Event::Empty(start) => {
+                let element = Element {
+                    name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+                    ..Element::default()
+                };
+                stack.last_mut().unwrap().children.push(element);
+            }
+            Event::End(_) => {
+                let element = stack.pop().unwrap();
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Err(xml_error("unbalanced end tag")),
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(xml_error)?;
+                stack.last_mut().unwrap().text.push_str(&text);
+            }
+            Event::CData(data) => {
+                let data = data.into_inner();
+                stack
+                    .last_mut()
+                    .unwrap()
+                    .text
+                    .push_str(&String::from_utf8_lossy(&data));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(root), true) => Ok(root),
+        _ => Err(xml_error("unclosed element")),
+    }
+}
+
+/// KML colors are `aabbggrr`; zones use `#rrggbb`.
+fn from_kml_color(color: &str) -> Option<String> {
+    let color = color.trim().trim_start_matches('#');
+    if color.len() != 8 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(format!("#{}{}{}", &color[6..8], &color[4..6], &color[2..4]).to_lowercase())
+}
+
+fn to_kml_color(color: &str, alpha: &str) -> Option<String> {
+    let color = color.trim_start_matches('#');
+    if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(format!(
+        "{}{}{}{}",
+        alpha,
+        &color[4..6],
+        &color[2..4],
+        &color[0..2]
+    ))
+}
+
+/// Fill and line colors by style id, with `StyleMap`s resolved to their
+/// `normal` style.
+fn styles(root: &Element) -> HashMap<String, (Option<String>, Option<String>)> {
+    let mut found = Vec::new();
+    root.descendants("Style", &mut found);
+    let mut styles: HashMap<String, _> = found
+        .into_iter()
+        .filter_map(|style| Some((style.attributes.get("id")?.clone(), style_colors(style))))
+        .collect();
+
+    let mut maps = Vec::new();
+    root.descendants("StyleMap", &mut maps);
+    for map in maps {
+        let normal = map
+            .children("Pair")
+            .find(|pair| pair.child_text("key").as_deref() == Some("normal"))
+            .and_then(|pair| pair.child_text("styleUrl"));
+        if let (Some(id), Some(url)) = (map.attributes.get("id"), normal) {
+            if let Some(colors) = styles.get(url.trim_start_matches('#')).cloned() {
+                styles.insert(id.clone(), colors);
+            }
+        }
+    }
+    styles
+}
+
+fn style_colors(style: &Element) -> (Option<String>, Option<String>) {
+    let color = |name: &str| {
+        style
+            .child(name)
+            .and_then(|s| s.child_text("color"))
+            .and_then(|c| from_kml_color(&c))
+    };
+    (color("PolyStyle"), color("LineStyle"))
+}
+
+/// `ExtendedData` values, from either `Data` or `SchemaData` entries.
+fn extended_data(placemark: &Element) -> HashMap<String, String> {
+    let mut data = HashMap::new();
+    let Some(extended) = placemark.child("ExtendedData") else {
+        return data;
+    };
+    for entry in extended.children("Data") {
+        if let (Some(name), Some(value)) = (entry.attributes.get("name"), entry.child_text("value"))
+        {
+            data.insert(name.clone(), value);
+        }
+    }
+    for schema in extended.children("SchemaData") {
+        for entry in schema.children("SimpleData") {
+            if let Some(name) = entry.attributes.get("name") {
+                data.insert(name.clone(), entry.text.trim().to_string());
+            }
+        }
+    }
+    data
+}
+
+fn ring(boundary: &Element) -> Result<LineString, String> {
+    let coordinates = boundary
+        .child("LinearRing")
+        .and_then(|ring| ring.child("coordinates"))
+        .ok_or("boundary has no LinearRing coordinates")?;
+    coordinates
+        .text
+        .split_whitespace()
+        .map(|tuple| {
+            let values: Vec<f64> = tuple
+                .split(',')
+                .map(|v| v.parse::<f64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("invalid coordinate {:?}", tuple))?;
+            match values[..] {
+                [lng, lat] | [lng, lat, _] => Point::new(lng, lat).map_err(|e| e.to_string()),
+                _ => Err(format!("invalid coordinate {:?}", tuple)),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(LineString::new)
+}
+
+fn polygon(polygon: &Element) -> Result<Polygon, String> {
+    let exterior = polygon
+        .child("outerBoundaryIs")
+        .ok_or("polygon has no outerBoundaryIs")
+        .map_err(str::to_string)
+        .and_then(ring)?;
+    let interiors = polygon
+        .children("innerBoundaryIs")
+        .map(ring)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Polygon::new(exterior, interiors))
+}
+
+/// The placemark's `Polygon`, or the polygons of its `MultiGeometry`, which
+/// QGIS writes for every polygon layer. Other parts of a `MultiGeometry`,
+/// such as a label `Point`, are ignored.
+fn polygons(placemark: &Element) -> Result<Vec<Polygon>, String> {
+    if let Some(single) = placemark.child("Polygon") {
+        return polygon(single).map(|polygon| vec![polygon]);
+    }
+    if let Some(multi) = placemark.child("MultiGeometry") {
+        let mut parts = Vec::new();
+        multi.descendants("Polygon", &mut parts);
+        if parts.is_empty() {
+            return Err("MultiGeometry has no Polygon".to_string());
+        }
+        return parts.into_iter().map(polygon).collect();
+    }
+    let geometry = ["Point", "LineString", "LinearRing", "Model"]
+        .into_iter()
+        .find(|name| placemark.child(name).is_some());
+    Err(match geometry {
+        Some(name) => format!("unsupported geometry type {}", name),
+        None => "placemark has no geometry".to_string(),
+    })
+}
+
+fn zone_feature(
+    placemark: &Element,
+    styles: &HashMap<String, (Option<String>, Option<String>)>,
+) -> (ZoneFeature, Result<Vec<Polygon>, String>) {
+    let (mut color, mut stroke_color) = placemark
+        .child_text("styleUrl")
+        .and_then(|url| styles.get(url.trim_start_matches('#')).cloned())
+        .unwrap_or_default();
+    if let Some(style) = placemark.child("Style") {
+        let (fill, line) = style_colors(style);
+        color = fill.or(color);
+        stroke_color = line.or(stroke_color);
+    }
+    let mut data = extended_data(placemark);
+    let zone = ZoneFeature {
+        name: placemark.child_text("name"),
+        description: placemark
+            .child_text("description")
+            .or_else(|| data.remove("description")),
+        color: data.remove("color").or(color),
+        stroke_color: data.remove("stroke_color").or(stroke_color),
+        status: data.remove("status"),
+    };
+    (zone, polygons(placemark))
+}
+
+fn write_text<W: io::Write>(writer: &mut Writer<W>, name: &str, text: &str) -> io::Result<()> {
+    writer
+        .create_element(name)
+        .write_text_content(BytesText::new(text))?;
+    Ok(())
+}
+
+fn write_polygon<W: io::Write>(writer: &mut Writer<W>, polygon: &Polygon) -> io::Result<()> {
+    let write_ring = |writer: &mut Writer<W>, boundary: &str, ring: &LineString| {
+        let coordinates: Vec<String> = ring
+            .points()
+            .iter()
+            .map(|p| format!("{},{}", p.longitude(), p.latitude()))
+            .collect();
+        writer.create_element(boundary).write_inner_content(|w| {
+            w.create_element("LinearRing")
+                .write_inner_content(|w| write_text(w, "coordinates", &coordinates.join(" ")))?;
+            Ok(())
+        })?;
+        Ok::<_, io::Error>(())
+    };
+    writer.create_element("Polygon").write_inner_content(|w| {
+        write_ring(w, "outerBoundaryIs", polygon.exterior())?;
+        for hole in polygon.interiors() {
+            write_ring(w, "innerBoundaryIs", hole)?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+fn write_data<W: io::Write>(writer: &mut Writer<W>, data: &[(&str, &str)]) -> io::Result<()> {
+    writer
+        .create_element("ExtendedData")
+        .write_inner_content(|w| {
+            for (name, value) in data {
+                w.create_element("Data")
+                    .with_attribute(("name", *name))
+                    .write_inner_content(|w| write_text(w, "value", value))?;
+            }
+            Ok(())
+        })?;
+    Ok(())
+}
+
+fn document<F>(name: &str, placemarks: F) -> String
+where
+    F: FnOnce(&mut Writer<Vec<u8>>) -> io::Result<()>,
+{
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    // Writing into a Vec cannot fail.
+    writer
+        .write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
+        .and_then(|_| {
+            writer
+                .create_element("kml")
+                .with_attribute(("xmlns", KML_NAMESPACE))
+                .write_inner_content(|w| {
+                    w.create_element("Document").write_inner_content(|w| {
+                        write_text(w, "name", name)?;
+                        placemarks(w)
+                    })?;
+                    Ok(())
+                })
+        })
+        .expect("writing to a Vec");
+    String::from_utf8(writer.into_inner()).expect("KML is UTF-8")
+}
+
+impl ZoneManager {
+    /// Creates a zone for each `Polygon` placemark, and for each polygon of
+    /// a `MultiGeometry`, as exported by Google My Maps, Google Earth or
+    /// QGIS. Colors come from the placemark's
+    /// style; `status` and the other fields may also be given as
+    /// `ExtendedData`.
+    pub fn import_kml(
+        &mut self,
+        kml: &str,
+        service_area: Option<&ServiceArea>,
+    ) -> Result<ImportReport, FleetbaseError> {
+        let root = parse(kml)?;
+        if root.child("kml").is_none() {
+            return Err(xml_error("missing <kml> root element"));
+        }
+        let styles = styles(&root);
+        let mut placemarks = Vec::new();
+        root.descendants("Placemark", &mut placemarks);
+        let features = placemarks
+            .into_iter()
+            .map(|placemark| zone_feature(placemark, &styles))
+            .collect();
+        Ok(import_zones(self, features, service_area))
+    }
+
+    pub fn export_kml(&self) -> String {
+        document("Zones", |w| {
+            for zone in sorted_zones(self) {
+                w.create_element("Placemark")
+                    .with_attribute(("id", zone.id.as_str()))
+                    .write_inner_content(|w| {
+                        write_text(w, "name", &zone.name)?;
+                        if let Some(description) = &zone.description {
+                            write_text(w, "description", description)?;
+                        }
+                        w.create_element("Style").write_inner_content(|w| {
+                            if let Some(color) = to_kml_color(&zone.stroke_color, "ff") {
+                                w.create_element("LineStyle")
+                                    .write_inner_content(|w| write_text(w, "color", &color))?;
+                            }
+                            if let Some(color) = to_kml_color(&zone.color, "80") {
+                                w.create_element("PolyStyle")
+                                    .write_inner_content(|w| write_text(w, "color", &color))?;
+                            }
+                            Ok(())
+                        })?;
+                        write_data(
+                            w,
+                            &[
+                                ("color", &zone.color),
+                                ("stroke_color", &zone.stroke_color),
+                                ("status", &zone.status),
+                            ],
+                        )?;
+                        write_polygon(w, &zone.border.polygon)
+                    })?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl ServiceAreaManager {
+    /// Service areas as polygons approximating their circles.
+    pub fn export_kml(&self) -> String {
+        document("Service areas", |w| {
+            for area in sorted_areas(self) {
+                let radius = area.radius.to_string();
+                w.create_element("Placemark")
+                    .with_attribute(("id", area.id.as_str()))
+                    .write_inner_content(|w| {
+                        write_text(w, "name", &area.name)?;
+                        write_data(
+                            w,
+                            &[
+                                ("country", &area.country),
+                                ("radius", &radius),
+                                ("status", &area.status),
+                                ("type", &area.r#type),
+                            ],
+                        )?;
+                        write_polygon(w, &circle(area))
+                    })?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kml_import_and_round_trip() {
+        // Trimmed from a Google My Maps export.
+        let kml = r##"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2">
+  <Document>
+    <Style id="poly-A52714-normal">
+      <LineStyle><color>ff1427a5</color></LineStyle>
+      <PolyStyle><color>4c1427a5</color></PolyStyle>
+    </Style>
+    <StyleMap id="poly-A52714">
+      <Pair><key>normal</key><styleUrl>#poly-A52714-normal</styleUrl></Pair>
+    </StyleMap>
+    <Folder>
+      <Placemark>
+        <name>Orchard</name>
+        <description><![CDATA[Malls <b>only</b>]]></description>
+        <styleUrl>#poly-A52714</styleUrl>
+        <ExtendedData><Data name="status"><value>inactive</value></Data></ExtendedData>
+        <Polygon>
+          <outerBoundaryIs>
+            <LinearRing>
+              <tessellate>1</tessellate>
+              <coordinates>
+                103.82,1.30,0 103.84,1.30,0 103.84,1.31,0 103.82,1.31,0 103.82,1.30,0
+              </coordinates>
+            </LinearRing>
+          </outerBoundaryIs>
+        </Polygon>
+      </Placemark>
+      <Placemark>
+        <name>Depot</name>
+        <Point><coordinates>103.85,1.29,0</coordinates></Point>
+      </Placemark>
+      <Placemark>
+        <name>Swapped</name>
+        <Polygon><outerBoundaryIs><LinearRing><coordinates>
+          1.30,103.82 1.30,103.84 1.31,103.84 1.30,103.82
+        </coordinates></LinearRing></outerBoundaryIs></Polygon>
+      </Placemark>
+    </Folder>
+  </Document>
+</kml>"##;
+        let mut zones = ZoneManager::new();
+        let report = zones.import_kml(kml, None).unwrap();
+        assert_eq!(report.imported.len(), 1);
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(report.skipped[0].reason, "unsupported geometry type Point");
+        assert_eq!(report.skipped[1].name.as_deref(), Some("Swapped"));
+
+        let zone = zones.get_zone(&report.imported[0]).unwrap();
+        assert_eq!(zone.color, "#a52714");
+        assert_eq!(zone.stroke_color, "#a52714");
+        assert_eq!(zone.status, "inactive");
+        assert_eq!(zone.description.as_deref(), Some("Malls <b>only</b>"));
+
+        let exported = zones.export_kml();
+        let mut copy = ZoneManager::new();
+        let report = copy.import_kml(&exported, None).unwrap();
+        assert!(report.skipped.is_empty());
+        let imported = copy.get_zone(&report.imported[0]).unwrap();
+        assert_eq!(imported.border.polygon, zone.border.polygon);
+        assert_eq!(imported.description, zone.description);
+        assert_eq!(imported.status, "inactive");
+
+        assert!(zones.import_kml("<kml><Document>", None).is_err());
+    }
+}
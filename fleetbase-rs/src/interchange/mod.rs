@@ -0,0 +1,185 @@
+//! GeoJSON FeatureCollection and KML import/export for zones and service
+//! areas, so zones can be drawn in desktop GIS or web map tools.
+//!
+//! Imports are lenient per feature: open or wrongly wound rings are
+//! repaired, and anything else that cannot become a zone is listed in the
+//! `ImportReport` instead of failing the whole file.
+
+use crate::geo::Polygon;
+use crate::service_area::{ServiceArea, ServiceAreaManager};
+use crate::zone::{Border, Zone, ZoneManager};
+
+mod geojson;
+mod kml;
+
+pub(crate) use geojson::features;
+
+/// Fill and stroke for imported zones that carry no color of their own.
+pub const DEFAULT_COLOR: &str = "#3388ff";
+
+/// Vertices used to approximate a service area's circle on export.
+const CIRCLE_SEGMENTS: usize = 64;
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Ids of the zones created, in document order.
+    pub imported: Vec<String>,
+    pub skipped: Vec<SkippedFeature>,
+}
+
+/// A feature or placemark that was not imported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedFeature {
+    /// Position among the document's features or placemarks, from zero.
+    pub index: usize,
+    pub name: Option<String>,
+    pub reason: String,
+}
+
+/// A zone read from either format, before validation.
+#[derive(Debug, Default)]
+struct ZoneFeature {
+    name: Option<String>,
+    description: Option<String>,
+    color: Option<String>,
+    stroke_color: Option<String>,
+    status: Option<String>,
+}
+
+/// Imports one zone per polygon. A multi-part feature becomes one zone per
+/// part, named `"{name} (1)"`, `"{name} (2)"` and so on.
+fn import_zones(
+    zones: &mut ZoneManager,
+    features: Vec<(ZoneFeature, Result<Vec<Polygon>, String>)>,
+    service_area: Option<&ServiceArea>,
+) -> ImportReport {
+    let mut report = ImportReport::default();
+    for (index, (feature, polygons)) in features.into_iter().enumerate() {
+        let polygons = match polygons {
+            Ok(polygons) => polygons,
+            Err(reason) => {
+                report.skipped.push(SkippedFeature {
+                    index,
+                    name: feature.name.clone(),
+                    reason,
+                });
+                continue;
+            }
+        };
+        let name = feature
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Zone {}", index + 1));
+        let parts = polygons.len();
+        for (part, polygon) in polygons.into_iter().enumerate() {
+            let name = match parts {
+                1 => name.clone(),
+                _ => format!("{} ({})", name, part + 1),
+            };
+            let skip = |reason: String| SkippedFeature {
+                index,
+                name: Some(name.clone()),
+                reason,
+            };
+            match import_zone(zones, &feature, name.clone(), polygon, service_area) {
+                Ok(id) => report.imported.push(id),
+                Err(reason) => report.skipped.push(skip(reason)),
+            }
+        }
+    }
+    report
+}
+
+fn import_zone(
+    zones: &mut ZoneManager,
+    feature: &ZoneFeature,
+    name: String,
+    polygon: Polygon,
+    service_area: Option<&ServiceArea>,
+) -> Result<String, String> {
+    let mut border = Border::new(polygon);
+    border.repair();
+
+    let color = feature
+        .color
+        .clone()
+        .unwrap_or_else(|| DEFAULT_COLOR.to_string());
+    let stroke_color = feature
+        .stroke_color
+        .clone()
+        .unwrap_or_else(|| color.clone());
+    let mut zone = Zone::new(name, border, color, stroke_color, None).map_err(|e| e.to_string())?;
+    zone.description = feature.description.clone();
+    if let Some(status) = feature.status.clone() {
+        zone.status = status;
+    }
+    Ok(zones.insert_zone(zone, service_area)?.id.clone())
+}
+
+/// Zones by name then id, so exports are stable.
+fn sorted_zones(zones: &ZoneManager) -> Vec<&Zone> {
+    let mut zones = zones.list_zones(None);
+    zones.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    zones
+}
+
+fn sorted_areas(areas: &ServiceAreaManager) -> Vec<&ServiceArea> {
+    let mut areas = areas.list_service_areas(None);
+    areas.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    areas
+}
+
+fn circle(area: &ServiceArea) -> Polygon {
+    Polygon::circle(&area.location, area.radius, CIRCLE_SEGMENTS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Layouts written by QGIS 3's "Export > Save Features As" for a
+    /// polygon layer with `fid`, `name` and `status` fields.
+    const QGIS_GEOJSON: &str = include_str!("../../data/fixtures/qgis_zones.geojson");
+    const QGIS_KML: &str = include_str!("../../data/fixtures/qgis_zones.kml");
+
+    #[test]
+    fn test_qgis_multi_part_exports() {
+        let mut from_geojson = ZoneManager::new();
+        let geojson = from_geojson.import_geojson(QGIS_GEOJSON, None).unwrap();
+        let mut from_kml = ZoneManager::new();
+        let kml = from_kml.import_kml(QGIS_KML, None).unwrap();
+
+        for (zones, report, reason) in [
+            (&from_geojson, &geojson, "MultiPolygon has no polygons"),
+            (&from_kml, &kml, "MultiGeometry has no Polygon"),
+        ] {
+            let imported: Vec<&Zone> = report
+                .imported
+                .iter()
+                .map(|id| zones.get_zone(id).unwrap())
+                .collect();
+            let names: Vec<&str> = imported.iter().map(|zone| zone.name.as_str()).collect();
+            assert_eq!(
+                names,
+                [
+                    "Sentosa",
+                    "Northeast Islands (1)",
+                    "Northeast Islands (2)",
+                    "Jurong Island"
+                ]
+            );
+            assert_eq!(imported[0].status, "active");
+            assert_eq!(imported[3].status, "inactive");
+            assert_eq!(imported[3].border.polygon.interiors().len(), 1);
+            assert_eq!(report.skipped.len(), 1);
+            assert_eq!(report.skipped[0].index, 3);
+            assert_eq!(report.skipped[0].reason, reason);
+        }
+        for (a, b) in geojson.imported.iter().zip(&kml.imported) {
+            assert_eq!(
+                from_geojson.get_zone(a).unwrap().border,
+                from_kml.get_zone(b).unwrap().border
+            );
+        }
+    }
+}
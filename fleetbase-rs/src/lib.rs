@@ -1,23 +1,50 @@
 pub mod client;
 pub mod contact;
 pub mod driver;
+pub mod driver_registry;
 pub mod entity;
-
+pub mod error;
+pub mod geo;
+pub mod geocoder;
+pub mod interchange;
+pub mod lifecycle;
+pub mod options;
 pub mod order;
+pub mod order_params;
 pub mod organization;
+pub mod pagination;
+pub mod payload;
 pub mod place;
 pub mod purchase_rate;
+pub mod query;
+pub mod rate_limit;
 pub mod resource;
+pub mod retry;
+pub mod service;
 pub mod service_area;
 pub mod service_quote;
 pub mod service_rate;
+pub mod session;
+pub mod spatial;
+pub mod store;
+pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tracker;
 pub mod tracking_number;
 pub mod tracking_status;
+pub mod transport;
 pub mod utils;
 pub mod vehicle;
 pub mod vendor;
 pub mod zone;
 
 pub use driver::Driver;
+pub use error::FleetbaseError;
+pub use options::RequestOptions;
 pub use organization::Organization;
+pub use query::ListQuery;
 pub use resource::Resource;
+pub use session::DriverSession;
+pub use transport::{HttpTransport, MemoryTransport};
+pub use utils::enpdpoints::Endpoint;
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::error::FleetbaseError;
+use crate::order::OrderStatus;
+
+/// The status-changing actions an order accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderTransition {
+    Dispatch,
+    Start,
+    UpdateActivity,
+    Complete,
+    Cancel,
+}
+
+impl OrderTransition {
+    pub const ALL: [OrderTransition; 5] = [
+        OrderTransition::Dispatch,
+        OrderTransition::Start,
+        OrderTransition::UpdateActivity,
+        OrderTransition::Complete,
+        OrderTransition::Cancel,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderTransition::Dispatch => "dispatch",
+            OrderTransition::Start => "start",
+            OrderTransition::UpdateActivity => "update activity of",
+            OrderTransition::Complete => "complete",
+            OrderTransition::Cancel => "cancel",
+        }
+    }
+}
+
+impl fmt::Display for OrderTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Client-side model of Fleetbase's order flow:
+///
+/// ```text
+/// created -> dispatched -> driver_enroute -> enroute -> completed
+///     \___________\______________\______________\----> canceled
+/// ```
+///
+/// A dispatched order may be started or moved straight to an activity.
+/// With `skip_dispatch`, so may a created one. Statuses the crate does not
+/// know are treated as custom in-progress activities, so they may be
+/// updated, completed or canceled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderLifecycle {
+    status: OrderStatus,
+    skip_dispatch: bool,
+}
+
+impl OrderLifecycle {
+    pub fn new(status: OrderStatus) -> Self {
+        Self {
+            status,
+            skip_dispatch: false,
+        }
+    }
+
+    /// Lets a created order be started or updated without being dispatched
+    /// first, as the `skip_dispatch` request flag does.
+    pub fn skip_dispatch(mut self, skip: bool) -> Self {
+        self.skip_dispatch = skip;
+        self
+    }
+
+    pub fn status(&self) -> &OrderStatus {
+        &self.status
+    }
+
+    pub fn allows(&self, transition: OrderTransition) -> bool {
+        use OrderStatus::*;
+
+        match (transition, &self.status) {
+            (_, Completed | Canceled) => false,
+            (OrderTransition::Dispatch, Created) => true,
+            (OrderTransition::Start | OrderTransition::UpdateActivity, Created) => {
+                self.skip_dispatch
+            }
+            (OrderTransition::Start, Dispatched) => true,
+            (
+                OrderTransition::UpdateActivity,
+                Dispatched | DriverEnroute | Enroute | Unknown(_),
+            ) => true,
+            (OrderTransition::Complete, DriverEnroute | Enroute | Unknown(_)) => true,
+            (OrderTransition::Cancel, _) => true,
+            _ => false,
+        }
+    }
+
+    pub fn allowed(&self) -> Vec<OrderTransition> {
+        OrderTransition::ALL
+            .into_iter()
+            .filter(|t| self.allows(*t))
+            .collect()
+    }
+
+    /// The status the order is expected to reach. `UpdateActivity` depends
+    /// on the activity chosen, so it has no fixed target.
+    pub fn target(transition: OrderTransition) -> Option<OrderStatus> {
+        match transition {
+            OrderTransition::Dispatch => Some(OrderStatus::Dispatched),
+            OrderTransition::Start => Some(OrderStatus::DriverEnroute),
+            OrderTransition::UpdateActivity => None,
+            OrderTransition::Complete => Some(OrderStatus::Completed),
+            OrderTransition::Cancel => Some(OrderStatus::Canceled),
+        }
+    }
+
+    /// Fails with `FleetbaseError::InvalidTransition` when `transition` is
+    /// not legal from the current status.
+    pub fn check(&self, order_id: &str, transition: OrderTransition) -> Result<(), FleetbaseError> {
+        if self.allows(transition) {
+            return Ok(());
+        }
+        Err(FleetbaseError::InvalidTransition {
+            order_id: order_id.to_string(),
+            status: self.status.to_string(),
+            action: transition.to_string(),
+        })
+    }
+}
+
+/// A step in an order's activity flow, as returned by `next-activity`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Activity {
+    pub code: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// Set on the activity that finishes the order.
+    #[serde(default)]
+    pub complete: bool,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Activity {
+    /// The order status this activity moves the order to.
+    pub fn order_status(&self) -> OrderStatus {
+        self.code.as_str().into()
+    }
+
+    /// `next-activity` answers with either one activity or a list of
+    /// alternatives.
+    pub fn from_response(value: serde_json::Value) -> Result<Vec<Activity>, FleetbaseError> {
+        let body = value.to_string();
+        let activities = match value {
+            serde_json::Value::Array(items) => items
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<Result<Vec<_>, _>>(),
+            serde_json::Value::Null => Ok(Vec::new()),
+            single => serde_json::from_value(single).map(|activity| vec![activity]),
+        };
+        activities.map_err(|source| FleetbaseError::Deserialize { source, body })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_transitions() {
+        let created = OrderLifecycle::new(OrderStatus::Created);
+        assert_eq!(
+            created.allowed(),
+            vec![OrderTransition::Dispatch, OrderTransition::Cancel]
+        );
+        assert!(matches!(
+            created.check("order_1", OrderTransition::Start),
+            Err(FleetbaseError::InvalidTransition { ref status, .. }) if status == "created"
+        ));
+
+        let canceled = OrderLifecycle::new(OrderStatus::Canceled);
+        assert!(canceled.allowed().is_empty());
+        assert!(canceled
+            .check("order_1", OrderTransition::Complete)
+            .is_err());
+
+        let skipping = created.clone().skip_dispatch(true);
+        assert_eq!(
+            skipping.allowed(),
+            vec![
+                OrderTransition::Dispatch,
+                OrderTransition::Start,
+                OrderTransition::UpdateActivity,
+                OrderTransition::Cancel
+            ]
+        );
+        assert!(
+            OrderLifecycle::new(OrderStatus::Dispatched).allows(OrderTransition::UpdateActivity)
+        );
+
+        let custom = OrderLifecycle::new(OrderStatus::Unknown("at_customs".to_string()));
+        assert!(custom.allows(OrderTransition::UpdateActivity));
+        assert!(!custom.allows(OrderTransition::Dispatch));
+    }
+
+    #[test]
+    fn test_activity_from_response() {
+        let single =
+            Activity::from_response(json!({"code": "enroute", "status": "Enroute"})).unwrap();
+        assert_eq!(single[0].order_status(), OrderStatus::Enroute);
+
+        let many = Activity::from_response(json!([
+            {"code": "completed", "complete": true},
+            {"code": "failed", "details": "Delivery failed"}
+        ]))
+        .unwrap();
+        assert!(many[0].complete);
+        assert_eq!(
+            many[1].order_status(),
+            OrderStatus::Unknown("failed".to_string())
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use crate::retry::new_idempotency_key;
+
+/// Per-request overrides, passed alongside an action's params.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestOptions {
+    /// Replaces the client-wide timeout for this request only.
+    pub timeout: Option<Duration>,
+    /// Sent as `Idempotency-Key`; also lets POST requests be retried.
+    pub idempotency_key: Option<String>,
+    pub headers: Vec<(String, String)>,
+}
+
+impl RequestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options carrying a freshly generated idempotency key.
+    pub fn idempotent() -> Self {
+        Self::new().idempotency_key(new_idempotency_key())
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
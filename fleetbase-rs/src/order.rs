@@ -1,439 +1,686 @@
-use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use reqwest::Method;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
-
-use crate::resource::Resource;
-use crate::utils::{is_resource, StoreActions};
+use std::fmt;
+
+use crate::client::FleetbaseClient;
+use crate::driver::Driver;
+use crate::error::FleetbaseError;
+use crate::lifecycle::{Activity, OrderLifecycle, OrderTransition};
+use crate::options::RequestOptions;
+use crate::order_params::{
+    CancelParams, CompleteParams, DispatchParams, StartParams, UpdateActivityParams,
+};
+use crate::payload::Payload;
+use crate::place::Place;
+use crate::service::ApiResource;
+use crate::utils::enpdpoints::{Endpoint, Orders};
+use crate::utils::{is_resource, timestamp};
+use crate::vehicle::Vehicle;
 
 pub struct OrderActions {
-    adapter: reqwest::Client,
-    namespace: String,
+    adapter: FleetbaseClient,
 }
 
-#[async_trait]
-impl StoreActions for OrderActions {
-    async fn get_distance_and_time(
+impl OrderActions {
+    pub fn new(adapter: FleetbaseClient) -> Self {
+        Self { adapter }
+    }
+
+    async fn get(
         &self,
-        id: &str,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        endpoint: Orders,
+        query: &HashMap<String, String>,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
         self.adapter
-            .get(&format!("{}/{}/distance-and-time", self.namespace, id))
-            .query(&params)
-            .send()
-            .await?
-            .json()
+            .request_with_options::<(), _>(
+                Method::GET,
+                Endpoint::Orders(endpoint),
+                Some(query),
+                None,
+                options,
+            )
             .await
-            .map_err(Into::into)
     }
 
-    async fn get_next_activity(
+    async fn post<B>(
         &self,
-        id: &str,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        endpoint: Orders,
+        body: &B,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError>
+    where
+        B: Serialize + ?Sized,
+    {
         self.adapter
-            .get(&format!("{}/{}/next-activity", self.namespace, id))
-            .query(&params)
-            .send()
-            .await?
-            .json()
+            .request_with_options(
+                Method::POST,
+                Endpoint::Orders(endpoint),
+                None,
+                Some(body),
+                options,
+            )
             .await
-            .map_err(Into::into)
     }
 
-    async fn dispatch(
+    pub async fn get_distance_and_time(
         &self,
         id: &str,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        self.adapter
-            .post(&format!("{}/{}/dispatch", self.namespace, id))
-            .json(&params)
-            .send()
-            .await?
-            .json()
-            .await
-            .map_err(Into::into)
+        query: &HashMap<String, String>,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.get(
+            Orders::OrdersDistanceAndTime(id.to_string()),
+            query,
+            options,
+        )
+        .await
     }
 
-    async fn start(
+    pub async fn get_next_activity(
         &self,
         id: &str,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        self.adapter
-            .post(&format!("{}/{}/start", self.namespace, id))
-            .json(&params)
-            .send()
-            .await?
-            .json()
+        query: &HashMap<String, String>,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.get(Orders::OrdersNextActivity(id.to_string()), query, options)
             .await
-            .map_err(Into::into)
     }
 
-    async fn update_activity(
+    pub async fn dispatch(
         &self,
         id: &str,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        self.adapter
-            .post(&format!("{}/{}/update-activity", self.namespace, id))
-            .json(&params)
-            .send()
-            .await?
-            .json()
+        params: &DispatchParams,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.post(Orders::OrdersDispatch(id.to_string()), params, options)
             .await
-            .map_err(Into::into)
     }
 
-    async fn set_destination(
+    pub async fn start(
         &self,
         id: &str,
-        destination_id: &str,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        let destination_id = if is_resource(destination_id) {
-            // Assuming is_resource returns a Result<String, Error>
-            destination_id
-                .parse::<serde_json::Value>()?
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or(destination_id)
-        } else {
-            destination_id
-        };
-
-        self.adapter
-            .post(&format!(
-                "{}/{}/set-destination/{}",
-                self.namespace, id, destination_id
-            ))
-            .json(&params)
-            .send()
-            .await?
-            .json()
+        params: &StartParams,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.post(Orders::OrdersStart(id.to_string()), params, options)
             .await
-            .map_err(Into::into)
     }
 
-    async fn capture_qr_code(
+    pub async fn update_activity(
         &self,
         id: &str,
-        subject_id: Option<&str>,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        let subject_id = subject_id.map(|sid| {
-            if is_resource(sid) {
-                // Assuming is_resource returns a Result<String, Error>
-                sid.parse::<serde_json::Value>()
-                    .ok()
-                    .and_then(|v| v.get("id").and_then(|v| v.as_str()).map(|s| s.to_string()))
-                    .unwrap_or_else(|| sid.to_string())
-            } else {
-                sid.to_string()
-            }
-        });
-
-        let url = match subject_id {
-            Some(sid) => format!("{}/{}/capture-qr/{}", self.namespace, id, sid),
-            None => format!("{}/{}/capture-qr", self.namespace, id),
-        };
+        params: &UpdateActivityParams,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.post(
+            Orders::OrdersUpdateActivity(id.to_string()),
+            params,
+            options,
+        )
+        .await
+    }
 
-        self.adapter
-            .post(&url)
-            .json(&params)
-            .send()
-            .await?
-            .json()
-            .await
-            .map_err(Into::into)
+    pub async fn set_destination(
+        &self,
+        id: &str,
+        destination_id: &str,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        let destination_id = resource_id(destination_id)?;
+        self.post(
+            Orders::OrdersSetDestination(id.to_string(), destination_id),
+            &serde_json::json!({}),
+            options,
+        )
+        .await
     }
 
-    async fn capture_signature(
+    /// `params` is sent as-is, e.g. `{"code": ..., "data": ...}`.
+    pub async fn capture_qr_code<B>(
         &self,
         id: &str,
         subject_id: Option<&str>,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        let subject_id = subject_id.map(|sid| {
-            if is_resource(sid) {
-                // Assuming is_resource returns a Result<String, Error>
-                sid.parse::<serde_json::Value>()
-                    .ok()
-                    .and_then(|v| v.get("id").and_then(|v| v.as_str()).map(|s| s.to_string()))
-                    .unwrap_or_else(|| sid.to_string())
-            } else {
-                sid.to_string()
-            }
-        });
-
-        let url = match subject_id {
-            Some(sid) => format!("{}/{}/capture-signature/{}", self.namespace, id, sid),
-            None => format!("{}/{}/capture-signature", self.namespace, id),
-        };
+        params: &B,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError>
+    where
+        B: Serialize + ?Sized,
+    {
+        let subject_id = subject_id.map(resource_id).transpose()?;
+        self.post(
+            Orders::OrdersCaptureQr(id.to_string(), subject_id),
+            params,
+            options,
+        )
+        .await
+    }
 
-        self.adapter
-            .post(&url)
-            .json(&params)
-            .send()
-            .await?
-            .json()
-            .await
-            .map_err(Into::into)
+    /// `params` is sent as-is, e.g. `{"signature": <base64>}`.
+    pub async fn capture_signature<B>(
+        &self,
+        id: &str,
+        subject_id: Option<&str>,
+        params: &B,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError>
+    where
+        B: Serialize + ?Sized,
+    {
+        let subject_id = subject_id.map(resource_id).transpose()?;
+        self.post(
+            Orders::OrdersCaptureSignature(id.to_string(), subject_id),
+            params,
+            options,
+        )
+        .await
     }
 
-    async fn complete(
+    pub async fn complete(
         &self,
         id: &str,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        self.adapter
-            .post(&format!("{}/{}/complete", self.namespace, id))
-            .json(&params)
-            .send()
-            .await?
-            .json()
+        params: &CompleteParams,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.post(Orders::OrdersComplete(id.to_string()), params, options)
             .await
-            .map_err(Into::into)
     }
 
-    async fn cancel(
+    pub async fn cancel(
         &self,
         id: &str,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        params: &CancelParams,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
         self.adapter
-            .delete(&format!("{}/{}/cancel", self.namespace, id))
-            .json(&params)
-            .send()
-            .await?
-            .json()
+            .request_with_options(
+                Method::DELETE,
+                Endpoint::Orders(Orders::OrdersCancel(id.to_string())),
+                None,
+                Some(params),
+                options,
+            )
             .await
-            .map_err(Into::into)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Accepts either a bare id or a serialized resource and returns the id.
+fn resource_id(value: &str) -> Result<String, FleetbaseError> {
+    if !is_resource(value) {
+        return Ok(value.to_string());
+    }
+    serde_json::from_str::<serde_json::Value>(value)
+        .map_err(|e| FleetbaseError::Validation(format!("invalid resource: {}", e)))?
+        .get("id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| FleetbaseError::Validation("resource is missing an id".to_string()))
+}
+
+/// Where an order is in its lifecycle. Statuses this crate does not know
+/// about, such as custom activity codes, are kept as `Unknown`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum OrderStatus {
+    #[default]
+    Created,
+    Dispatched,
+    DriverEnroute,
+    Enroute,
+    Completed,
+    Canceled,
+    Unknown(String),
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            OrderStatus::Created => "created",
+            OrderStatus::Dispatched => "dispatched",
+            OrderStatus::DriverEnroute => "driver_enroute",
+            OrderStatus::Enroute => "enroute",
+            OrderStatus::Completed => "completed",
+            OrderStatus::Canceled => "canceled",
+            OrderStatus::Unknown(status) => status,
+        }
+    }
+
+    /// Completed and canceled orders accept no further actions.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, OrderStatus::Completed | OrderStatus::Canceled)
+    }
+}
+
+impl From<&str> for OrderStatus {
+    fn from(status: &str) -> Self {
+        match status {
+            "created" => OrderStatus::Created,
+            "dispatched" => OrderStatus::Dispatched,
+            "driver_enroute" => OrderStatus::DriverEnroute,
+            "enroute" => OrderStatus::Enroute,
+            "completed" => OrderStatus::Completed,
+            // Fleetbase has used both spellings.
+            "canceled" | "cancelled" => OrderStatus::Canceled,
+            other => OrderStatus::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for OrderStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for OrderStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(String::deserialize(deserializer)?.as_str().into())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
+    pub id: String,
+    #[serde(default)]
+    pub status: OrderStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Payload>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub driver_assigned: Option<Driver>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vehicle_assigned: Option<Vehicle>,
+    #[serde(
+        default,
+        with = "timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub scheduled_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        with = "timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub dispatched_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        with = "timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        with = "timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        with = "timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Value>,
+    /// Fields not modelled above, kept so the order round-trips.
     #[serde(flatten)]
-    resource: Resource,
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    #[serde(skip)]
+    adapter: Option<FleetbaseClient>,
 }
 
 impl Order {
     pub fn new(
         attributes: serde_json::Value,
-        adapter: reqwest::Client,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(Self {
-            resource: Resource::new(attributes, adapter, "order")?,
-        })
+        adapter: FleetbaseClient,
+    ) -> Result<Self, FleetbaseError> {
+        let body = attributes.to_string();
+        let mut order: Order = serde_json::from_value(attributes)
+            .map_err(|source| FleetbaseError::Deserialize { source, body })?;
+        order.adapter = Some(adapter);
+        Ok(order)
     }
 
-    pub async fn get_distance_and_time(
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn actions(&self) -> Result<OrderActions, FleetbaseError> {
+        let client = self.adapter.as_ref().ok_or_else(|| {
+            FleetbaseError::Validation(format!("order {} is not attached to a client", self.id))
+        })?;
+        Ok(OrderActions::new(client.clone()))
+    }
+
+    pub fn lifecycle(&self) -> OrderLifecycle {
+        OrderLifecycle::new(self.status.clone())
+    }
+
+    /// Fails before any request is sent when `transition` is not legal from
+    /// the current status.
+    fn check(
         &self,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
+        transition: OrderTransition,
+        skip_dispatch: bool,
+    ) -> Result<(), FleetbaseError> {
+        self.lifecycle()
+            .skip_dispatch(skip_dispatch)
+            .check(&self.id, transition)
+    }
+
+    /// Replaces this order with the one in `response` when the server sent
+    /// it back, otherwise moves to the expected status.
+    fn apply_response(
+        &mut self,
+        transition: OrderTransition,
+        response: serde_json::Value,
+        expected: Option<OrderStatus>,
+    ) -> Result<(), FleetbaseError> {
+        if response.get("id").and_then(|id| id.as_str()) == Some(self.id.as_str()) {
+            let body = response.to_string();
+            let mut updated: Order = serde_json::from_value(response)
+                .map_err(|source| FleetbaseError::Deserialize { source, body })?;
+            updated.adapter = self.adapter.take();
+            *self = updated;
+            return Ok(());
         }
-        .get_distance_and_time(&self.resource.id, params, options)
-        .await
+
+        if let Some(status) = expected {
+            self.status = status;
+        }
+        match transition {
+            OrderTransition::Dispatch => {
+                self.dispatched_at.get_or_insert_with(Utc::now);
+            }
+            OrderTransition::Start => {
+                self.started_at.get_or_insert_with(Utc::now);
+            }
+            _ => {}
+        }
+        Ok(())
     }
 
     pub async fn dispatch(
-        &self,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
-        }
-        .dispatch(&self.resource.id, params, options)
-        .await
+        &mut self,
+        params: &DispatchParams,
+        options: &RequestOptions,
+    ) -> Result<(), FleetbaseError> {
+        let transition = OrderTransition::Dispatch;
+        self.check(transition, false)?;
+        let response = self.actions()?.dispatch(&self.id, params, options).await?;
+        self.apply_response(transition, response, OrderLifecycle::target(transition))
     }
 
     pub async fn start(
+        &mut self,
+        params: &StartParams,
+        options: &RequestOptions,
+    ) -> Result<(), FleetbaseError> {
+        let transition = OrderTransition::Start;
+        self.check(transition, params.skip_dispatch)?;
+        let response = self.actions()?.start(&self.id, params, options).await?;
+        self.apply_response(transition, response, OrderLifecycle::target(transition))
+    }
+
+    /// Moves the order to the activity named by `params.code`, or to the
+    /// next activity in its flow when no code is given.
+    pub async fn update_activity(
+        &mut self,
+        params: &UpdateActivityParams,
+        options: &RequestOptions,
+    ) -> Result<(), FleetbaseError> {
+        let transition = OrderTransition::UpdateActivity;
+        self.check(transition, params.skip_dispatch)?;
+        let response = self
+            .actions()?
+            .update_activity(&self.id, params, options)
+            .await?;
+        let expected = params.code.as_deref().map(OrderStatus::from);
+        self.apply_response(transition, response, expected)
+    }
+
+    pub async fn complete(
+        &mut self,
+        params: &CompleteParams,
+        options: &RequestOptions,
+    ) -> Result<(), FleetbaseError> {
+        let transition = OrderTransition::Complete;
+        self.check(transition, false)?;
+        let response = self.actions()?.complete(&self.id, params, options).await?;
+        self.apply_response(transition, response, OrderLifecycle::target(transition))
+    }
+
+    pub async fn cancel(
+        &mut self,
+        params: &CancelParams,
+        options: &RequestOptions,
+    ) -> Result<(), FleetbaseError> {
+        let transition = OrderTransition::Cancel;
+        self.check(transition, false)?;
+        let response = self.actions()?.cancel(&self.id, params, options).await?;
+        self.apply_response(transition, response, OrderLifecycle::target(transition))
+    }
+
+    pub async fn get_next_activity(
         &self,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
-        }
-        .start(&self.resource.id, params, options)
-        .await
+        options: &RequestOptions,
+    ) -> Result<Vec<Activity>, FleetbaseError> {
+        let response = self
+            .actions()?
+            .get_next_activity(&self.id, &HashMap::new(), options)
+            .await?;
+        Activity::from_response(response)
     }
 
-    pub async fn set_destination(
+    pub async fn get_distance_and_time(
         &self,
-        destination_id: &str,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
-        }
-        .set_destination(&self.resource.id, destination_id, params, options)
-        .await
+        query: &HashMap<String, String>,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.actions()?
+            .get_distance_and_time(&self.id, query, options)
+            .await
     }
 
-    pub async fn capture_qr_code(
+    pub async fn set_destination(
         &self,
-        subject_id: Option<&str>,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
-        }
-        .capture_qr_code(&self.resource.id, subject_id, params, options)
-        .await
+        destination_id: &str,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError> {
+        self.actions()?
+            .set_destination(&self.id, destination_id, options)
+            .await
     }
 
-    pub async fn capture_signature(
+    pub async fn capture_qr_code<B>(
         &self,
         subject_id: Option<&str>,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
-        }
-        .capture_signature(&self.resource.id, subject_id, params, options)
-        .await
+        params: &B,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError>
+    where
+        B: Serialize + ?Sized,
+    {
+        self.actions()?
+            .capture_qr_code(&self.id, subject_id, params, options)
+            .await
     }
 
-    pub async fn get_next_activity(
+    pub async fn capture_signature<B>(
         &self,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
-        }
-        .get_next_activity(&self.resource.id, params, options)
-        .await
+        subject_id: Option<&str>,
+        params: &B,
+        options: &RequestOptions,
+    ) -> Result<serde_json::Value, FleetbaseError>
+    where
+        B: Serialize + ?Sized,
+    {
+        self.actions()?
+            .capture_signature(&self.id, subject_id, params, options)
+            .await
     }
 
-    pub async fn update_activity(
-        &self,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
-        }
-        .update_activity(&self.resource.id, params, options)
-        .await
+    pub fn pickup(&self) -> Option<&Place> {
+        self.payload.as_ref()?.pickup.as_ref()
     }
 
-    pub async fn cancel(
-        &self,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
-        }
-        .cancel(&self.resource.id, params, options)
-        .await
+    pub fn dropoff(&self) -> Option<&Place> {
+        self.payload.as_ref()?.dropoff.as_ref()
     }
 
-    pub async fn complete(
-        &self,
-        params: HashMap<String, String>,
-        options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        OrderActions {
-            adapter: self.resource.adapter.clone(),
-            namespace: "orders".to_string(),
-        }
-        .complete(&self.resource.id, params, options)
-        .await
+    pub fn waypoints(&self) -> &[Place] {
+        self.payload
+            .as_ref()
+            .map(|payload| payload.waypoints.as_slice())
+            .unwrap_or_default()
     }
 
     pub fn is_dispatched(&self) -> bool {
-        self.resource
-            .get_attribute::<Option<String>>("dispatched_at")
-            .is_some()
+        self.dispatched_at.is_some()
     }
 
     pub fn is_not_dispatched(&self) -> bool {
-        self.resource
-            .get_attribute::<Option<String>>("dispatched_at")
-            .is_none()
+        self.dispatched_at.is_none()
     }
 
     pub fn is_started(&self) -> bool {
-        self.resource
-            .get_attribute::<Option<String>>("started_at")
-            .is_some()
+        self.started_at.is_some()
     }
 
     pub fn is_not_started(&self) -> bool {
-        self.resource
-            .get_attribute::<Option<String>>("started_at")
-            .is_none()
+        self.started_at.is_none()
     }
 
     pub fn is_completed(&self) -> bool {
-        self.resource.get_attribute::<String>("status") == Some("completed".to_string())
+        self.status == OrderStatus::Completed
     }
 
     pub fn is_canceled(&self) -> bool {
-        self.resource.get_attribute::<String>("status") == Some("canceled".to_string())
+        self.status == OrderStatus::Canceled
     }
 
     pub fn is_enroute(&self) -> bool {
-        let status = self.resource.get_attribute::<String>("status");
-        status == Some("driver_enroute".to_string()) || status == Some("enroute".to_string())
+        matches!(
+            self.status,
+            OrderStatus::DriverEnroute | OrderStatus::Enroute
+        )
     }
 
     pub fn is_in_progress(&self) -> bool {
-        self.is_started() && !self.is_canceled() && !self.is_completed()
+        self.is_started() && !self.status.is_terminal()
     }
+}
 
-    pub fn scheduled_at(&self) -> Option<DateTime<Utc>> {
-        self.resource
-            .get_attribute::<String>("scheduled_at")
-            .and_then(|s| s.parse().ok())
+impl ApiResource for Order {
+    fn collection() -> Endpoint {
+        Endpoint::Orders(Orders::Orders)
     }
 
-    pub fn started_at(&self) -> Option<DateTime<Utc>> {
-        self.resource
-            .get_attribute::<String>("started_at")
-            .and_then(|s| s.parse().ok())
+    fn member(id: &str) -> Endpoint {
+        Endpoint::Orders(Orders::OrdersById(id.to_string()))
     }
 
-    pub fn dispatched_at(&self) -> Option<DateTime<Utc>> {
-        self.resource
-            .get_attribute::<String>("dispatched_at")
-            .and_then(|s| s.parse().ok())
+    fn attach(&mut self, client: &FleetbaseClient) {
+        self.adapter = Some(client.clone());
     }
+}
 
-    pub fn status(&self) -> Option<String> {
-        self.resource.get_attribute("status")
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_order_round_trips_unknown_fields() {
+        let value = json!({
+            "id": "order_1",
+            "status": "driver_enroute",
+            "payload": {
+                "id": "payload_1",
+                "pickup": {"id": "place_a", "name": "Warehouse"},
+                "dropoff": {"id": "place_b", "name": "Customer"},
+                "waypoints": [{"id": "place_c"}],
+                "cod_amount": 1200
+            },
+            "scheduled_at": "2024-03-01 09:30:00",
+            "dispatched_at": "2024-03-01T09:00:00Z",
+            "started_at": null,
+            "meta": {"priority": "high"},
+            "tracking_number": {"tracking_number": "ABC123"},
+            "internal_id": "INV-42"
+        });
+
+        let order: Order = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(order.status, OrderStatus::DriverEnroute);
+        assert!(order.is_enroute() && order.is_dispatched() && !order.is_started());
+        assert_eq!(order.pickup().unwrap().name.as_deref(), Some("Warehouse"));
+        assert_eq!(order.waypoints().len(), 1);
+        assert_eq!(order.extra["internal_id"], "INV-42");
+
+        let round_trip = serde_json::to_value(&order).unwrap();
+        assert_eq!(round_trip["internal_id"], "INV-42");
+        assert_eq!(round_trip["payload"]["cod_amount"], 1200);
+        assert_eq!(round_trip["scheduled_at"], "2024-03-01T09:30:00Z");
+        assert_eq!(round_trip["tracking_number"], value["tracking_number"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_activity_skipping_dispatch() {
+        use crate::options::RequestOptions;
+        use crate::order_params::UpdateActivityParams;
+        use crate::transport::{HttpResponse, MemoryTransport};
+        use reqwest::StatusCode;
+        use std::sync::Arc;
+
+        let transport = Arc::new(MemoryTransport::new());
+        let client = FleetbaseClient::builder("test_token")
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        let order_with = |status: &str| {
+            let mut order: Order =
+                serde_json::from_value(json!({"id": "order_1", "status": status})).unwrap();
+            order.attach(&client);
+            order
+        };
+        let options = RequestOptions::default();
+        let params = UpdateActivityParams::new().code("enroute");
+
+        let mut created = order_with("created");
+        assert!(matches!(
+            created.update_activity(&params, &options).await,
+            Err(FleetbaseError::InvalidTransition { .. })
+        ));
+        assert!(transport.requests().is_empty());
+
+        transport.push(HttpResponse::json(StatusCode::OK, json!({"status": "ok"})));
+        created
+            .update_activity(&params.clone().skip_dispatch(true), &options)
+            .await
+            .unwrap();
+        assert_eq!(created.status, OrderStatus::Enroute);
+        let request = transport.last_request().unwrap();
+        assert_eq!(request.path(), "/v1/orders/order_1/update-activity");
+        assert_eq!(request.json().unwrap()["skip_dispatch"], true);
+
+        transport.push(HttpResponse::json(StatusCode::OK, json!({"status": "ok"})));
+        let mut dispatched = order_with("dispatched");
+        dispatched.update_activity(&params, &options).await.unwrap();
+        assert_eq!(dispatched.status, OrderStatus::Enroute);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_status_is_preserved() {
+        let status: OrderStatus = serde_json::from_value(json!("at_customs")).unwrap();
+        assert_eq!(status, OrderStatus::Unknown("at_customs".to_string()));
+        assert_eq!(serde_json::to_value(&status).unwrap(), json!("at_customs"));
+        assert_eq!(OrderStatus::from("cancelled"), OrderStatus::Canceled);
     }
 }
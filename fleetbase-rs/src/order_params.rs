@@ -0,0 +1,151 @@
+use serde::Serialize;
+
+use crate::geo::Point;
+
+/// Evidence captured when an activity is updated or an order completed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Proof {
+    /// Base64-encoded signature image.
+    Signature {
+        data: String,
+    },
+    /// Base64-encoded photo.
+    Photo {
+        data: String,
+    },
+    QrCode {
+        code: String,
+    },
+    /// A proof already uploaded through `capture-signature`/`capture-qr`.
+    Reference {
+        id: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DispatchParams {
+    /// Assigns this driver while dispatching.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl DispatchParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn driver(mut self, driver_id: impl Into<String>) -> Self {
+        self.driver = Some(driver_id.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StartParams {
+    /// Starts an order that has not been dispatched yet.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skip_dispatch: bool,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl StartParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn skip_dispatch(mut self, skip: bool) -> Self {
+        self.skip_dispatch = skip;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateActivityParams {
+    /// Activity to move to; the next one in the order's flow when `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Where the driver was when the activity happened.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Point>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<Proof>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skip_dispatch: bool,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl UpdateActivityParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn location(mut self, location: Point) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    pub fn proof(mut self, proof: Proof) -> Self {
+        self.proof = Some(proof);
+        self
+    }
+
+    pub fn skip_dispatch(mut self, skip: bool) -> Self {
+        self.skip_dispatch = skip;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompleteParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<Proof>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl CompleteParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn proof(mut self, proof: Proof) -> Self {
+        self.proof = Some(proof);
+        self
+    }
+
+    pub fn notes(mut self, notes: impl Into<String>) -> Self {
+        self.notes = Some(notes.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CancelParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl CancelParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
@@ -1,21 +1,162 @@
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::resource::Resource;
+use crate::client::FleetbaseClient;
+use crate::error::FleetbaseError;
+use crate::utils::timestamp;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The user who owns an organization. Fleetbase sends either the full user
+/// or just its id.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrganizationOwner {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl<'de> Deserialize<'de> for OrganizationOwner {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct User {
+            id: String,
+            #[serde(default)]
+            name: Option<String>,
+            #[serde(default)]
+            email: Option<String>,
+            #[serde(default)]
+            phone: Option<String>,
+            #[serde(flatten)]
+            extra: serde_json::Map<String, serde_json::Value>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Id(String),
+            User(User),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Id(id) => OrganizationOwner {
+                id,
+                name: None,
+                email: None,
+                phone: None,
+                extra: serde_json::Map::new(),
+            },
+            Repr::User(user) => OrganizationOwner {
+                id: user.id,
+                name: user.name,
+                email: user.email,
+                phone: user.phone,
+                extra: user.extra,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Organization {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<OrganizationOwner>,
+    #[serde(
+        default,
+        with = "timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        with = "timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Fields not modelled above, kept so the organization round-trips.
     #[serde(flatten)]
-    resource: Resource,
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    #[serde(skip)]
+    adapter: Option<FleetbaseClient>,
 }
 
 impl Organization {
     pub fn new(
         attributes: serde_json::Value,
-        adapter: Client,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(Self {
-            resource: Resource::new(attributes, adapter, "organization")?,
-        })
+        adapter: FleetbaseClient,
+    ) -> Result<Self, FleetbaseError> {
+        let body = attributes.to_string();
+        let mut organization: Organization = serde_json::from_value(attributes)
+            .map_err(|source| FleetbaseError::Deserialize { source, body })?;
+        organization.adapter = Some(adapter);
+        Ok(organization)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// A client scoped to this organization; see
+    /// `FleetbaseClient::in_organization`.
+    pub fn client(&self) -> Result<FleetbaseClient, FleetbaseError> {
+        self.adapter
+            .as_ref()
+            .ok_or_else(|| {
+                FleetbaseError::Validation("organization is not attached to a client".to_string())
+            })?
+            .in_organization(&self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_owner_as_object_or_id() {
+        let client = FleetbaseClient::new("test_token".to_string());
+        let org = Organization::new(
+            json!({
+                "id": "org_1",
+                "name": "Acme Logistics",
+                "slug": "acme",
+                "timezone": "Asia/Singapore",
+                "currency": "SGD",
+                "owner": {"id": "user_1", "name": "Ada", "email": "ada@example.com"},
+                "created_at": "2024-01-02 03:04:05",
+                "logo_url": "https://example.com/logo.png"
+            }),
+            client.clone(),
+        )
+        .unwrap();
+        assert_eq!(org.slug.as_deref(), Some("acme"));
+        assert_eq!(
+            org.owner.as_ref().unwrap().email.as_deref(),
+            Some("ada@example.com")
+        );
+        assert!(org.created_at.is_some());
+        assert_eq!(
+            serde_json::to_value(&org).unwrap()["logo_url"],
+            "https://example.com/logo.png"
+        );
+
+        let org = Organization::new(json!({"id": "org_2", "owner": "user_2"}), client).unwrap();
+        assert_eq!(org.owner.unwrap().id, "user_2");
     }
 }
@@ -0,0 +1,346 @@
+use futures::stream::{self, Stream};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+
+use crate::client::FleetbaseClient;
+use crate::error::FleetbaseError;
+use crate::query::ListQuery;
+use crate::utils::enpdpoints::Endpoint;
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// The `meta` block Fleetbase attaches to paginated list responses.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageMeta {
+    pub current_page: Option<u64>,
+    pub last_page: Option<u64>,
+    pub per_page: Option<u64>,
+    pub total: Option<u64>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub meta: Option<PageMeta>,
+}
+
+impl<T: DeserializeOwned> Page<T> {
+    /// Accepts a bare array, `{"data": [...], "meta": {...}}`, or Fleetbase's
+    /// resource-keyed form such as `{"orders": [...], "meta": {...}}`, where
+    /// `key` is `orders`. Any other object is an error.
+    pub fn from_value(value: serde_json::Value, key: &str) -> Result<Self, FleetbaseError> {
+        let body = value.to_string();
+        let (items, meta) = match value {
+            serde_json::Value::Array(items) => (items, None),
+            serde_json::Value::Object(mut object) => {
+                let meta = match object.remove("meta") {
+                    Some(meta) => Some(serde_json::from_value(meta).map_err(|source| {
+                        FleetbaseError::Deserialize {
+                            source,
+                            body: body.clone(),
+                        }
+                    })?),
+                    None => None,
+                };
+                let items = match object.remove("data").or_else(|| object.remove(key)) {
+                    Some(serde_json::Value::Array(items)) => items,
+                    _ => {
+                        return Err(FleetbaseError::Validation(format!(
+                            "expected a `data` or `{}` list, got {}",
+                            key, body
+                        )))
+                    }
+                };
+                (items, meta)
+            }
+            _ => {
+                return Err(FleetbaseError::Validation(format!(
+                    "expected a list response, got {}",
+                    body
+                )))
+            }
+        };
+
+        let items = items
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<Vec<T>, _>>()
+            .map_err(|source| FleetbaseError::Deserialize { source, body })?;
+
+        Ok(Self { items, meta })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum NextPage {
+    Page(u64),
+    Cursor(String),
+    Done,
+}
+
+/// Walks a list endpoint page by page, following either `meta.next_cursor`
+/// or `meta.current_page`/`meta.last_page`. Responses without `meta` are
+/// assumed to continue while full pages come back.
+pub struct Paginator<T> {
+    client: FleetbaseClient,
+    endpoint: Endpoint,
+    query: HashMap<String, String>,
+    per_page: u32,
+    next: NextPage,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Paginator<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    pub fn new(client: FleetbaseClient, endpoint: Endpoint) -> Self {
+        Self {
+            client,
+            endpoint,
+            query: HashMap::new(),
+            per_page: DEFAULT_PAGE_SIZE,
+            next: NextPage::Page(1),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = per_page.max(1);
+        self
+    }
+
+    pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.insert(key.into(), value.into());
+        self
+    }
+
+    /// Applies filters, sorting and includes. `limit` becomes the page size
+    /// and `page` the starting page.
+    pub fn list_query(mut self, query: &ListQuery) -> Self {
+        let mut params = query.to_params();
+        if let Some(limit) = params.remove("limit").and_then(|l| l.parse().ok()) {
+            self = self.per_page(limit);
+        }
+        if let Some(page) = params.remove("page").and_then(|p| p.parse().ok()) {
+            self.next = NextPage::Page(page);
+        }
+        self.query.extend(params);
+        self
+    }
+
+    pub async fn next_page(&mut self) -> Result<Option<Vec<T>>, FleetbaseError> {
+        let mut query = self.query.clone();
+        query.insert("limit".to_string(), self.per_page.to_string());
+        match &self.next {
+            NextPage::Done => return Ok(None),
+            NextPage::Page(page) => {
+                query.insert("page".to_string(), page.to_string());
+            }
+            NextPage::Cursor(cursor) => {
+                query.insert("cursor".to_string(), cursor.clone());
+            }
+        }
+
+        let value: serde_json::Value = self
+            .client
+            .get_with_query(self.endpoint.clone(), &query)
+            .await?;
+        let page = Page::<T>::from_value(value, &self.endpoint.resource_key())?;
+        self.next = self.following(&page);
+
+        Ok(Some(page.items))
+    }
+
+    fn following(&self, page: &Page<T>) -> NextPage {
+        if page.items.is_empty() {
+            return NextPage::Done;
+        }
+
+        let Some(meta) = &page.meta else {
+            return self.guess_following(page.items.len());
+        };
+        if let Some(cursor) = &meta.next_cursor {
+            return NextPage::Cursor(cursor.clone());
+        }
+        match (meta.current_page, meta.last_page) {
+            (Some(current), Some(last)) if current < last => NextPage::Page(current + 1),
+            (Some(_), Some(_)) => NextPage::Done,
+            _ => self.guess_following(page.items.len()),
+        }
+    }
+
+    fn guess_following(&self, len: usize) -> NextPage {
+        match self.next {
+            NextPage::Page(page) if len >= self.per_page as usize => NextPage::Page(page + 1),
+            _ => NextPage::Done,
+        }
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Result<T, FleetbaseError>> + Send {
+        stream::try_unfold(
+            (self, VecDeque::new()),
+            |(mut paginator, mut buffer)| async move {
+                loop {
+                    if let Some(item) = buffer.pop_front() {
+                        return Ok(Some((item, (paginator, buffer))));
+                    }
+                    match paginator.next_page().await? {
+                        Some(items) if !items.is_empty() => buffer.extend(items),
+                        _ => return Ok(None),
+                    }
+                }
+            },
+        )
+    }
+
+    /// Fetches every page. Fails instead of growing without bound once more
+    /// than `max_items` have been returned.
+    pub async fn collect_all(mut self, max_items: usize) -> Result<Vec<T>, FleetbaseError> {
+        let mut all = Vec::new();
+        while let Some(items) = self.next_page().await? {
+            if items.is_empty() {
+                break;
+            }
+            all.extend(items);
+            if all.len() > max_items {
+                return Err(FleetbaseError::Validation(format!(
+                    "{} returned more than {} items",
+                    self.endpoint, max_items
+                )));
+            }
+        }
+        Ok(all)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpResponse, MemoryTransport};
+    use crate::utils::enpdpoints::Orders;
+    use futures::StreamExt;
+    use reqwest::StatusCode;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_page_from_value_shapes() {
+        let page =
+            Page::<serde_json::Value>::from_value(json!([{"id": "a"}, {"id": "b"}]), "orders")
+                .unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert!(page.meta.is_none());
+
+        let page = Page::<serde_json::Value>::from_value(
+            json!({
+                "orders": [{"id": "order_1"}],
+                "meta": {"current_page": 1, "last_page": 3, "per_page": 1, "total": 3}
+            }),
+            "orders",
+        )
+        .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.meta.unwrap().last_page, Some(3));
+
+        let page = Page::<serde_json::Value>::from_value(
+            json!({
+                "data": [{"id": "a"}],
+                "meta": {"next_cursor": "abc"}
+            }),
+            "orders",
+        )
+        .unwrap();
+        assert_eq!(page.meta.unwrap().next_cursor.as_deref(), Some("abc"));
+
+        // An error list is not mistaken for the records.
+        assert!(Page::<serde_json::Value>::from_value(
+            json!({"errors": ["Too many filters"], "links": []}),
+            "orders"
+        )
+        .is_err());
+    }
+
+    fn scripted(responses: Vec<serde_json::Value>) -> (Arc<MemoryTransport>, Paginator<Value>) {
+        let transport = Arc::new(MemoryTransport::new());
+        for response in responses {
+            transport.push(HttpResponse::json(StatusCode::OK, response));
+        }
+        let client = FleetbaseClient::builder("test_token")
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        let paginator = client
+            .paginate(Endpoint::Orders(Orders::Orders))
+            .per_page(2);
+        (transport, paginator)
+    }
+
+    fn queries(transport: &MemoryTransport) -> Vec<String> {
+        transport
+            .requests()
+            .iter()
+            .map(|r| r.query().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_follows_page_numbers_until_last_page() {
+        let (transport, paginator) = scripted(vec![
+            json!({"orders": [{"id": 1}, {"id": 2}], "meta": {"current_page": 1, "last_page": 3}}),
+            json!({"orders": [{"id": 3}, {"id": 4}], "meta": {"current_page": 2, "last_page": 3}}),
+            json!({"orders": [{"id": 5}, {"id": 6}], "meta": {"current_page": 3, "last_page": 3}}),
+            json!({"orders": [{"id": 7}], "meta": {"current_page": 4, "last_page": 3}}),
+        ]);
+        let ids: Vec<Value> = paginator
+            .into_stream()
+            .map(|item| item.unwrap()["id"].clone())
+            .collect()
+            .await;
+        assert_eq!(ids, (1..=6).map(|id| json!(id)).collect::<Vec<_>>());
+        assert_eq!(
+            queries(&transport),
+            ["limit=2&page=1", "limit=2&page=2", "limit=2&page=3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_follows_cursors() {
+        let (transport, mut paginator) = scripted(vec![
+            json!({"data": [{"id": 1}, {"id": 2}], "meta": {"next_cursor": "c2"}}),
+            json!({"data": [{"id": 3}, {"id": 4}], "meta": {"next_cursor": "c3"}}),
+            json!({"data": [{"id": 5}], "meta": {}}),
+        ]);
+        let mut pages = Vec::new();
+        while let Some(page) = paginator.next_page().await.unwrap() {
+            pages.push(page.len());
+        }
+        assert_eq!(pages, [2, 2, 1]);
+        assert_eq!(
+            queries(&transport),
+            ["limit=2&page=1", "cursor=c2&limit=2", "cursor=c3&limit=2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_all_stops_at_the_cap() {
+        let (_, paginator) = scripted(vec![
+            json!([{"id": 1}, {"id": 2}]),
+            json!([{"id": 3}, {"id": 4}]),
+            json!([{"id": 5}]),
+        ]);
+        let error = paginator.collect_all(3).await.unwrap_err();
+        assert!(error.to_string().contains("more than 3 items"), "{}", error);
+
+        let (transport, paginator) = scripted(vec![
+            json!([{"id": 1}, {"id": 2}]),
+            json!([{"id": 3}, {"id": 4}]),
+            json!([{"id": 5}]),
+        ]);
+        assert_eq!(paginator.collect_all(5).await.unwrap().len(), 5);
+        assert_eq!(transport.requests().len(), 3);
+    }
+}
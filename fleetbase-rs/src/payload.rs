@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use crate::place::Place;
+
+/// What an order carries and where: pickup, dropoff, an optional return
+/// location and any intermediate waypoints.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Payload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pickup: Option<Place>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dropoff: Option<Place>,
+    #[serde(rename = "return", default, skip_serializing_if = "Option::is_none")]
+    pub return_: Option<Place>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub waypoints: Vec<Place>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Value>,
+    /// Fields not modelled above, kept so the payload round-trips.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Payload {
+    /// Pickup, waypoints and dropoff in travel order.
+    pub fn stops(&self) -> impl Iterator<Item = &Place> {
+        self.pickup
+            .iter()
+            .chain(self.waypoints.iter())
+            .chain(self.dropoff.iter())
+    }
+}
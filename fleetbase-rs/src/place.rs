@@ -1,26 +1,35 @@
+use crate::client::FleetbaseClient;
+use crate::error::FleetbaseError;
+use crate::geo::Point;
+use crate::geocoder::{Address, Geocoder};
 use crate::resource::Resource;
-use crate::utils::{is_resource, Point};
+use crate::utils::is_resource;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Place {
-    name: Option<String>,
-    address: Option<String>,
-    location: Option<Point>,
-    street1: Option<String>,
-    street2: Option<String>,
-    city: Option<String>,
-    province: Option<String>,
-    postal_code: Option<String>,
-    neighborhood: Option<String>,
-    district: Option<String>,
-    building: Option<String>,
-    country: Option<String>,
-    phone: Option<String>,
-    security_access_code: Option<String>,
-    website: Option<String>,
-    description: Option<String>,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub address: Option<String>,
+    pub location: Option<Point>,
+    pub street1: Option<String>,
+    pub street2: Option<String>,
+    pub city: Option<String>,
+    pub province: Option<String>,
+    pub postal_code: Option<String>,
+    pub neighborhood: Option<String>,
+    pub district: Option<String>,
+    pub building: Option<String>,
+    pub country: Option<String>,
+    pub phone: Option<String>,
+    pub security_access_code: Option<String>,
+    pub website: Option<String>,
+    pub description: Option<String>,
 }
+
+/// How far outside its geocoded address a place's `location` may lie.
+pub const ADDRESS_TOLERANCE_METERS: f64 = 250.0;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PlaceResource {
     #[serde(flatten)]
@@ -30,9 +39,9 @@ pub struct PlaceResource {
 impl PlaceResource {
     pub fn new(
         attributes: serde_json::Value,
-        adapter: reqwest::Client,
-        options: Option<serde_json::Value>,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+        adapter: FleetbaseClient,
+        _options: Option<serde_json::Value>,
+    ) -> Result<Self, FleetbaseError> {
         Ok(Self {
             resource: Resource::new(attributes, adapter, "place")?,
         })
@@ -40,7 +49,7 @@ impl PlaceResource {
     /*
     pub fn from_google_address(
         google_address: &Resource,
-        adapter: reqwest::Client,
+        adapter: FleetbaseClient,
         options: Option<serde_json::Value>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let coordinates: Vec<f64> = google_address.get_attribute("coordinates")?;
@@ -68,19 +77,103 @@ impl PlaceResource {
     pub fn latitude(&self) -> Option<f64> {
         self.resource
             .get_attribute::<Point>("location")
-            .and_then(|point| point.coordinates.get(1).cloned())
+            .map(|point| point.latitude())
     }
 
     pub fn longitude(&self) -> Option<f64> {
         self.resource
             .get_attribute::<Point>("location")
-            .and_then(|point| point.coordinates.get(0).cloned())
+            .map(|point| point.longitude())
+    }
+
+    pub fn location(&self) -> Option<Point> {
+        self.resource.get_attribute::<Point>("location")
     }
 
     pub fn coordinates(&self) -> Option<(f64, f64)> {
         Some((self.latitude()?, self.longitude()?))
     }
 
+    /// `address` if set, otherwise the street, city, province, postal code
+    /// and country joined with commas.
+    pub fn address_line(&self) -> Option<String> {
+        let non_empty = |key: &str| {
+            self.resource
+                .get_attribute::<String>(key)
+                .filter(|value| !value.trim().is_empty())
+        };
+        if let Some(address) = non_empty("address") {
+            return Some(address);
+        }
+        let parts: Vec<String> = ["street1", "city", "province", "postal_code", "country"]
+            .into_iter()
+            .filter_map(non_empty)
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// Geocodes the address and checks the best match against the place's
+    /// `country` (a code or a name) and `location`, where set; checking the
+    /// location takes a reverse lookup too. Returns the match, e.g. to fill
+    /// in missing parts of the address.
+    pub async fn validate_address(
+        &self,
+        geocoder: &dyn Geocoder,
+    ) -> Result<Address, FleetbaseError> {
+        let query = self
+            .address_line()
+            .ok_or_else(|| FleetbaseError::Validation("place has no address".to_string()))?;
+        let address = geocoder
+            .geocode(&query)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                FleetbaseError::Validation(format!("address {:?} was not found", query))
+            })?;
+
+        if let (Some(expected), Some(found)) = (
+            self.resource.get_attribute::<String>("country"),
+            address.country.as_deref(),
+        ) {
+            let matches = expected.eq_ignore_ascii_case(found)
+                || address
+                    .country_name
+                    .as_deref()
+                    .is_some_and(|name| expected.eq_ignore_ascii_case(name));
+            if !matches {
+                return Err(FleetbaseError::Validation(format!(
+                    "address {:?} is in {}, not {}",
+                    query, found, expected
+                )));
+            }
+        }
+        if let Some(location) = self.location() {
+            // A country-level match covers its whole bounding box, so also
+            // check which country the location itself is in.
+            let located = geocoder
+                .reverse(&location)
+                .await?
+                .and_then(|address| address.country);
+            if let (Some(located), Some(found)) = (located, address.country.as_deref()) {
+                if located != found {
+                    return Err(FleetbaseError::Validation(format!(
+                        "location is in {}, but address {:?} is in {}",
+                        located, query, found
+                    )));
+                }
+            }
+            if !address.covers(&location, ADDRESS_TOLERANCE_METERS) {
+                return Err(FleetbaseError::Validation(format!(
+                    "location is {:.0} m from address {:?}",
+                    location.distance_to(&address.location),
+                    query
+                )));
+            }
+        }
+        Ok(address)
+    }
+
     pub fn set_owner(&mut self, owner: &str) -> &mut Self {
         if is_resource(owner) {
             if let Ok(owner_json) = serde_json::from_str::<serde_json::Value>(owner) {
@@ -94,3 +187,50 @@ impl PlaceResource {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geocoder::OfflineGeocoder;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_validate_address() {
+        let client = FleetbaseClient::new("test_token".to_string());
+        let geocoder = OfflineGeocoder::bundled();
+        let place = |attributes| PlaceResource::new(attributes, client.clone(), None).unwrap();
+
+        let marina = place(json!({
+            "id": "place_1",
+            "street1": "10 Bayfront Avenue",
+            "city": "Singapore",
+            "country": "SG",
+            "location": {"type": "Point", "coordinates": [103.8591, 1.2837]}
+        }));
+        assert_eq!(
+            marina.address_line().unwrap(),
+            "10 Bayfront Avenue, Singapore, SG"
+        );
+        let address = marina.validate_address(geocoder).await.unwrap();
+        assert_eq!(address.country_name.as_deref(), Some("Singapore"));
+
+        let misplaced = place(json!({
+            "id": "place_2",
+            "address": "Jalan Ampang, Kuala Lumpur, Malaysia",
+            "location": {"type": "Point", "coordinates": [103.8591, 1.2837]}
+        }));
+        let err = misplaced.validate_address(geocoder).await.unwrap_err();
+        assert!(err.to_string().contains("location is in SG"), "{}", err);
+
+        let wrong_country = place(json!({
+            "id": "place_3",
+            "address": "Orchard Road, Singapore",
+            "country": "Malaysia"
+        }));
+        assert!(wrong_country.validate_address(geocoder).await.is_err());
+        assert!(place(json!({"id": "place_4"}))
+            .validate_address(geocoder)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+/// Filters, sorting and relationship includes for list endpoints, serialized
+/// the way Fleetbase reads them: plain `key=value` filters, `sort` as a comma
+/// separated list with a `-` prefix for descending fields, and `with` as a
+/// comma separated list of relationships.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListQuery {
+    filters: BTreeMap<String, String>,
+    sort: Vec<String>,
+    with: Vec<String>,
+    search: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    page: Option<u32>,
+}
+
+impl ListQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn filter(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.filters.insert(key.into(), value.to_string());
+        self
+    }
+
+    /// Free-text search, sent as `query`.
+    pub fn search(mut self, term: impl Into<String>) -> Self {
+        self.search = Some(term.into());
+        self
+    }
+
+    pub fn sort_asc(mut self, field: impl Into<String>) -> Self {
+        self.sort.push(field.into());
+        self
+    }
+
+    pub fn sort_desc(mut self, field: impl Into<String>) -> Self {
+        self.sort.push(format!("-{}", field.into()));
+        self
+    }
+
+    pub fn with<I, S>(mut self, relations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with.extend(relations.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn created_between(self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        self.filter("created_after", timestamp(from))
+            .filter("created_before", timestamp(to))
+    }
+
+    pub fn updated_since(self, since: DateTime<Utc>) -> Self {
+        self.filter("updated_after", timestamp(since))
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    pub fn get_limit(&self) -> Option<u32> {
+        self.limit
+    }
+
+    pub fn to_params(&self) -> HashMap<String, String> {
+        let mut params: HashMap<String, String> = self
+            .filters
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        if !self.sort.is_empty() {
+            params.insert("sort".to_string(), self.sort.join(","));
+        }
+        if !self.with.is_empty() {
+            params.insert("with".to_string(), self.with.join(","));
+        }
+        if let Some(search) = &self.search {
+            params.insert("query".to_string(), search.clone());
+        }
+        if let Some(limit) = self.limit {
+            params.insert("limit".to_string(), limit.to_string());
+        }
+        if let Some(offset) = self.offset {
+            params.insert("offset".to_string(), offset.to_string());
+        }
+        if let Some(page) = self.page {
+            params.insert("page".to_string(), page.to_string());
+        }
+
+        params
+    }
+}
+
+impl From<ListQuery> for HashMap<String, String> {
+    fn from(query: ListQuery) -> Self {
+        query.to_params()
+    }
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_to_params() {
+        let from = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let params = ListQuery::new()
+            .filter("status", "active")
+            .sort_desc("created_at")
+            .sort_asc("name")
+            .with(["payload", "driver"])
+            .created_between(from, to)
+            .limit(25)
+            .to_params();
+
+        assert_eq!(params["status"], "active");
+        assert_eq!(params["sort"], "-created_at,name");
+        assert_eq!(params["with"], "payload,driver");
+        assert_eq!(params["created_after"], "2024-01-01T00:00:00Z");
+        assert_eq!(params["created_before"], "2024-02-01T00:00:00Z");
+        assert_eq!(params["limit"], "25");
+        assert!(!params.contains_key("page"));
+    }
+}
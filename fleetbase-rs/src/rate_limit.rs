@@ -0,0 +1,137 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "X-RateLimit-Remaining";
+pub const RATE_LIMIT_RESET_HEADER: &str = "X-RateLimit-Reset";
+
+/// Token bucket shared by every request made through a `FleetbaseClient`
+/// (and its clones). Tokens refill continuously at `requests_per_second`
+/// up to `burst`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+    blocked_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            requests_per_second: requests_per_second.max(f64::MIN_POSITIVE),
+            burst,
+            state: Mutex::new(BucketState {
+                tokens: burst,
+                last_refill: Instant::now(),
+                blocked_until: None,
+            }),
+        }
+    }
+
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+
+    pub fn burst(&self) -> u32 {
+        self.burst as u32
+    }
+
+    /// Waits until a token is available and takes it.
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a token if one is available, otherwise returns how long to wait
+    /// before trying again.
+    pub fn try_acquire(&self) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        if let Some(until) = state.blocked_until {
+            if until > now {
+                return Some(until - now);
+            }
+            state.blocked_until = None;
+        }
+
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.requests_per_second).min(self.burst);
+        state.last_refill = now;
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            None
+        } else {
+            let missing = 1.0 - state.tokens;
+            Some(Duration::from_secs_f64(missing / self.requests_per_second))
+        }
+    }
+
+    /// Adapts the bucket to the server's view of the quota. A lower
+    /// `X-RateLimit-Remaining` drains local tokens; an exhausted quota blocks
+    /// until `Retry-After` or `X-RateLimit-Reset` (epoch seconds).
+    pub fn update_from_headers(&self, headers: &HeaderMap) {
+        let Some(remaining) = header_u64(headers, RATE_LIMIT_REMAINING_HEADER) else {
+            return;
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.tokens = state.tokens.min(remaining as f64);
+
+        if remaining == 0 {
+            let wait = header_u64(headers, RETRY_AFTER.as_str())
+                .map(Duration::from_secs)
+                .or_else(|| {
+                    let reset = header_u64(headers, RATE_LIMIT_RESET_HEADER)?;
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+                    Some(Duration::from_secs(reset).saturating_sub(now))
+                });
+            if let Some(wait) = wait {
+                state.blocked_until = Some(Instant::now() + wait);
+            }
+        }
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_bucket_allows_burst_then_waits() {
+        let limiter = RateLimiter::new(2.0, 3);
+        assert!(limiter.try_acquire().is_none());
+        assert!(limiter.try_acquire().is_none());
+        assert!(limiter.try_acquire().is_none());
+
+        let wait = limiter.try_acquire().expect("bucket should be empty");
+        assert!(wait <= Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_server_remaining_drains_bucket() {
+        let limiter = RateLimiter::new(100.0, 10);
+        let mut headers = HeaderMap::new();
+        headers.insert(RATE_LIMIT_REMAINING_HEADER, HeaderValue::from_static("0"));
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("30"));
+        limiter.update_from_headers(&headers);
+
+        let wait = limiter.try_acquire().expect("server quota is exhausted");
+        assert!(wait > Duration::from_secs(29));
+    }
+}
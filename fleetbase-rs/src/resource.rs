@@ -1,26 +1,41 @@
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::client::FleetbaseClient;
+use crate::error::FleetbaseError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Resource {
     pub id: String,
     #[serde(flatten)]
     pub attributes: serde_json::Value,
+    /// `None` for resources that were deserialized rather than fetched
+    /// through a client.
     #[serde(skip)]
-    pub adapter: Client,
+    pub adapter: Option<FleetbaseClient>,
 }
 
 impl Resource {
     pub fn new(
         attributes: serde_json::Value,
-        adapter: Client,
+        adapter: FleetbaseClient,
         resource_type: &str,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let id = attributes["id"].as_str().ok_or("Missing id")?.to_string();
+    ) -> Result<Self, FleetbaseError> {
+        let id = attributes["id"]
+            .as_str()
+            .ok_or_else(|| {
+                FleetbaseError::Validation(format!("{} is missing an id", resource_type))
+            })?
+            .to_string();
         Ok(Self {
             id,
             attributes,
-            adapter,
+            adapter: Some(adapter),
+        })
+    }
+
+    pub fn client(&self) -> Result<&FleetbaseClient, FleetbaseError> {
+        self.adapter.as_ref().ok_or_else(|| {
+            FleetbaseError::Validation(format!("resource {} is not attached to a client", self.id))
         })
     }
 
@@ -0,0 +1,213 @@
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::error::FleetbaseError;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one; `1` disables retries.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    /// Longest wait before a retry. A `Retry-After` beyond it ends the
+    /// retries instead of being shortened.
+    pub max_delay: Duration,
+    /// Randomise each delay between half and the full backoff value.
+    pub jitter: bool,
+    pub retry_statuses: Vec<StatusCode>,
+    /// Methods replayed without an idempotency key. Anything else (POST,
+    /// PATCH) is only replayed when the request carries one.
+    pub idempotent_methods: Vec<Method>,
+    /// Replay connection failures and timeouts.
+    pub retry_transport_errors: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            retry_statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            idempotent_methods: vec![
+                Method::GET,
+                Method::HEAD,
+                Method::OPTIONS,
+                Method::PUT,
+                Method::DELETE,
+            ],
+            retry_transport_errors: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn retry_statuses(mut self, statuses: Vec<StatusCode>) -> Self {
+        self.retry_statuses = statuses;
+        self
+    }
+
+    pub fn idempotent_methods(mut self, methods: Vec<Method>) -> Self {
+        self.idempotent_methods = methods;
+        self
+    }
+
+    pub fn retry_transport_errors(mut self, retry: bool) -> Self {
+        self.retry_transport_errors = retry;
+        self
+    }
+
+    pub fn allows_method(&self, method: &Method, has_idempotency_key: bool) -> bool {
+        has_idempotency_key || self.idempotent_methods.contains(method)
+    }
+
+    pub fn retries_status(&self, status: StatusCode) -> bool {
+        self.retry_statuses.contains(&status)
+    }
+
+    pub fn retries_error(&self, error: &FleetbaseError) -> bool {
+        match error {
+            FleetbaseError::Transport(e) => {
+                self.retry_transport_errors && (e.is_connect() || e.is_timeout())
+            }
+            _ => false,
+        }
+    }
+
+    /// Delay before the attempt following `attempt` (1-based). A server
+    /// supplied `Retry-After` wins over the computed backoff; `None` means
+    /// it asks for longer than `max_delay`, so retrying sooner would only
+    /// be refused again.
+    pub fn delay_for(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_delay).then_some(retry_after);
+        }
+
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        if self.jitter && !backoff.is_zero() {
+            let half = backoff / 2;
+            Some(half + rand::thread_rng().gen_range(Duration::ZERO..=half))
+        } else {
+            Some(backoff)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RetryEvent<'a> {
+    /// The attempt that just failed (1-based).
+    pub attempt: u32,
+    pub method: &'a Method,
+    pub url: &'a str,
+    pub status: Option<StatusCode>,
+    pub error: Option<&'a FleetbaseError>,
+    pub delay: Duration,
+}
+
+pub type RetryHook = Arc<dyn Fn(&RetryEvent<'_>) + Send + Sync>;
+
+/// Parses `Retry-After` as either delta-seconds or an HTTP date.
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(
+        at.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+pub fn new_idempotency_key() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_delay_for_backs_off_exponentially() {
+        let policy = RetryPolicy::default()
+            .jitter(false)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(500));
+
+        assert_eq!(policy.delay_for(1, None), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay_for(2, None), Some(Duration::from_millis(200)));
+        assert_eq!(policy.delay_for(3, None), Some(Duration::from_millis(400)));
+        assert_eq!(policy.delay_for(4, None), Some(Duration::from_millis(500)));
+        assert_eq!(
+            policy.delay_for(1, Some(Duration::from_millis(450))),
+            Some(Duration::from_millis(450))
+        );
+        assert_eq!(policy.delay_for(1, Some(Duration::from_secs(60))), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_post_requires_idempotency_key() {
+        let policy = RetryPolicy::default();
+        assert!(policy.allows_method(&Method::GET, false));
+        assert!(!policy.allows_method(&Method::POST, false));
+        assert!(policy.allows_method(&Method::POST, true));
+    }
+}
@@ -0,0 +1,285 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+
+use crate::client::FleetbaseClient;
+use crate::contact::Contact;
+use crate::entity::Entity;
+use crate::error::FleetbaseError;
+use crate::pagination::Paginator;
+use crate::place::Place;
+use crate::query::ListQuery;
+use crate::service_rate::ServiceRate;
+use crate::tracking_number::TrackingNumber;
+use crate::tracking_status::TrackingStatus;
+use crate::utils::enpdpoints::{
+    Contacts, Endpoint, Entities, Places, ServiceRates, TrackingNumbers, TrackingStatuses,
+    Vehicles, Vendors,
+};
+use crate::vehicle::Vehicle;
+use crate::vendor::Vendor;
+
+/// A type that maps onto a Fleetbase REST collection.
+pub trait ApiResource: DeserializeOwned + Send + 'static {
+    fn collection() -> Endpoint;
+
+    fn member(id: &str) -> Endpoint;
+
+    /// Lets resource wrappers such as `Order` keep a handle to the client
+    /// they were loaded through.
+    fn attach(&mut self, _client: &FleetbaseClient) {}
+}
+
+macro_rules! impl_api_resource {
+    ($type:ty, $group:ident, $collection:ident, $member:ident) => {
+        impl ApiResource for $type {
+            fn collection() -> Endpoint {
+                Endpoint::$group($group::$collection)
+            }
+
+            fn member(id: &str) -> Endpoint {
+                Endpoint::$group($group::$member(id.to_string()))
+            }
+        }
+    };
+}
+
+impl_api_resource!(Place, Places, Places, PlacesById);
+impl_api_resource!(Contact, Contacts, Contacts, ContactsById);
+impl_api_resource!(Vendor, Vendors, Vendors, VendorsById);
+impl_api_resource!(Vehicle, Vehicles, Vehicles, VehiclesById);
+impl_api_resource!(Entity, Entities, Entities, EntitiesById);
+impl_api_resource!(ServiceRate, ServiceRates, ServiceRates, ServiceRatesById);
+impl_api_resource!(
+    TrackingNumber,
+    TrackingNumbers,
+    TrackingNumbers,
+    TrackingNumbersById
+);
+impl_api_resource!(
+    TrackingStatus,
+    TrackingStatuses,
+    TrackingStatuses,
+    TrackingStatusesById
+);
+
+/// CRUD operations for one resource type, e.g. `client.vehicles()`.
+pub struct ResourceService<T> {
+    client: FleetbaseClient,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: ApiResource> ResourceService<T> {
+    pub fn new(client: FleetbaseClient) -> Self {
+        Self {
+            client,
+            _marker: PhantomData,
+        }
+    }
+
+    fn attach(&self, mut resource: T) -> T {
+        resource.attach(&self.client);
+        resource
+    }
+
+    pub async fn create<B>(&self, attributes: &B) -> Result<T, FleetbaseError>
+    where
+        B: Serialize + ?Sized,
+    {
+        let resource = self.client.post(T::collection(), attributes).await?;
+        Ok(self.attach(resource))
+    }
+
+    pub async fn retrieve(&self, id: &str) -> Result<T, FleetbaseError> {
+        let resource = self.client.get(T::member(id)).await?;
+        Ok(self.attach(resource))
+    }
+
+    pub async fn update<B>(&self, id: &str, attributes: &B) -> Result<T, FleetbaseError>
+    where
+        B: Serialize + ?Sized,
+    {
+        let resource = self.client.put(T::member(id), attributes).await?;
+        Ok(self.attach(resource))
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<T, FleetbaseError> {
+        let resource = self.client.delete(T::member(id)).await?;
+        Ok(self.attach(resource))
+    }
+
+    /// First page with the server's default ordering.
+    pub async fn list(&self) -> Result<Vec<T>, FleetbaseError> {
+        self.query(&ListQuery::new()).await
+    }
+
+    pub async fn query(&self, query: &ListQuery) -> Result<Vec<T>, FleetbaseError> {
+        let resources = self.client.list(T::collection(), query).await?;
+        Ok(resources.into_iter().map(|r| self.attach(r)).collect())
+    }
+
+    /// Every matching record, page by page. Wrappers are not attached to the
+    /// client; use `retrieve` when actions are needed on a result.
+    pub fn paginate(&self, query: &ListQuery) -> Paginator<T> {
+        self.client.paginate(T::collection()).list_query(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpResponse, MemoryTransport};
+    use reqwest::{Method, StatusCode};
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn client(transport: &Arc<MemoryTransport>) -> FleetbaseClient {
+        FleetbaseClient::builder("test_token")
+            .transport(transport.clone())
+            .build()
+            .unwrap()
+    }
+
+    fn vehicle(id: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "make": "Toyota",
+            "model": "HiAce",
+            "plate_number": "SGX1234A",
+            "status": "active",
+            "trim": "GL",
+            "vin": "JTFSX23P400012345",
+            "year": "2021"
+        })
+    }
+
+    /// Records whether the service attached it to a client.
+    #[derive(Debug, Deserialize)]
+    struct Probe {
+        id: String,
+        #[serde(skip)]
+        attached: bool,
+    }
+
+    impl ApiResource for Probe {
+        fn collection() -> Endpoint {
+            Vehicle::collection()
+        }
+
+        fn member(id: &str) -> Endpoint {
+            Vehicle::member(id)
+        }
+
+        fn attach(&mut self, _client: &FleetbaseClient) {
+            self.attached = true;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crud_requests_and_decoding() {
+        let transport = Arc::new(MemoryTransport::new());
+        transport
+            .on(
+                Method::POST,
+                "/v1/vehicles",
+                HttpResponse::json(StatusCode::OK, vehicle("vehicle_1")),
+            )
+            .on(
+                Method::GET,
+                "/v1/vehicles/vehicle_1",
+                HttpResponse::json(StatusCode::OK, vehicle("vehicle_1")),
+            )
+            .on(
+                Method::PUT,
+                "/v1/vehicles/vehicle_1",
+                HttpResponse::json(StatusCode::OK, vehicle("vehicle_1")),
+            )
+            .on(
+                Method::DELETE,
+                "/v1/vehicles/vehicle_1",
+                HttpResponse::json(StatusCode::OK, vehicle("vehicle_1")),
+            )
+            .on(
+                Method::GET,
+                "/v1/vehicles",
+                HttpResponse::json(
+                    StatusCode::OK,
+                    json!([vehicle("vehicle_1"), vehicle("vehicle_2")]),
+                ),
+            );
+        let vehicles = client(&transport).vehicles();
+        let last = || {
+            let request = transport.last_request().unwrap();
+            (request.method.clone(), request.path().to_string())
+        };
+
+        let created = vehicles.create(&json!({"make": "Toyota"})).await.unwrap();
+        assert_eq!(created.plate_number, "SGX1234A");
+        assert_eq!(last(), (Method::POST, "/v1/vehicles".to_string()));
+        assert_eq!(
+            transport.last_request().unwrap().json().unwrap(),
+            json!({"make": "Toyota"})
+        );
+
+        let retrieved = vehicles.retrieve("vehicle_1").await.unwrap();
+        assert_eq!(retrieved.id, "vehicle_1");
+        assert_eq!(last(), (Method::GET, "/v1/vehicles/vehicle_1".to_string()));
+
+        vehicles
+            .update("vehicle_1", &json!({"status": "active"}))
+            .await
+            .unwrap();
+        assert_eq!(last(), (Method::PUT, "/v1/vehicles/vehicle_1".to_string()));
+
+        vehicles.delete("vehicle_1").await.unwrap();
+        assert_eq!(
+            last(),
+            (Method::DELETE, "/v1/vehicles/vehicle_1".to_string())
+        );
+
+        let listed = vehicles.query(&ListQuery::new().limit(2)).await.unwrap();
+        assert_eq!(
+            listed.iter().map(|v| v.id.as_str()).collect::<Vec<_>>(),
+            ["vehicle_1", "vehicle_2"]
+        );
+        assert_eq!(last(), (Method::GET, "/v1/vehicles".to_string()));
+        assert_eq!(transport.last_request().unwrap().query(), Some("limit=2"));
+
+        let error = client(&transport)
+            .contacts()
+            .retrieve("contact_1")
+            .await
+            .unwrap_err();
+        assert!(error.is_not_found());
+        assert_eq!(last(), (Method::GET, "/v1/contacts/contact_1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_results_are_attached() {
+        let transport = Arc::new(MemoryTransport::new());
+        let probe = HttpResponse::json(StatusCode::OK, json!({"id": "probe_1"}));
+        for method in [Method::GET, Method::PUT, Method::DELETE] {
+            transport.on(method, "/v1/vehicles/probe_1", probe.clone());
+        }
+        transport.on(Method::POST, "/v1/vehicles", probe).on(
+            Method::GET,
+            "/v1/vehicles",
+            HttpResponse::json(StatusCode::OK, json!([{"id": "probe_1"}])),
+        );
+        let probes = ResourceService::<Probe>::new(client(&transport));
+
+        let results = vec![
+            probes.create(&json!({})).await.unwrap(),
+            probes.retrieve("probe_1").await.unwrap(),
+            probes.update("probe_1", &json!({})).await.unwrap(),
+            probes.delete("probe_1").await.unwrap(),
+        ]
+        .into_iter()
+        .chain(probes.query(&ListQuery::new()).await.unwrap());
+        for result in results {
+            assert_eq!(result.id, "probe_1");
+            assert!(result.attached);
+        }
+    }
+}
@@ -1,33 +1,34 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::error::FleetbaseError;
+use crate::geo::{BoundingBox, Point, Polygon};
+use crate::geocoder::{Geocoder, OfflineGeocoder};
+use crate::spatial::SpatialIndex;
+use crate::store::{load_records, Collection, MemoryStore, Store, Write};
+use crate::zone::{Zone, ZoneManager};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceArea {
     pub id: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub name: String,
     pub country: String,
-    pub location: Location,
+    pub location: Point,
+    /// Meters around `location`.
     pub radius: f64,
     pub status: String,
     pub r#type: String,
-    pub latitude: f64,
-    pub longitude: f64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Location {
-    pub r#type: String,
-    pub coordinates: Vec<f64>,
 }
 
 impl ServiceArea {
     pub fn new(
         name: String,
         country: String,
-        location: Location,
+        location: Point,
         radius: f64,
         status: String,
         r#type: String,
@@ -39,8 +40,6 @@ impl ServiceArea {
             updated_at: now,
             name,
             country,
-            latitude: location.coordinates[1],
-            longitude: location.coordinates[0],
             location,
             radius,
             status,
@@ -48,38 +47,169 @@ impl ServiceArea {
         }
     }
 
+    pub fn latitude(&self) -> f64 {
+        self.location.latitude()
+    }
+
+    pub fn longitude(&self) -> f64 {
+        self.location.longitude()
+    }
+
+    pub fn bbox(&self) -> BoundingBox {
+        BoundingBox::around(&self.location, self.radius)
+    }
+
+    /// Whether `point` is within `radius` meters of `location`.
+    pub fn contains(&self, point: &Point) -> bool {
+        self.location.distance_to(point) <= self.radius
+    }
+
+    /// Whether every vertex of the exterior ring is within `radius`. The
+    /// circle is convex, so this covers the edges between them too.
+    pub fn contains_polygon(&self, polygon: &Polygon) -> bool {
+        polygon
+            .exterior()
+            .points()
+            .iter()
+            .all(|point| self.contains(point))
+    }
+
     pub fn update_status(&mut self, status: String) {
         self.status = status;
         self.updated_at = Utc::now();
     }
 }
 
+/// Where a point falls: the service area and, when one matches, the zone
+/// inside it.
+#[derive(Debug, Clone, Copy)]
+pub struct Located<'a> {
+    pub service_area: &'a ServiceArea,
+    pub zone: Option<&'a Zone>,
+}
+
+/// What deleting a service area does to the zones that belong to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeletePolicy {
+    /// Delete the zones along with the area.
+    Cascade,
+    /// Refuse while the area still has zones.
+    Restrict,
+}
+
 pub struct ServiceAreaManager {
     service_areas: HashMap<String, ServiceArea>,
+    index: SpatialIndex,
+    store: Arc<dyn Store>,
 }
 
-impl ServiceAreaManager {
-    pub fn new() -> Self {
+impl Default for ServiceAreaManager {
+    fn default() -> Self {
         Self {
             service_areas: HashMap::new(),
+            index: SpatialIndex::new(),
+            store: Arc::new(MemoryStore::new()),
+        }
+    }
+}
+
+impl ServiceAreaManager {
+    /// Keeps service areas in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the service areas already in `store` and writes every change
+    /// to it.
+    pub fn with_store(store: Arc<dyn Store>) -> Result<Self, FleetbaseError> {
+        let mut manager = Self {
+            store,
+            ..Self::default()
+        };
+        for service_area in
+            load_records::<ServiceArea>(manager.store.as_ref(), Collection::ServiceAreas)?
+        {
+            manager.cache(service_area);
         }
+        Ok(manager)
+    }
+
+    pub fn store(&self) -> &Arc<dyn Store> {
+        &self.store
+    }
+
+    fn cache(&mut self, service_area: ServiceArea) -> &ServiceArea {
+        let id = service_area.id.clone();
+        self.index.insert(id.clone(), &service_area.bbox());
+        self.service_areas.insert(id.clone(), service_area);
+        self.service_areas.get(&id).unwrap()
+    }
+
+    fn persist(&self, writes: &[Write]) -> Result<(), String> {
+        self.store.apply(writes).map_err(|e| e.to_string())
+    }
+
+    /// Stores `service_area` as is, replacing any area with the same id.
+    pub(crate) fn put_service_area(
+        &mut self,
+        service_area: ServiceArea,
+    ) -> Result<&ServiceArea, String> {
+        let write = Write::put(Collection::ServiceAreas, &service_area.id, &service_area)
+            .map_err(|e| e.to_string())?;
+        self.persist(&[write])?;
+        Ok(self.cache(service_area))
+    }
+
+    /// Deletes only the area; zones keep pointing at its id.
+    pub(crate) fn remove_service_area(&mut self, id: &str) -> Result<(), String> {
+        self.persist(&[Write::delete(Collection::ServiceAreas, id)])?;
+        self.service_areas.remove(id);
+        self.index.remove(id);
+        Ok(())
     }
 
     pub fn create_service_area(
         &mut self,
         name: String,
         country: String,
-        location: Location,
+        location: Point,
         radius: f64,
         status: String,
         r#type: String,
     ) -> Result<&ServiceArea, String> {
-        // Here you would typically implement reverse geocoding logic
-        // For this example, we'll assume it always succeeds
+        // An empty country is filled in from the bundled boundaries, which
+        // cover six countries; use `create_geocoded_service_area` for a
+        // full geocoder.
+        let country = if country.is_empty() {
+            OfflineGeocoder::bundled()
+                .country_at(&location)
+                .map(|country| country.code.clone())
+                .ok_or("No country found at the service area's location")?
+        } else {
+            country
+        };
         let service_area = ServiceArea::new(name, country, location, radius, status, r#type);
-        let id = service_area.id.clone();
-        self.service_areas.insert(id.clone(), service_area);
-        Ok(self.service_areas.get(&id).unwrap())
+        self.put_service_area(service_area)
+    }
+
+    /// Like `create_service_area`, with `country` found by reverse
+    /// geocoding `location`.
+    pub async fn create_geocoded_service_area(
+        &mut self,
+        geocoder: &dyn Geocoder,
+        name: String,
+        location: Point,
+        radius: f64,
+        status: String,
+        r#type: String,
+    ) -> Result<&ServiceArea, String> {
+        let country = geocoder
+            .reverse(&location)
+            .await
+            .map_err(|e| e.to_string())?
+            .and_then(|address| address.country)
+            .ok_or("No country found at the service area's location")?;
+        self.create_service_area(name, country, location, radius, status, r#type)
     }
 
     pub fn get_service_area(&self, id: &str) -> Option<&ServiceArea> {
@@ -91,18 +221,74 @@ impl ServiceAreaManager {
         id: &str,
         status: String,
     ) -> Result<&ServiceArea, String> {
-        if let Some(service_area) = self.service_areas.get_mut(id) {
-            service_area.update_status(status);
-            Ok(service_area)
+        let mut service_area = self
+            .service_areas
+            .get(id)
+            .ok_or("Service area not found")?
+            .clone();
+        service_area.update_status(status);
+        self.put_service_area(service_area)
+    }
+
+    pub fn delete_service_area(
+        &mut self,
+        id: &str,
+        zones: &mut ZoneManager,
+        policy: DeletePolicy,
+    ) -> Result<(), String> {
+        if !self.service_areas.contains_key(id) {
+            return Err("Service area not found".to_string());
+        }
+        let zone_ids: Vec<String> = zones
+            .zones_in_service_area(id)
+            .into_iter()
+            .map(|zone| zone.id.clone())
+            .collect();
+        if policy == DeletePolicy::Restrict && !zone_ids.is_empty() {
+            return Err(format!("Service area still has {} zone(s)", zone_ids.len()));
+        }
+
+        let mut writes: Vec<Write> = zone_ids
+            .iter()
+            .map(|zone_id| Write::delete(Collection::Zones, zone_id))
+            .collect();
+        let area_delete = Write::delete(Collection::ServiceAreas, id);
+        if Arc::ptr_eq(&self.store, zones.store()) {
+            // One transaction, so a failure keeps both the area and its zones.
+            writes.push(area_delete);
+            self.persist(&writes)?;
         } else {
-            Err("Service area not found".to_string())
+            // The area goes first and is put back if its zones cannot be
+            // deleted, so a failure never leaves the area without its zones.
+            self.persist(&[area_delete])?;
+            if let Err(e) = zones.store().apply(&writes) {
+                let restore = Write::put(Collection::ServiceAreas, id, &self.service_areas[id])
+                    .map_err(|e| e.to_string())?;
+                return Err(match self.persist(&[restore]) {
+                    Ok(()) => e.to_string(),
+                    Err(restore_error) => format!(
+                        "{}; restoring service area {} also failed: {}",
+                        e, id, restore_error
+                    ),
+                });
+            }
+        }
+        for zone_id in &zone_ids {
+            zones.forget(zone_id);
         }
+        self.service_areas.remove(id);
+        self.index.remove(id);
+        Ok(())
     }
 
-    pub fn delete_service_area(&mut self, id: &str) -> Result<(), String> {
-        if self.service_areas.remove(id).is_some() {
-            // Here you would typically also delete associated zones
-            Ok(())
+    /// Zones belonging to the service area `id`.
+    pub fn list_zones<'a>(
+        &self,
+        zones: &'a ZoneManager,
+        id: &str,
+    ) -> Result<Vec<&'a Zone>, String> {
+        if self.service_areas.contains_key(id) {
+            Ok(zones.zones_in_service_area(id))
         } else {
             Err("Service area not found".to_string())
         }
@@ -111,7 +297,298 @@ impl ServiceAreaManager {
     pub fn list_service_areas(&self, name: Option<&str>) -> Vec<&ServiceArea> {
         self.service_areas
             .values()
-            .filter(|&sa| name.map_or(true, |n| sa.name.contains(n)))
+            .filter(|&sa| name.is_none_or(|n| sa.name.contains(n)))
+            .collect()
+    }
+
+    /// Service areas whose circle's bounding box intersects `bbox`.
+    pub fn areas_in_bbox(&self, bbox: &BoundingBox) -> Vec<&ServiceArea> {
+        self.index
+            .intersecting(bbox)
+            .filter_map(|id| self.service_areas.get(id))
             .collect()
     }
+
+    /// Service areas covering `point`, smallest radius first.
+    pub fn areas_containing(&self, point: &Point) -> Vec<&ServiceArea> {
+        let mut areas: Vec<&ServiceArea> = self
+            .index
+            .containing(point)
+            .filter_map(|id| self.service_areas.get(id))
+            .filter(|sa| sa.contains(point))
+            .collect();
+        areas.sort_by(|a, b| a.radius.total_cmp(&b.radius).then(a.id.cmp(&b.id)));
+        areas
+    }
+
+    /// The most specific service area covering `point` that has a zone
+    /// containing it, or else the most specific service area alone.
+    pub fn locate<'a>(&'a self, zones: &'a ZoneManager, point: &Point) -> Option<Located<'a>> {
+        let areas = self.areas_containing(point);
+        let candidates = zones.zones_containing(point);
+        areas
+            .iter()
+            .find_map(|&service_area| {
+                candidates
+                    .iter()
+                    .find(|zone| zone.service_area.as_deref() == Some(service_area.id.as_str()))
+                    .map(|&zone| Located {
+                        service_area,
+                        zone: Some(zone),
+                    })
+            })
+            .or_else(|| {
+                areas.first().map(|&service_area| Located {
+                    service_area,
+                    zone: None,
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::LineString;
+    use crate::zone::Border;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn square(min: (f64, f64), max: (f64, f64)) -> Border {
+        let corners = [
+            (min.0, min.1),
+            (max.0, min.1),
+            (max.0, max.1),
+            (min.0, max.1),
+            (min.0, min.1),
+        ];
+        Border::new(Polygon::new(
+            LineString::new(
+                corners
+                    .iter()
+                    .map(|(lng, lat)| Point::new(*lng, *lat).unwrap())
+                    .collect(),
+            ),
+            Vec::new(),
+        ))
+    }
+
+    #[test]
+    fn test_locate_area_and_zone() {
+        let mut areas = ServiceAreaManager::new();
+        let singapore = areas
+            .create_service_area(
+                "Singapore".to_string(),
+                "SG".to_string(),
+                Point::from_lat_lng(1.3521, 103.8198).unwrap(),
+                30_000.0,
+                "active".to_string(),
+                "city".to_string(),
+            )
+            .unwrap()
+            .id
+            .clone();
+        let mut zones = ZoneManager::new();
+        let central = zones
+            .create_zone(
+                "Central".to_string(),
+                square((103.80, 1.27), (103.87, 1.32)),
+                "#00ff00".to_string(),
+                "#008800".to_string(),
+                areas.get_service_area(&singapore),
+            )
+            .unwrap()
+            .id
+            .clone();
+
+        let marina_bay = Point::from_lat_lng(1.2834, 103.8607).unwrap();
+        assert_eq!(areas.areas_containing(&marina_bay).len(), 1);
+        let located = areas.locate(&zones, &marina_bay).unwrap();
+        assert_eq!(located.service_area.id, singapore);
+        assert_eq!(located.zone.unwrap().id, central);
+
+        let changi = Point::from_lat_lng(1.3644, 103.9915).unwrap();
+        let located = areas.locate(&zones, &changi).unwrap();
+        assert!(located.zone.is_none());
+
+        let kuala_lumpur = Point::from_lat_lng(3.1390, 101.6869).unwrap();
+        assert!(areas.locate(&zones, &kuala_lumpur).is_none());
+        assert!(zones.zones_containing(&kuala_lumpur).is_empty());
+    }
+
+    #[test]
+    fn test_zones_follow_their_service_area() {
+        let mut areas = ServiceAreaManager::new();
+        let area = areas
+            .create_service_area(
+                "Marina".to_string(),
+                "SG".to_string(),
+                Point::from_lat_lng(1.2834, 103.8607).unwrap(),
+                5_000.0,
+                "active".to_string(),
+                "district".to_string(),
+            )
+            .unwrap()
+            .id
+            .clone();
+        let mut zones = ZoneManager::new();
+        let err = zones
+            .create_zone(
+                "Too big".to_string(),
+                square((103.80, 1.27), (103.90, 1.32)),
+                "#00ff00".to_string(),
+                "#008800".to_string(),
+                areas.get_service_area(&area),
+            )
+            .unwrap_err();
+        assert!(err.contains("outside service area Marina"));
+        zones
+            .create_zone(
+                "Bayfront".to_string(),
+                square((103.85, 1.27), (103.87, 1.29)),
+                "#00ff00".to_string(),
+                "#008800".to_string(),
+                areas.get_service_area(&area),
+            )
+            .unwrap();
+        assert_eq!(areas.list_zones(&zones, &area).unwrap().len(), 1);
+
+        assert!(areas
+            .delete_service_area(&area, &mut zones, DeletePolicy::Restrict)
+            .is_err());
+        assert!(areas.get_service_area(&area).is_some());
+        areas
+            .delete_service_area(&area, &mut zones, DeletePolicy::Cascade)
+            .unwrap();
+        assert!(zones.list_zones(None).is_empty());
+        assert!(areas.list_zones(&zones, &area).is_err());
+    }
+
+    /// A store whose writes fail while `failing` is set.
+    #[derive(Debug, Default)]
+    struct FlakyStore {
+        inner: MemoryStore,
+        failing: AtomicBool,
+    }
+
+    impl Store for FlakyStore {
+        fn load(&self, collection: Collection) -> Result<Vec<serde_json::Value>, FleetbaseError> {
+            self.inner.load(collection)
+        }
+
+        fn apply(&self, writes: &[Write]) -> Result<(), FleetbaseError> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(std::io::Error::other("disk full").into());
+            }
+            self.inner.apply(writes)
+        }
+    }
+
+    #[test]
+    fn test_cascade_delete_across_stores_keeps_area_on_failure() {
+        let area_store = Arc::new(MemoryStore::new());
+        let zone_store = Arc::new(FlakyStore::default());
+        let mut areas = ServiceAreaManager::with_store(area_store.clone()).unwrap();
+        let mut zones = ZoneManager::with_store(zone_store.clone()).unwrap();
+        let area = areas
+            .create_service_area(
+                "Marina".to_string(),
+                "SG".to_string(),
+                Point::from_lat_lng(1.2834, 103.8607).unwrap(),
+                5_000.0,
+                "active".to_string(),
+                "district".to_string(),
+            )
+            .unwrap()
+            .id
+            .clone();
+        let zone = zones
+            .create_zone(
+                "Bayfront".to_string(),
+                square((103.85, 1.27), (103.87, 1.29)),
+                "#00ff00".to_string(),
+                "#008800".to_string(),
+                areas.get_service_area(&area),
+            )
+            .unwrap()
+            .id
+            .clone();
+
+        zone_store.failing.store(true, Ordering::SeqCst);
+        let err = areas
+            .delete_service_area(&area, &mut zones, DeletePolicy::Cascade)
+            .unwrap_err();
+        assert!(err.contains("disk full"), "{}", err);
+        assert!(areas.get_service_area(&area).is_some());
+        assert!(zones.get_zone(&zone).is_some());
+        let reloaded = ServiceAreaManager::with_store(area_store.clone()).unwrap();
+        assert!(reloaded.get_service_area(&area).is_some());
+
+        zone_store.failing.store(false, Ordering::SeqCst);
+        areas
+            .delete_service_area(&area, &mut zones, DeletePolicy::Cascade)
+            .unwrap();
+        assert!(zones.get_zone(&zone).is_none());
+        assert!(ServiceAreaManager::with_store(area_store)
+            .unwrap()
+            .list_service_areas(None)
+            .is_empty());
+        assert!(ZoneManager::with_store(zone_store)
+            .unwrap()
+            .get_zone(&zone)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_country_from_location() {
+        let mut areas = ServiceAreaManager::new();
+        let kuala_lumpur = Point::from_lat_lng(3.1390, 101.6869).unwrap();
+        let area = areas
+            .create_service_area(
+                "Kuala Lumpur".to_string(),
+                String::new(),
+                kuala_lumpur,
+                20_000.0,
+                "active".to_string(),
+                "city".to_string(),
+            )
+            .unwrap();
+        assert_eq!(area.country, "MY");
+        let nairobi = Point::from_lat_lng(-1.2921, 36.8219).unwrap();
+        assert!(areas
+            .create_service_area(
+                "Nairobi".to_string(),
+                String::new(),
+                nairobi,
+                20_000.0,
+                "active".to_string(),
+                "city".to_string(),
+            )
+            .is_err());
+
+        let geocoder = OfflineGeocoder::bundled();
+        let area = areas
+            .create_geocoded_service_area(
+                geocoder,
+                "Vancouver".to_string(),
+                Point::from_lat_lng(49.2827, -123.1207).unwrap(),
+                20_000.0,
+                "active".to_string(),
+                "city".to_string(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(area.country, "CA");
+        let ocean = Point::from_lat_lng(0.0, -140.0).unwrap();
+        assert!(areas
+            .create_geocoded_service_area(
+                geocoder,
+                "Pacific".to_string(),
+                ocean,
+                1_000.0,
+                "active".to_string(),
+                "region".to_string(),
+            )
+            .await
+            .is_err());
+    }
 }
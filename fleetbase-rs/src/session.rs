@@ -0,0 +1,410 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::client::FleetbaseClient;
+use crate::driver::{Driver, DriverActions};
+use crate::error::FleetbaseError;
+use crate::utils::enpdpoints::{Drivers, Endpoint};
+use crate::utils::{is_phone, StoreActions};
+
+/// What is kept between runs so a driver does not have to log in again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DriverCredentials {
+    pub driver_id: String,
+    pub token: String,
+    pub issued_at: DateTime<Utc>,
+    /// The organization the driver last acted in, restored with the token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+}
+
+/// Persists driver tokens keyed by login identity (phone or email).
+pub trait CredentialStore: fmt::Debug + Send + Sync {
+    fn load(&self, identity: &str) -> Result<Option<DriverCredentials>, FleetbaseError>;
+
+    fn save(&self, identity: &str, credentials: &DriverCredentials) -> Result<(), FleetbaseError>;
+
+    fn remove(&self, identity: &str) -> Result<(), FleetbaseError>;
+}
+
+/// Keeps credentials for the lifetime of the process only.
+#[derive(Debug, Default)]
+pub struct MemoryCredentialStore {
+    entries: Mutex<HashMap<String, DriverCredentials>>,
+}
+
+impl MemoryCredentialStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CredentialStore for MemoryCredentialStore {
+    fn load(&self, identity: &str) -> Result<Option<DriverCredentials>, FleetbaseError> {
+        Ok(self.entries.lock().unwrap().get(identity).cloned())
+    }
+
+    fn save(&self, identity: &str, credentials: &DriverCredentials) -> Result<(), FleetbaseError> {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(identity.to_string(), credentials.clone());
+        Ok(())
+    }
+
+    fn remove(&self, identity: &str) -> Result<(), FleetbaseError> {
+        self.entries.lock().unwrap().remove(identity);
+        Ok(())
+    }
+}
+
+/// Stores every identity's credentials in one JSON file. Writes go through a
+/// temporary file and a rename so a crash never leaves a half-written store.
+/// The file holds bearer tokens; keep it somewhere only the app can read.
+#[derive(Debug)]
+pub struct FileCredentialStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileCredentialStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> Result<HashMap<String, DriverCredentials>, FleetbaseError> {
+        match fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write(&self, entries: &HashMap<String, DriverCredentials>) -> Result<(), FleetbaseError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let bytes = serde_json::to_vec_pretty(entries)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let tmp = self.path.with_extension("tmp");
+        let mut file = open_private(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Creates or truncates `path`, readable and writable by the owner only.
+#[cfg(unix)]
+fn open_private(path: &Path) -> io::Result<fs::File> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // `mode` only applies when the file is created.
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn open_private(path: &Path) -> io::Result<fs::File> {
+    fs::File::create(path)
+}
+
+impl CredentialStore for FileCredentialStore {
+    fn load(&self, identity: &str) -> Result<Option<DriverCredentials>, FleetbaseError> {
+        let _guard = self.lock.lock().unwrap();
+        Ok(self.read()?.remove(identity))
+    }
+
+    fn save(&self, identity: &str, credentials: &DriverCredentials) -> Result<(), FleetbaseError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = self.read()?;
+        entries.insert(identity.to_string(), credentials.clone());
+        self.write(&entries)
+    }
+
+    fn remove(&self, identity: &str) -> Result<(), FleetbaseError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut entries = self.read()?;
+        if entries.remove(identity).is_some() {
+            self.write(&entries)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a session driver's token is saved, so that a refreshed token
+/// replaces the stored one.
+#[derive(Debug, Clone)]
+pub(crate) struct StoredSession {
+    identity: String,
+    store: Arc<dyn CredentialStore>,
+}
+
+impl StoredSession {
+    pub(crate) fn save(
+        &self,
+        driver_id: &str,
+        token: &str,
+        organization: Option<String>,
+    ) -> Result<(), FleetbaseError> {
+        self.store.save(
+            &self.identity,
+            &DriverCredentials {
+                driver_id: driver_id.to_string(),
+                token: token.to_string(),
+                issued_at: Utc::now(),
+                organization,
+            },
+        )
+    }
+}
+
+/// A logged-in driver together with a client that authenticates as them.
+/// The driver is attached to that client, so `driver.track(..)` and
+/// `driver.sync_device(..)` are sent with the driver's token.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedDriver {
+    pub driver: Driver,
+    pub client: FleetbaseClient,
+}
+
+/// Runs the driver login flows against an organization client and keeps
+/// the resulting tokens in a `CredentialStore`:
+///
+/// * SMS: `request_sms_code(phone)`, then `verify_code(phone, code)`
+/// * password: `login_with_password(identity, password)`
+/// * later runs: `restore(identity)`
+#[derive(Debug, Clone)]
+pub struct DriverSession {
+    client: FleetbaseClient,
+    store: Arc<dyn CredentialStore>,
+}
+
+impl DriverSession {
+    /// Tokens are kept in memory until `store` is called.
+    pub fn new(client: FleetbaseClient) -> Self {
+        Self {
+            client,
+            store: Arc::new(MemoryCredentialStore::new()),
+        }
+    }
+
+    pub fn store(mut self, store: Arc<dyn CredentialStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub fn credential_store(&self) -> &Arc<dyn CredentialStore> {
+        &self.store
+    }
+
+    /// Asks Fleetbase to text a one-time code to `phone`.
+    pub async fn request_sms_code(&self, phone: &str) -> Result<(), FleetbaseError> {
+        if !is_phone(phone) {
+            return Err(FleetbaseError::Validation(format!(
+                "{} is not a phone number",
+                phone
+            )));
+        }
+        DriverActions::new(self.client.clone())
+            .login(phone, None, HashMap::new())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn verify_code(
+        &self,
+        phone: &str,
+        code: &str,
+    ) -> Result<AuthenticatedDriver, FleetbaseError> {
+        let response = DriverActions::new(self.client.clone())
+            .verify_code(phone, code, HashMap::new())
+            .await?;
+        self.authenticate(phone, response)
+    }
+
+    pub async fn login_with_password(
+        &self,
+        identity: &str,
+        password: &str,
+    ) -> Result<AuthenticatedDriver, FleetbaseError> {
+        let response = self
+            .client
+            .post(
+                Endpoint::Drivers(Drivers::DriversLogin),
+                &serde_json::json!({ "identity": identity, "password": password }),
+            )
+            .await?;
+        self.authenticate(identity, response)
+    }
+
+    /// Resumes a stored session. Returns `None` when nothing is stored or the
+    /// stored token has been revoked, in which case it is also forgotten.
+    pub async fn restore(
+        &self,
+        identity: &str,
+    ) -> Result<Option<AuthenticatedDriver>, FleetbaseError> {
+        let Some(credentials) = self.store.load(identity)? else {
+            return Ok(None);
+        };
+
+        let client = self.client.with_token(credentials.token.clone());
+        if let Some(organization) = &credentials.organization {
+            client.set_organization(organization, Some(credentials.token.clone()));
+        }
+        let response: serde_json::Value = match client
+            .get(Endpoint::Drivers(Drivers::DriversById(
+                credentials.driver_id.clone(),
+            )))
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if e.is_unauthorized() => {
+                self.store.remove(identity)?;
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut driver = Driver::new(response, client.clone())?;
+        driver.set_token(credentials.token);
+        driver.set_session(self.stored_session(identity));
+        Ok(Some(AuthenticatedDriver { driver, client }))
+    }
+
+    /// Forgets the stored token for `identity`.
+    pub fn logout(&self, identity: &str) -> Result<(), FleetbaseError> {
+        self.store.remove(identity)
+    }
+
+    fn authenticate(
+        &self,
+        identity: &str,
+        response: serde_json::Value,
+    ) -> Result<AuthenticatedDriver, FleetbaseError> {
+        let token = response
+            .get("token")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| {
+                FleetbaseError::Validation("login response did not include a token".to_string())
+            })?;
+
+        let client = self.client.with_token(token);
+        let organization = self.client.organization();
+        if let Some(organization) = &organization {
+            client.set_organization(organization, Some(token.to_string()));
+        }
+        let mut driver = Driver::new(response.clone(), client.clone())?;
+        let session = self.stored_session(identity);
+        session.save(driver.id(), token, organization)?;
+        driver.set_session(session);
+
+        Ok(AuthenticatedDriver { driver, client })
+    }
+
+    fn stored_session(&self, identity: &str) -> StoredSession {
+        StoredSession {
+            identity: identity.to_string(),
+            store: self.store.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FakeFleetbase, SMS_CODE};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_sms_login_stores_token_and_restores() {
+        let fake = FakeFleetbase::start().await;
+        let driver_id = fake.add_driver(json!({ "phone": "+15555550100" }));
+        let store = Arc::new(MemoryCredentialStore::new());
+        let session = DriverSession::new(fake.client()).store(store.clone());
+
+        session.request_sms_code("+15555550100").await.unwrap();
+        let authenticated = session.verify_code("+15555550100", SMS_CODE).await.unwrap();
+        assert_eq!(authenticated.driver.id(), driver_id);
+        let token = authenticated.driver.token().unwrap().to_string();
+        assert_eq!(store.load("+15555550100").unwrap().unwrap().token, token);
+
+        let restored = session.restore("+15555550100").await.unwrap().unwrap();
+        assert_eq!(restored.driver.id(), driver_id);
+        assert_eq!(restored.driver.token(), Some(token.as_str()));
+
+        let organization_id = fake.add_organization(json!({ "name": "Night Shift" }));
+        let mut driver = restored.driver;
+        driver
+            .switch_organization(&organization_id, HashMap::new())
+            .await
+            .unwrap();
+        let switched = driver.token().unwrap().to_string();
+        assert_ne!(switched, token);
+        let stored = store.load("+15555550100").unwrap().unwrap();
+        assert_eq!(stored.token, switched);
+        assert_eq!(
+            stored.organization.as_deref(),
+            Some(organization_id.as_str())
+        );
+        let restored = session.restore("+15555550100").await.unwrap().unwrap();
+        assert_eq!(restored.driver.token(), Some(switched.as_str()));
+        assert_eq!(restored.client.organization(), Some(organization_id));
+
+        session.logout("+15555550100").unwrap();
+        assert!(session.restore("+15555550100").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_file_store_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("fleetbase-rs-{}", uuid::Uuid::new_v4()))
+            .join("credentials.json");
+        let store = FileCredentialStore::new(&path);
+        let credentials = DriverCredentials {
+            driver_id: "driver_1".to_string(),
+            token: "secret".to_string(),
+            issued_at: Utc::now(),
+            organization: Some("org_1".to_string()),
+        };
+
+        assert!(store.load("driver@example.com").unwrap().is_none());
+        store.save("driver@example.com", &credentials).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let reopened = FileCredentialStore::new(&path);
+        assert_eq!(
+            reopened.load("driver@example.com").unwrap(),
+            Some(credentials)
+        );
+        reopened.remove("driver@example.com").unwrap();
+        assert!(store.load("driver@example.com").unwrap().is_none());
+
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
@@ -0,0 +1,189 @@
+use rstar::{RTree, RTreeObject, AABB};
+use std::collections::HashMap;
+
+use crate::geo::{BoundingBox, Point};
+
+/// Half the Earth's circumference; no two points are further apart.
+const MAX_DISTANCE_METERS: f64 = 20_037_509.0;
+
+#[derive(Debug, Clone)]
+struct Entry {
+    key: String,
+    envelope: AABB<[f64; 2]>,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl RTreeObject for Entry {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
+}
+
+fn aabb(bbox: &BoundingBox) -> AABB<[f64; 2]> {
+    AABB::from_corners(bbox.min().position(), bbox.max().position())
+}
+
+/// An R-tree of bounding boxes keyed by id, in longitude/latitude degrees.
+///
+/// Queries return candidates whose boxes match; callers refine them with the
+/// exact geometry (polygon containment, circle radius). Points are stored
+/// as zero-sized boxes, so point queries on them are exact.
+#[derive(Debug, Default)]
+pub struct SpatialIndex {
+    tree: RTree<Entry>,
+    envelopes: HashMap<String, AABB<[f64; 2]>>,
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.envelopes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envelopes.is_empty()
+    }
+
+    /// Adds `key`, replacing any box it already had.
+    pub fn insert(&mut self, key: impl Into<String>, bbox: &BoundingBox) {
+        let key = key.into();
+        self.remove(&key);
+        let envelope = aabb(bbox);
+        self.envelopes.insert(key.clone(), envelope);
+        self.tree.insert(Entry { key, envelope });
+    }
+
+    pub fn insert_point(&mut self, key: impl Into<String>, point: &Point) {
+        self.insert(key, &BoundingBox::new(*point, *point));
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        match self.envelopes.remove(key) {
+            Some(envelope) => {
+                self.tree.remove(&Entry {
+                    key: key.to_string(),
+                    envelope,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keys whose boxes intersect `bbox`.
+    pub fn intersecting(&self, bbox: &BoundingBox) -> impl Iterator<Item = &str> {
+        self.tree
+            .locate_in_envelope_intersecting(&aabb(bbox))
+            .map(|entry| entry.key.as_str())
+    }
+
+    /// Keys whose boxes contain `point`.
+    pub fn containing(&self, point: &Point) -> impl Iterator<Item = &str> {
+        self.intersecting(&BoundingBox::new(*point, *point))
+    }
+
+    /// Keys whose boxes intersect the box around the circle of `meters`
+    /// around `center`. Includes false positives near the box corners; use
+    /// `points_within_radius` for an exact answer on points.
+    pub fn within_radius(&self, center: &Point, meters: f64) -> impl Iterator<Item = &str> {
+        self.intersecting(&BoundingBox::around(center, meters))
+    }
+
+    /// Point entries within `meters` of `center` by great-circle distance,
+    /// with their distance, in no particular order.
+    pub fn points_within_radius(&self, center: &Point, meters: f64) -> Vec<(&str, f64)> {
+        self.within_radius(center, meters)
+            .filter_map(|key| {
+                let [lng, lat] = self.envelopes.get(key)?.lower();
+                let distance = Point::new(lng, lat).ok()?.distance_to(center);
+                (distance <= meters).then_some((key, distance))
+            })
+            .collect()
+    }
+
+    /// The `k` point entries nearest `center` by great-circle distance, with
+    /// their distance in meters, nearest first. Found by widening a radius
+    /// search until it holds `k` points, so the result is exact.
+    pub fn nearest_points(&self, center: &Point, k: usize) -> Vec<(&str, f64)> {
+        if k == 0 || self.is_empty() {
+            return Vec::new();
+        }
+
+        let mut radius = 1_000.0;
+        loop {
+            let mut found = self.points_within_radius(center, radius);
+
+            if found.len() >= k || radius >= MAX_DISTANCE_METERS {
+                found.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(b.0)));
+                found.truncate(k);
+                return found;
+            }
+            radius = (radius * 4.0).min(MAX_DISTANCE_METERS);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_query_remove() {
+        let mut index = SpatialIndex::new();
+        let square = BoundingBox::new(
+            Point::new(103.80, 1.27).unwrap(),
+            Point::new(103.87, 1.32).unwrap(),
+        );
+        index.insert("central", &square);
+        index.insert_point("driver_1", &Point::new(103.85, 1.29).unwrap());
+        index.insert_point("driver_2", &Point::new(101.69, 3.139).unwrap());
+
+        let hits: Vec<&str> = index
+            .containing(&Point::new(103.82, 1.30).unwrap())
+            .collect();
+        assert_eq!(hits, vec!["central"]);
+        let mut hits: Vec<&str> = index.intersecting(&square).collect();
+        hits.sort();
+        assert_eq!(hits, vec!["central", "driver_1"]);
+
+        // Moving a point replaces its old box.
+        index.insert_point("driver_1", &Point::new(103.99, 1.36).unwrap());
+        assert_eq!(index.intersecting(&square).count(), 1);
+        assert!(index.remove("central"));
+        assert!(!index.remove("central"));
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_nearest_points_by_distance() {
+        let mut index = SpatialIndex::new();
+        let origin = Point::from_lat_lng(1.3521, 103.8198).unwrap();
+        for (key, lat, lng) in [
+            ("near", 1.3600, 103.8200),
+            ("mid", 1.4000, 103.9000),
+            ("far", 3.1390, 101.6869),
+            ("very_far", 51.5072, -0.1276),
+        ] {
+            index.insert_point(key, &Point::from_lat_lng(lat, lng).unwrap());
+        }
+
+        let nearest = index.nearest_points(&origin, 2);
+        let keys: Vec<&str> = nearest.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec!["near", "mid"]);
+        assert!(nearest[0].1 < 1_000.0);
+
+        assert_eq!(index.nearest_points(&origin, 10).len(), 4);
+        let within = index.points_within_radius(&origin, 15_000.0);
+        assert_eq!(within.len(), 2);
+    }
+}
@@ -0,0 +1,265 @@
+//! Persistence for `ServiceAreaManager`, `ZoneManager` and `VendorManager`.
+//!
+//! Managers keep their records in memory for lookups and write every change
+//! through to a `Store` first, so a failed write leaves both unchanged.
+//! Records are stored as JSON documents keyed by id.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::Mutex;
+
+use crate::error::FleetbaseError;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Collection {
+    ServiceAreas,
+    Zones,
+    Vendors,
+    /// Bookkeeping for `sync::SyncEngine`.
+    SyncState,
+}
+
+impl Collection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Collection::ServiceAreas => "service_areas",
+            Collection::Zones => "zones",
+            Collection::Vendors => "vendors",
+            Collection::SyncState => "sync_state",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Write {
+    /// Inserts the record, or replaces it in place if `id` exists.
+    Put {
+        collection: Collection,
+        id: String,
+        record: Value,
+    },
+    Delete {
+        collection: Collection,
+        id: String,
+    },
+}
+
+impl Write {
+    pub fn put<T: Serialize>(
+        collection: Collection,
+        id: &str,
+        record: &T,
+    ) -> Result<Self, FleetbaseError> {
+        let record = serde_json::to_value(record)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Write::Put {
+            collection,
+            id: id.to_string(),
+            record,
+        })
+    }
+
+    pub fn delete(collection: Collection, id: &str) -> Self {
+        Write::Delete {
+            collection,
+            id: id.to_string(),
+        }
+    }
+}
+
+/// Keeps manager records between runs.
+pub trait Store: fmt::Debug + Send + Sync {
+    /// Every record in `collection`, in the order it was first written.
+    fn load(&self, collection: Collection) -> Result<Vec<Value>, FleetbaseError>;
+
+    /// Applies all of `writes` or, on error, none of them.
+    fn apply(&self, writes: &[Write]) -> Result<(), FleetbaseError>;
+}
+
+/// Reads every record in `collection` as `T`.
+pub(crate) fn load_records<T: serde::de::DeserializeOwned>(
+    store: &dyn Store,
+    collection: Collection,
+) -> Result<Vec<T>, FleetbaseError> {
+    store
+        .load(collection)?
+        .into_iter()
+        .map(|record| {
+            serde_json::from_value(record)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+        })
+        .collect()
+}
+
+/// Keeps records for the lifetime of the process only.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    collections: Mutex<HashMap<Collection, Vec<(String, Value)>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Store for MemoryStore {
+    fn load(&self, collection: Collection) -> Result<Vec<Value>, FleetbaseError> {
+        let collections = self.collections.lock().unwrap();
+        Ok(collections
+            .get(&collection)
+            .map(|records| records.iter().map(|(_, record)| record.clone()).collect())
+            .unwrap_or_default())
+    }
+
+    fn apply(&self, writes: &[Write]) -> Result<(), FleetbaseError> {
+        let mut collections = self.collections.lock().unwrap();
+        for write in writes {
+            match write {
+                Write::Put {
+                    collection,
+                    id,
+                    record,
+                } => {
+                    let records = collections.entry(*collection).or_default();
+                    match records.iter_mut().find(|(key, _)| key == id) {
+                        Some((_, existing)) => *existing = record.clone(),
+                        None => records.push((id.clone(), record.clone())),
+                    }
+                }
+                Write::Delete { collection, id } => {
+                    if let Some(records) = collections.get_mut(collection) {
+                        records.retain(|(key, _)| key != id);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::{LineString, Point, Polygon};
+    use crate::service_area::{DeletePolicy, ServiceAreaManager};
+    use crate::vendor::VendorManager;
+    use crate::zone::{Border, ZoneManager};
+    use std::sync::Arc;
+
+    fn border() -> Border {
+        let ring = [
+            [103.85, 1.28],
+            [103.86, 1.28],
+            [103.86, 1.29],
+            [103.85, 1.28],
+        ]
+        .iter()
+        .map(|[lng, lat]| Point::new(*lng, *lat).unwrap())
+        .collect();
+        Border::new(Polygon::new(LineString::new(ring), Vec::new()))
+    }
+
+    /// Runs the same scenario against any store, reopening the managers
+    /// over it to check that everything survives.
+    pub(crate) fn exercise(store: Arc<dyn Store>) {
+        let mut areas = ServiceAreaManager::with_store(store.clone()).unwrap();
+        let mut zones = ZoneManager::with_store(store.clone()).unwrap();
+        let mut vendors = VendorManager::with_store(store.clone()).unwrap();
+
+        let area = areas
+            .create_service_area(
+                "Marina".to_string(),
+                "SG".to_string(),
+                Point::from_lat_lng(1.2834, 103.8607).unwrap(),
+                5_000.0,
+                "active".to_string(),
+                "district".to_string(),
+            )
+            .unwrap()
+            .id
+            .clone();
+        let zone = zones
+            .create_zone(
+                "Bayfront".to_string(),
+                border(),
+                "#00ff00".to_string(),
+                "#008800".to_string(),
+                areas.get_service_area(&area),
+            )
+            .unwrap()
+            .id
+            .clone();
+        zones
+            .update_zone(&zone, None, None, Some("inactive".to_string()))
+            .unwrap();
+        for name in ["Acme Couriers", "Bolt Freight"] {
+            vendors
+                .try_create_vendor(
+                    "ops@example.com".to_string(),
+                    name.to_string(),
+                    "5550100".to_string(),
+                    "logistics".to_string(),
+                    None,
+                )
+                .unwrap();
+        }
+        let first = vendors.list_vendors()[0].id.clone();
+        vendors
+            .try_update_vendor(
+                &first,
+                None,
+                Some("Acme Express".to_string()),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        let areas = ServiceAreaManager::with_store(store.clone()).unwrap();
+        let mut zones = ZoneManager::with_store(store.clone()).unwrap();
+        let vendors = VendorManager::with_store(store.clone()).unwrap();
+        assert_eq!(areas.get_service_area(&area).unwrap().name, "Marina");
+        assert_eq!(zones.get_zone(&zone).unwrap().status, "inactive");
+        assert_eq!(
+            zones
+                .zones_containing(&Point::new(103.855, 1.282).unwrap())
+                .len(),
+            1
+        );
+        let names: Vec<&str> = vendors
+            .list_vendors()
+            .iter()
+            .map(|v| v.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Acme Express", "Bolt Freight"]);
+        assert_eq!(vendors.list_vendors()[0].slug, "acme-express");
+
+        let mut areas = areas;
+        areas
+            .delete_service_area(&area, &mut zones, DeletePolicy::Cascade)
+            .unwrap();
+        assert!(ServiceAreaManager::with_store(store.clone())
+            .unwrap()
+            .list_service_areas(None)
+            .is_empty());
+        assert!(ZoneManager::with_store(store)
+            .unwrap()
+            .get_zone(&zone)
+            .is_none());
+    }
+
+    #[test]
+    fn test_memory_store_persists_across_managers() {
+        exercise(Arc::new(MemoryStore::new()));
+    }
+}
@@ -0,0 +1,155 @@
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::{Collection, Store, Write};
+use crate::error::FleetbaseError;
+
+/// Schema changes, applied in order and tracked with `PRAGMA user_version`.
+/// Only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE service_areas (id TEXT PRIMARY KEY, record TEXT NOT NULL);
+     CREATE TABLE zones (id TEXT PRIMARY KEY, record TEXT NOT NULL);
+     CREATE TABLE vendors (id TEXT PRIMARY KEY, record TEXT NOT NULL);",
+    "CREATE TABLE sync_state (id TEXT PRIMARY KEY, record TEXT NOT NULL);",
+];
+
+fn sqlite_error(e: rusqlite::Error) -> FleetbaseError {
+    io::Error::other(e).into()
+}
+
+/// Stores records in a SQLite database, one table per collection. Every
+/// `apply` runs in a single transaction.
+#[derive(Debug)]
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Opens or creates the database at `path` and brings its schema up to
+    /// date.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FleetbaseError> {
+        Self::migrate(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    pub fn in_memory() -> Result<Self, FleetbaseError> {
+        Self::migrate(Connection::open_in_memory().map_err(sqlite_error)?)
+    }
+
+    /// The number of migrations applied to the database.
+    pub fn schema_version(&self) -> Result<usize, FleetbaseError> {
+        let connection = self.connection.lock().unwrap();
+        user_version(&connection).map_err(sqlite_error)
+    }
+
+    fn migrate(mut connection: Connection) -> Result<Self, FleetbaseError> {
+        let transaction = connection.transaction().map_err(sqlite_error)?;
+        let version = user_version(&transaction).map_err(sqlite_error)?;
+        if version > MIGRATIONS.len() {
+            return Err(FleetbaseError::Validation(format!(
+                "database schema version {} is newer than this library supports ({})",
+                version,
+                MIGRATIONS.len()
+            )));
+        }
+        for migration in &MIGRATIONS[version..] {
+            transaction.execute_batch(migration).map_err(sqlite_error)?;
+        }
+        transaction
+            .pragma_update(None, "user_version", MIGRATIONS.len())
+            .map_err(sqlite_error)?;
+        transaction.commit().map_err(sqlite_error)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+fn user_version(connection: &Connection) -> rusqlite::Result<usize> {
+    connection.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+impl Store for SqliteStore {
+    fn load(&self, collection: Collection) -> Result<Vec<Value>, FleetbaseError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT record FROM {} ORDER BY rowid",
+                collection.name()
+            ))
+            .map_err(sqlite_error)?;
+        let records = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(sqlite_error)?;
+        records
+            .map(|record| {
+                let record = record.map_err(sqlite_error)?;
+                serde_json::from_str(&record)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+            })
+            .collect()
+    }
+
+    fn apply(&self, writes: &[Write]) -> Result<(), FleetbaseError> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction().map_err(sqlite_error)?;
+        for write in writes {
+            match write {
+                Write::Put {
+                    collection,
+                    id,
+                    record,
+                } => transaction.execute(
+                    &format!(
+                        "INSERT INTO {} (id, record) VALUES (?1, ?2)
+                         ON CONFLICT(id) DO UPDATE SET record = excluded.record",
+                        collection.name()
+                    ),
+                    params![id, record.to_string()],
+                ),
+                Write::Delete { collection, id } => transaction.execute(
+                    &format!("DELETE FROM {} WHERE id = ?1", collection.name()),
+                    params![id],
+                ),
+            }
+            .map_err(sqlite_error)?;
+        }
+        // Dropping the transaction on an early return rolls it back.
+        transaction.commit().map_err(sqlite_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_sqlite_store_migrates_and_persists() {
+        let path = std::env::temp_dir().join(format!("fleetbase-{}.db", uuid::Uuid::new_v4()));
+        super::super::tests::exercise(Arc::new(SqliteStore::open(&path).unwrap()));
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+        // A failing write rolls back the ones before it.
+        store
+            .connection
+            .lock()
+            .unwrap()
+            .execute_batch("DROP TABLE zones")
+            .unwrap();
+        let result = store.apply(&[
+            Write::Put {
+                collection: Collection::Vendors,
+                id: "v1".to_string(),
+                record: serde_json::json!({ "id": "v1" }),
+            },
+            Write::delete(Collection::Zones, "z1"),
+        ]);
+        assert!(result.is_err());
+        assert_eq!(store.load(Collection::Vendors).unwrap().len(), 2);
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,1243 @@
+//! Two-way sync between the local managers and the Fleetbase API.
+//!
+//! Each run pulls records changed remotely since the previous run, then
+//! pushes local creates, updates and deletes. Local ids stay the UUIDs the
+//! managers generate; the engine keeps a map from them to the server's
+//! public ids, together with the `updated_at` both sides had at the last
+//! sync, in the `sync_state` collection of a `Store`. A record changed on
+//! both sides since then is a conflict, settled by the `ConflictPolicy` and
+//! listed in the `SyncReport`. Records the server returns with `deleted_at`
+//! set, or no longer lists at all, are deleted locally.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::client::FleetbaseClient;
+use crate::error::FleetbaseError;
+use crate::geo::Point;
+use crate::query::ListQuery;
+use crate::service_area::{ServiceArea, ServiceAreaManager};
+use crate::store::{load_records, Collection, Store, Write};
+use crate::utils::enpdpoints::{Endpoint, ServiceAreas, Vendors, Zones};
+use crate::utils::timestamp;
+use crate::vendor::{Vendor, VendorManager};
+use crate::zone::{Border, Zone, ZoneManager};
+
+/// Upper bound on records pulled per collection in one run.
+const DEFAULT_MAX_PULL: usize = 10_000;
+
+/// Which side wins when a record changed both locally and remotely.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep the server's version; the local change is discarded.
+    #[default]
+    RemoteWins,
+    /// Keep the local version and push it over the server's.
+    LocalWins,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both sides updated the record.
+    BothChanged,
+    /// Deleted locally, updated remotely.
+    DeletedLocally,
+    /// Updated locally, deleted remotely.
+    DeletedRemotely,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub collection: Collection,
+    pub local_id: String,
+    pub public_id: String,
+    pub kind: ConflictKind,
+    /// The policy that settled it.
+    pub resolution: ConflictPolicy,
+}
+
+/// A remote record that could not be turned into a local one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedRecord {
+    pub collection: Collection,
+    pub public_id: String,
+    pub reason: String,
+}
+
+/// Local ids touched by a sync run.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Created or updated on the server.
+    pub pushed: Vec<String>,
+    /// Created or updated locally from the server.
+    pub pulled: Vec<String>,
+    /// Deleted on the server, or locally when the server had deleted them.
+    pub deleted: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+    pub skipped: Vec<SkippedRecord>,
+}
+
+impl SyncReport {
+    pub fn is_empty(&self) -> bool {
+        self.pushed.is_empty()
+            && self.pulled.is_empty()
+            && self.deleted.is_empty()
+            && self.conflicts.is_empty()
+            && self.skipped.is_empty()
+    }
+
+    fn merge(&mut self, other: SyncReport) {
+        self.pushed.extend(other.pushed);
+        self.pulled.extend(other.pulled);
+        self.deleted.extend(other.deleted);
+        self.conflicts.extend(other.conflicts);
+        self.skipped.extend(other.skipped);
+    }
+}
+
+/// What both sides looked like when a record was last synced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Mapping {
+    public_id: String,
+    local_updated_at: DateTime<Utc>,
+    remote_updated_at: Option<DateTime<Utc>>,
+}
+
+/// A row of the `sync_state` collection. Each mapping is its own row, so
+/// saving one record's mapping does not rewrite the rest.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum StateRow {
+    Mapping {
+        collection: String,
+        local_id: String,
+        #[serde(flatten)]
+        mapping: Mapping,
+    },
+    Cursor {
+        collection: String,
+        last_pulled: Option<DateTime<Utc>>,
+    },
+}
+
+#[derive(Debug, Default)]
+struct CollectionState {
+    /// The newest remote `updated_at` pulled so far.
+    last_pulled: Option<DateTime<Utc>>,
+    /// Keyed by local id.
+    records: HashMap<String, Mapping>,
+    /// Local ids keyed by public id.
+    by_public: HashMap<String, String>,
+    /// Local ids whose rows changed since the last save.
+    dirty: HashSet<String>,
+    cursor_dirty: bool,
+}
+
+impl CollectionState {
+    fn local_id(&self, public_id: &str) -> Option<String> {
+        self.by_public.get(public_id).cloned()
+    }
+
+    fn insert(&mut self, local_id: &str, mapping: Mapping) {
+        if let Some(old) = self.records.get(local_id) {
+            self.by_public.remove(&old.public_id);
+        }
+        self.by_public
+            .insert(mapping.public_id.clone(), local_id.to_string());
+        self.records.insert(local_id.to_string(), mapping);
+        self.dirty.insert(local_id.to_string());
+    }
+
+    fn remove(&mut self, local_id: &str) -> Option<Mapping> {
+        let mapping = self.records.remove(local_id)?;
+        self.by_public.remove(&mapping.public_id);
+        self.dirty.insert(local_id.to_string());
+        Some(mapping)
+    }
+
+    fn pulled(&mut self, remote_updated_at: Option<DateTime<Utc>>) {
+        if remote_updated_at > self.last_pulled {
+            self.last_pulled = remote_updated_at;
+            self.cursor_dirty = true;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SyncState {
+    collections: HashMap<Collection, CollectionState>,
+}
+
+impl SyncState {
+    fn get(&self, collection: Collection) -> Option<&CollectionState> {
+        self.collections.get(&collection)
+    }
+
+    fn get_mut(&mut self, collection: Collection) -> &mut CollectionState {
+        self.collections.entry(collection).or_default()
+    }
+
+    fn public_id(&self, collection: Collection, local_id: &str) -> Option<&str> {
+        self.get(collection)?
+            .records
+            .get(local_id)
+            .map(|mapping| mapping.public_id.as_str())
+    }
+
+    fn local_id(&self, collection: Collection, public_id: &str) -> Option<String> {
+        self.get(collection)?.local_id(public_id)
+    }
+
+    /// Writes for the rows changed since the last save.
+    fn changes(&self) -> Result<Vec<Write>, FleetbaseError> {
+        let mut writes = Vec::new();
+        for (collection, state) in &self.collections {
+            if state.cursor_dirty {
+                writes.push(Write::put(
+                    Collection::SyncState,
+                    collection.name(),
+                    &StateRow::Cursor {
+                        collection: collection.name().to_string(),
+                        last_pulled: state.last_pulled,
+                    },
+                )?);
+            }
+            for local_id in &state.dirty {
+                let id = format!("{}/{}", collection.name(), local_id);
+                writes.push(match state.records.get(local_id) {
+                    Some(mapping) => Write::put(
+                        Collection::SyncState,
+                        &id,
+                        &StateRow::Mapping {
+                            collection: collection.name().to_string(),
+                            local_id: local_id.clone(),
+                            mapping: mapping.clone(),
+                        },
+                    )?,
+                    None => Write::delete(Collection::SyncState, &id),
+                });
+            }
+        }
+        Ok(writes)
+    }
+
+    fn mark_saved(&mut self) {
+        for state in self.collections.values_mut() {
+            state.dirty.clear();
+            state.cursor_dirty = false;
+        }
+    }
+}
+
+/// A record type the engine knows how to send and receive.
+trait Synced: Clone {
+    const COLLECTION: Collection;
+
+    fn endpoint(public_id: Option<&str>) -> Endpoint;
+
+    fn local_id(&self) -> &str;
+
+    fn updated_at(&self) -> DateTime<Utc>;
+
+    /// The create/update request body, with references to other local
+    /// records translated to public ids.
+    fn payload(&self, state: &SyncState) -> Value;
+
+    /// Builds the local record for `remote`, keeping `local_id` and any
+    /// local-only fields of `existing`.
+    fn from_remote(
+        remote: &Value,
+        local_id: &str,
+        existing: Option<&Self>,
+        state: &SyncState,
+    ) -> Result<Self, String>;
+}
+
+/// The manager operations the engine needs, without the checks the public
+/// API applies to user input.
+trait SyncedManager {
+    type Record: Synced;
+
+    fn records(&self) -> Vec<&Self::Record>;
+
+    fn record(&self, local_id: &str) -> Option<&Self::Record>;
+
+    fn put(&mut self, record: Self::Record) -> Result<(), String>;
+
+    fn remove(&mut self, local_id: &str) -> Result<(), String>;
+}
+
+fn string(remote: &Value, key: &str) -> Option<String> {
+    remote.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn required(remote: &Value, key: &str) -> Result<String, String> {
+    string(remote, key).ok_or_else(|| format!("missing {}", key))
+}
+
+fn remote_timestamp(remote: &Value, key: &str) -> Option<DateTime<Utc>> {
+    remote
+        .get(key)
+        .and_then(Value::as_str)
+        .and_then(timestamp::parse)
+}
+
+/// A relation sent either as a public id or as the embedded resource.
+fn relation_id(remote: &Value, key: &str) -> Option<String> {
+    match remote.get(key)? {
+        Value::String(id) => Some(id.clone()),
+        Value::Object(object) => object.get("id")?.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+impl Synced for ServiceArea {
+    const COLLECTION: Collection = Collection::ServiceAreas;
+
+    fn endpoint(public_id: Option<&str>) -> Endpoint {
+        Endpoint::ServiceAreas(match public_id {
+            Some(id) => ServiceAreas::ServiceAreasById(id.to_string()),
+            None => ServiceAreas::ServiceAreas,
+        })
+    }
+
+    fn local_id(&self) -> &str {
+        &self.id
+    }
+
+    fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    fn payload(&self, _state: &SyncState) -> Value {
+        json!({
+            "name": self.name,
+            "country": self.country,
+            "latitude": self.latitude(),
+            "longitude": self.longitude(),
+            "radius": self.radius,
+            "status": self.status,
+            "type": self.r#type,
+        })
+    }
+
+    fn from_remote(
+        remote: &Value,
+        local_id: &str,
+        existing: Option<&Self>,
+        _state: &SyncState,
+    ) -> Result<Self, String> {
+        let location = match (remote["latitude"].as_f64(), remote["longitude"].as_f64()) {
+            (Some(latitude), Some(longitude)) => {
+                Point::from_lat_lng(latitude, longitude).map_err(|e| e.to_string())?
+            }
+            _ => serde_json::from_value(remote["location"].clone())
+                .map_err(|_| "missing location".to_string())?,
+        };
+        let now = Utc::now();
+        Ok(ServiceArea {
+            id: local_id.to_string(),
+            created_at: remote_timestamp(remote, "created_at")
+                .or(existing.map(|area| area.created_at))
+                .unwrap_or(now),
+            updated_at: remote_timestamp(remote, "updated_at").unwrap_or(now),
+            name: required(remote, "name")?,
+            country: string(remote, "country").unwrap_or_default(),
+            location,
+            radius: remote["radius"].as_f64().ok_or("missing radius")?,
+            status: string(remote, "status").unwrap_or_else(|| "active".to_string()),
+            r#type: string(remote, "type").unwrap_or_default(),
+        })
+    }
+}
+
+impl Synced for Zone {
+    const COLLECTION: Collection = Collection::Zones;
+
+    fn endpoint(public_id: Option<&str>) -> Endpoint {
+        Endpoint::Zones(match public_id {
+            Some(id) => Zones::ZonesById(id.to_string()),
+            None => Zones::Zones,
+        })
+    }
+
+    fn local_id(&self) -> &str {
+        &self.id
+    }
+
+    fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    fn payload(&self, state: &SyncState) -> Value {
+        let service_area = self
+            .service_area
+            .as_deref()
+            .and_then(|id| state.public_id(Collection::ServiceAreas, id));
+        json!({
+            "name": self.name,
+            "description": self.description,
+            "color": self.color,
+            "stroke_color": self.stroke_color,
+            "border": self.border,
+            "service_area": service_area,
+            "status": self.status,
+        })
+    }
+
+    fn from_remote(
+        remote: &Value,
+        local_id: &str,
+        existing: Option<&Self>,
+        state: &SyncState,
+    ) -> Result<Self, String> {
+        let border: Border = serde_json::from_value(remote["border"].clone())
+            .map_err(|e| format!("invalid border: {}", e))?;
+        border.validate().map_err(|e| e.to_string())?;
+        let color = string(remote, "color").unwrap_or_else(|| "#3388ff".to_string());
+        let now = Utc::now();
+        Ok(Zone {
+            id: local_id.to_string(),
+            created_at: remote_timestamp(remote, "created_at")
+                .or(existing.map(|zone| zone.created_at))
+                .unwrap_or(now),
+            updated_at: remote_timestamp(remote, "updated_at").unwrap_or(now),
+            name: required(remote, "name")?,
+            description: string(remote, "description"),
+            stroke_color: string(remote, "stroke_color").unwrap_or_else(|| color.clone()),
+            color,
+            border,
+            // An area that has not been synced yet cannot be linked.
+            service_area: relation_id(remote, "service_area")
+                .and_then(|id| state.local_id(Collection::ServiceAreas, &id)),
+            status: string(remote, "status").unwrap_or_else(|| "active".to_string()),
+        })
+    }
+}
+
+impl Synced for Vendor {
+    const COLLECTION: Collection = Collection::Vendors;
+
+    fn endpoint(public_id: Option<&str>) -> Endpoint {
+        Endpoint::Vendors(match public_id {
+            Some(id) => Vendors::VendorsById(id.to_string()),
+            None => Vendors::Vendors,
+        })
+    }
+
+    fn local_id(&self) -> &str {
+        &self.id
+    }
+
+    fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    fn payload(&self, _state: &SyncState) -> Value {
+        json!({
+            "name": self.name,
+            "email": self.email,
+            "phone": self.phone_number,
+            "type": self.r#type,
+            "internal_id": self.internal_id,
+            "address": self.address,
+        })
+    }
+
+    fn from_remote(
+        remote: &Value,
+        local_id: &str,
+        existing: Option<&Self>,
+        _state: &SyncState,
+    ) -> Result<Self, String> {
+        let name = required(remote, "name")?;
+        let now = Utc::now();
+        Ok(Vendor {
+            id: local_id.to_string(),
+            created_at: remote_timestamp(remote, "created_at")
+                .or(existing.map(|vendor| vendor.created_at))
+                .unwrap_or(now),
+            updated_at: remote_timestamp(remote, "updated_at").unwrap_or(now),
+            address: string(remote, "address"),
+            email: string(remote, "email").unwrap_or_default(),
+            internal_id: string(remote, "internal_id"),
+            slug: string(remote, "slug").unwrap_or_else(|| name.to_lowercase().replace(' ', "-")),
+            name,
+            phone_country_code: existing
+                .map(|vendor| vendor.phone_country_code.clone())
+                .unwrap_or_else(|| "+1".to_string()),
+            phone_number: string(remote, "phone").unwrap_or_default(),
+            r#type: string(remote, "type").unwrap_or_default(),
+        })
+    }
+}
+
+impl SyncedManager for ServiceAreaManager {
+    type Record = ServiceArea;
+
+    fn records(&self) -> Vec<&ServiceArea> {
+        self.list_service_areas(None)
+    }
+
+    fn record(&self, local_id: &str) -> Option<&ServiceArea> {
+        self.get_service_area(local_id)
+    }
+
+    fn put(&mut self, record: ServiceArea) -> Result<(), String> {
+        self.put_service_area(record).map(|_| ())
+    }
+
+    fn remove(&mut self, local_id: &str) -> Result<(), String> {
+        self.remove_service_area(local_id)
+    }
+}
+
+impl SyncedManager for ZoneManager {
+    type Record = Zone;
+
+    fn records(&self) -> Vec<&Zone> {
+        self.list_zones(None)
+    }
+
+    fn record(&self, local_id: &str) -> Option<&Zone> {
+        self.get_zone(local_id)
+    }
+
+    fn put(&mut self, record: Zone) -> Result<(), String> {
+        self.put_zone(record).map(|_| ())
+    }
+
+    fn remove(&mut self, local_id: &str) -> Result<(), String> {
+        self.delete_zone(local_id).map(|_| ())
+    }
+}
+
+impl SyncedManager for VendorManager {
+    type Record = Vendor;
+
+    fn records(&self) -> Vec<&Vendor> {
+        self.list_vendors().iter().collect()
+    }
+
+    fn record(&self, local_id: &str) -> Option<&Vendor> {
+        self.get_vendor(local_id)
+    }
+
+    fn put(&mut self, record: Vendor) -> Result<(), String> {
+        self.put_vendor(record).map(|_| ())
+    }
+
+    fn remove(&mut self, local_id: &str) -> Result<(), String> {
+        self.try_delete_vendor(local_id).map(|_| ())
+    }
+}
+
+fn local_error(message: String) -> FleetbaseError {
+    FleetbaseError::Validation(message)
+}
+
+/// Syncs `ServiceAreaManager`, `ZoneManager` and `VendorManager` with the
+/// API:
+///
+/// ```ignore
+/// let store: Arc<dyn Store> = Arc::new(SqliteStore::open("fleet.db")?);
+/// let mut zones = ZoneManager::with_store(store.clone())?;
+/// let engine = SyncEngine::new(client, store);
+/// let report = engine.sync_zones(&mut zones).await?;
+/// ```
+///
+/// Using the managers' own store keeps the id map next to the records it
+/// describes. Sync service areas before zones so zones can reference them.
+pub struct SyncEngine {
+    client: FleetbaseClient,
+    store: Arc<dyn Store>,
+    policy: ConflictPolicy,
+    max_pull: usize,
+}
+
+impl SyncEngine {
+    pub fn new(client: FleetbaseClient, store: Arc<dyn Store>) -> Self {
+        Self {
+            client,
+            store,
+            policy: ConflictPolicy::default(),
+            max_pull: DEFAULT_MAX_PULL,
+        }
+    }
+
+    pub fn conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Fails a run that would pull more than `max` records of one kind.
+    pub fn max_pull(mut self, max: usize) -> Self {
+        self.max_pull = max;
+        self
+    }
+
+    /// The server's id for a synced local record.
+    pub fn public_id(
+        &self,
+        collection: Collection,
+        local_id: &str,
+    ) -> Result<Option<String>, FleetbaseError> {
+        Ok(self
+            .load_state()?
+            .public_id(collection, local_id)
+            .map(str::to_string))
+    }
+
+    /// The local id of a record the server knows as `public_id`.
+    pub fn local_id(
+        &self,
+        collection: Collection,
+        public_id: &str,
+    ) -> Result<Option<String>, FleetbaseError> {
+        Ok(self.load_state()?.local_id(collection, public_id))
+    }
+
+    pub async fn sync_service_areas(
+        &self,
+        service_areas: &mut ServiceAreaManager,
+    ) -> Result<SyncReport, FleetbaseError> {
+        self.sync(service_areas).await
+    }
+
+    pub async fn sync_zones(&self, zones: &mut ZoneManager) -> Result<SyncReport, FleetbaseError> {
+        self.sync(zones).await
+    }
+
+    pub async fn sync_vendors(
+        &self,
+        vendors: &mut VendorManager,
+    ) -> Result<SyncReport, FleetbaseError> {
+        self.sync(vendors).await
+    }
+
+    /// Service areas, then zones, then vendors.
+    pub async fn sync_all(
+        &self,
+        service_areas: &mut ServiceAreaManager,
+        zones: &mut ZoneManager,
+        vendors: &mut VendorManager,
+    ) -> Result<SyncReport, FleetbaseError> {
+        let mut report = self.sync(service_areas).await?;
+        report.merge(self.sync(zones).await?);
+        report.merge(self.sync(vendors).await?);
+        Ok(report)
+    }
+
+    fn load_state(&self) -> Result<SyncState, FleetbaseError> {
+        let mut state = SyncState::default();
+        let collection = |name: &str| {
+            [
+                Collection::ServiceAreas,
+                Collection::Zones,
+                Collection::Vendors,
+            ]
+            .into_iter()
+            .find(|c| c.name() == name)
+        };
+        for row in load_records::<StateRow>(self.store.as_ref(), Collection::SyncState)? {
+            match row {
+                StateRow::Mapping {
+                    collection: name,
+                    local_id,
+                    mapping,
+                } => {
+                    if let Some(collection) = collection(&name) {
+                        state.get_mut(collection).insert(&local_id, mapping);
+                    }
+                }
+                StateRow::Cursor {
+                    collection: name,
+                    last_pulled,
+                } => {
+                    if let Some(collection) = collection(&name) {
+                        state.get_mut(collection).last_pulled = last_pulled;
+                    }
+                }
+            }
+        }
+        state.mark_saved();
+        Ok(state)
+    }
+
+    /// Writes the rows changed since the last save in one batch. Called
+    /// after every create on the server, so an interrupted run never
+    /// creates the same record twice, and once when a run ends.
+    fn save_state(&self, state: &mut SyncState) -> Result<(), FleetbaseError> {
+        let writes = state.changes()?;
+        if !writes.is_empty() {
+            self.store.apply(&writes)?;
+        }
+        state.mark_saved();
+        Ok(())
+    }
+
+    async fn sync<M: SyncedManager>(&self, manager: &mut M) -> Result<SyncReport, FleetbaseError> {
+        let mut state = self.load_state()?;
+        // Saved even when the run fails, so pulled records keep their ids.
+        let report = self.run(manager, &mut state).await;
+        self.save_state(&mut state)?;
+        report
+    }
+
+    async fn run<M: SyncedManager>(
+        &self,
+        manager: &mut M,
+        state: &mut SyncState,
+    ) -> Result<SyncReport, FleetbaseError> {
+        let collection = M::Record::COLLECTION;
+        let mut report = SyncReport::default();
+        // Local ids already settled while pulling, left out of the push.
+        let mut settled = HashSet::new();
+
+        let mut query = ListQuery::new().sort_asc("updated_at");
+        if let Some(since) = state.get(collection).and_then(|s| s.last_pulled) {
+            query = query.updated_since(since);
+        }
+        let remote_records: Vec<Value> = self
+            .client
+            .paginate(M::Record::endpoint(None))
+            .list_query(&query)
+            .collect_all(self.max_pull)
+            .await?;
+
+        for remote in remote_records {
+            let Some(public_id) = string(&remote, "id") else {
+                continue;
+            };
+            let remote_updated_at = remote_timestamp(&remote, "updated_at");
+            state.get_mut(collection).pulled(remote_updated_at);
+            let deleted = remote.get("deleted_at").is_some_and(|at| !at.is_null());
+
+            let Some(local_id) = state.local_id(collection, &public_id) else {
+                if !deleted {
+                    let local_id = uuid::Uuid::new_v4().to_string();
+                    self.pull(manager, state, &mut report, &remote, &local_id)?;
+                }
+                continue;
+            };
+            if deleted {
+                settled.insert(local_id.clone());
+                self.deleted_remotely(manager, state, &mut report, &local_id)
+                    .await?;
+                continue;
+            }
+            let mapping = state.get_mut(collection).records[&local_id].clone();
+            if remote_updated_at.is_some() && remote_updated_at <= mapping.remote_updated_at {
+                // Our own push coming back.
+                continue;
+            }
+            settled.insert(local_id.clone());
+
+            let kind = match manager.record(&local_id) {
+                Some(local) if local.updated_at() > mapping.local_updated_at => {
+                    ConflictKind::BothChanged
+                }
+                Some(_) => {
+                    self.pull(manager, state, &mut report, &remote, &local_id)?;
+                    continue;
+                }
+                None => ConflictKind::DeletedLocally,
+            };
+            report.conflicts.push(Conflict {
+                collection,
+                local_id: local_id.clone(),
+                public_id: public_id.clone(),
+                kind,
+                resolution: self.policy,
+            });
+            match (self.policy, kind) {
+                (ConflictPolicy::RemoteWins, _) => {
+                    self.pull(manager, state, &mut report, &remote, &local_id)?
+                }
+                (ConflictPolicy::LocalWins, ConflictKind::DeletedLocally) => {
+                    self.delete_remote::<M::Record>(state, &mut report, &local_id)
+                        .await?
+                }
+                (ConflictPolicy::LocalWins, _) => {
+                    let local = manager.record(&local_id).unwrap().clone();
+                    self.push(manager, state, &mut report, &local).await?
+                }
+            }
+        }
+
+        // Deletions only show up in the pull when the server soft-deletes,
+        // so the records it did not send are checked against its full list.
+        let unseen: Vec<String> = state
+            .get(collection)
+            .map(|s| {
+                s.records
+                    .keys()
+                    .filter(|id| !settled.contains(*id) && manager.record(id).is_some())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !unseen.is_empty() {
+            let remote_ids = self.remote_ids::<M::Record>().await?;
+            for local_id in unseen {
+                let listed = state
+                    .public_id(collection, &local_id)
+                    .is_some_and(|public_id| remote_ids.contains(public_id));
+                if !listed {
+                    settled.insert(local_id.clone());
+                    self.deleted_remotely(manager, state, &mut report, &local_id)
+                        .await?;
+                }
+            }
+        }
+
+        let locals: Vec<M::Record> = manager
+            .records()
+            .into_iter()
+            .filter(|record| !settled.contains(record.local_id()))
+            .cloned()
+            .collect();
+        for local in &locals {
+            let changed = state
+                .get(collection)
+                .and_then(|s| s.records.get(local.local_id()))
+                .is_none_or(|mapping| local.updated_at() > mapping.local_updated_at);
+            if changed {
+                self.push(manager, state, &mut report, local).await?;
+            }
+        }
+
+        let deleted: Vec<String> = state
+            .get(collection)
+            .map(|s| {
+                s.records
+                    .keys()
+                    .filter(|id| !settled.contains(*id) && manager.record(id).is_none())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        for local_id in deleted {
+            self.delete_remote::<M::Record>(state, &mut report, &local_id)
+                .await?;
+        }
+
+        Ok(report)
+    }
+
+    /// Every public id the server has for `R`.
+    async fn remote_ids<R: Synced>(&self) -> Result<HashSet<String>, FleetbaseError> {
+        let mut pages = self.client.paginate::<Value>(R::endpoint(None));
+        let mut ids = HashSet::new();
+        while let Some(page) = pages.next_page().await? {
+            if page.is_empty() {
+                break;
+            }
+            ids.extend(page.iter().filter_map(|remote| string(remote, "id")));
+        }
+        Ok(ids)
+    }
+
+    /// Writes `remote` over the local record `local_id`, creating it if
+    /// needed.
+    fn pull<M: SyncedManager>(
+        &self,
+        manager: &mut M,
+        state: &mut SyncState,
+        report: &mut SyncReport,
+        remote: &Value,
+        local_id: &str,
+    ) -> Result<(), FleetbaseError> {
+        let collection = M::Record::COLLECTION;
+        let public_id = string(remote, "id").unwrap_or_default();
+        let record = match M::Record::from_remote(remote, local_id, manager.record(local_id), state)
+        {
+            Ok(record) => record,
+            Err(reason) => {
+                report.skipped.push(SkippedRecord {
+                    collection,
+                    public_id,
+                    reason,
+                });
+                return Ok(());
+            }
+        };
+        let local_updated_at = record.updated_at();
+        manager.put(record).map_err(local_error)?;
+        state.get_mut(collection).insert(
+            local_id,
+            Mapping {
+                public_id,
+                local_updated_at,
+                remote_updated_at: remote_timestamp(remote, "updated_at"),
+            },
+        );
+        report.pulled.push(local_id.to_string());
+        Ok(())
+    }
+
+    /// Creates or updates `local` on the server. An update the server
+    /// answers with 404 means it was deleted remotely.
+    async fn push<M: SyncedManager>(
+        &self,
+        manager: &mut M,
+        state: &mut SyncState,
+        report: &mut SyncReport,
+        local: &M::Record,
+    ) -> Result<(), FleetbaseError> {
+        let collection = M::Record::COLLECTION;
+        let local_id = local.local_id().to_string();
+        let payload = local.payload(state);
+        let public_id = state.public_id(collection, &local_id).map(str::to_string);
+
+        let response: Value = match &public_id {
+            None => {
+                self.client
+                    .post(M::Record::endpoint(None), &payload)
+                    .await?
+            }
+            Some(public_id) => {
+                match self
+                    .client
+                    .put(M::Record::endpoint(Some(public_id)), &payload)
+                    .await
+                {
+                    Ok(response) => response,
+                    Err(e) if e.is_not_found() => {
+                        return self
+                            .deleted_remotely(manager, state, report, &local_id)
+                            .await;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+
+        let created = public_id.is_none();
+        let public_id = string(&response, "id")
+            .or(public_id)
+            .ok_or_else(|| local_error(format!("{} response has no id", collection.name())))?;
+        state.get_mut(collection).insert(
+            &local_id,
+            Mapping {
+                public_id,
+                local_updated_at: local.updated_at(),
+                remote_updated_at: remote_timestamp(&response, "updated_at"),
+            },
+        );
+        if created {
+            self.save_state(state)?;
+        }
+        report.pushed.push(local_id);
+        Ok(())
+    }
+
+    /// Settles a record the server no longer has. An unchanged local copy
+    /// is removed; a changed one is a `DeletedRemotely` conflict, and with
+    /// `LocalWins` it is created again.
+    async fn deleted_remotely<M: SyncedManager>(
+        &self,
+        manager: &mut M,
+        state: &mut SyncState,
+        report: &mut SyncReport,
+        local_id: &str,
+    ) -> Result<(), FleetbaseError> {
+        let collection = M::Record::COLLECTION;
+        let Some(mapping) = state.get_mut(collection).remove(local_id) else {
+            return Ok(());
+        };
+        let Some(local) = manager.record(local_id).cloned() else {
+            return Ok(());
+        };
+        let changed = local.updated_at() > mapping.local_updated_at;
+        if changed {
+            report.conflicts.push(Conflict {
+                collection,
+                local_id: local_id.to_string(),
+                public_id: mapping.public_id,
+                kind: ConflictKind::DeletedRemotely,
+                resolution: self.policy,
+            });
+            if self.policy == ConflictPolicy::LocalWins {
+                return Box::pin(self.push(manager, state, report, &local)).await;
+            }
+        }
+        manager.remove(local_id).map_err(local_error)?;
+        report.deleted.push(local_id.to_string());
+        Ok(())
+    }
+
+    /// Deletes the server's copy of a record deleted locally. Already gone
+    /// counts as success.
+    async fn delete_remote<R: Synced>(
+        &self,
+        state: &mut SyncState,
+        report: &mut SyncReport,
+        local_id: &str,
+    ) -> Result<(), FleetbaseError> {
+        let Some(public_id) = state.public_id(R::COLLECTION, local_id).map(str::to_string) else {
+            return Ok(());
+        };
+        match self
+            .client
+            .delete::<Value>(R::endpoint(Some(&public_id)))
+            .await
+        {
+            Ok(_) => {}
+            Err(e) if e.is_not_found() => {}
+            Err(e) => return Err(e),
+        }
+        state.get_mut(R::COLLECTION).remove(local_id);
+        report.deleted.push(local_id.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::{LineString, Polygon};
+    use crate::store::MemoryStore;
+    use crate::testing::FakeFleetbase;
+
+    fn square() -> Border {
+        let ring = [
+            [103.85, 1.28],
+            [103.86, 1.28],
+            [103.86, 1.29],
+            [103.85, 1.29],
+            [103.85, 1.28],
+        ]
+        .iter()
+        .map(|[lng, lat]| Point::new(*lng, *lat).unwrap())
+        .collect();
+        Border::new(Polygon::new(LineString::new(ring), Vec::new()))
+    }
+
+    #[tokio::test]
+    async fn test_push_pull_and_id_map() {
+        let fake = FakeFleetbase::start().await;
+        let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+        let engine = SyncEngine::new(fake.client(), store.clone());
+        let mut areas = ServiceAreaManager::with_store(store.clone()).unwrap();
+        let mut zones = ZoneManager::with_store(store.clone()).unwrap();
+        let mut vendors = VendorManager::with_store(store.clone()).unwrap();
+
+        let area = areas
+            .create_service_area(
+                "Marina".to_string(),
+                "SG".to_string(),
+                Point::from_lat_lng(1.2834, 103.8607).unwrap(),
+                5_000.0,
+                "active".to_string(),
+                "district".to_string(),
+            )
+            .unwrap()
+            .id
+            .clone();
+        let zone = zones
+            .create_zone(
+                "Bayfront".to_string(),
+                square(),
+                "#00ff00".to_string(),
+                "#008800".to_string(),
+                areas.get_service_area(&area),
+            )
+            .unwrap()
+            .id
+            .clone();
+        fake.add_resource(
+            "vendors",
+            json!({"name": "Acme Couriers", "phone": "5550100"}),
+        );
+
+        let report = engine
+            .sync_all(&mut areas, &mut zones, &mut vendors)
+            .await
+            .unwrap();
+        assert_eq!(report.pushed, vec![area.clone(), zone.clone()]);
+        assert_eq!(report.pulled.len(), 1);
+        assert!(report.conflicts.is_empty());
+
+        let area_public = engine
+            .public_id(Collection::ServiceAreas, &area)
+            .unwrap()
+            .unwrap();
+        let zone_public = engine.public_id(Collection::Zones, &zone).unwrap().unwrap();
+        assert_eq!(
+            fake.resource("zones", &zone_public).unwrap()["service_area"],
+            json!(area_public)
+        );
+        assert_eq!(vendors.list_vendors()[0].name, "Acme Couriers");
+
+        // Nothing changed: nothing to do.
+        let report = engine
+            .sync_all(&mut areas, &mut zones, &mut vendors)
+            .await
+            .unwrap();
+        assert!(report.is_empty(), "{:?}", report);
+
+        // A remote edit comes down, a local edit goes up.
+        fake.update_resource("zones", &zone_public, json!({"name": "Marina Bay"}));
+        areas
+            .update_service_area(&area, "inactive".to_string())
+            .unwrap();
+        let report = engine
+            .sync_all(&mut areas, &mut zones, &mut vendors)
+            .await
+            .unwrap();
+        assert_eq!(report.pulled, vec![zone.clone()]);
+        assert_eq!(report.pushed, vec![area.clone()]);
+        assert_eq!(zones.get_zone(&zone).unwrap().name, "Marina Bay");
+        assert_eq!(
+            zones.get_zone(&zone).unwrap().service_area,
+            Some(area.clone())
+        );
+        assert_eq!(
+            fake.resource("service-areas", &area_public).unwrap()["status"],
+            "inactive"
+        );
+
+        // A local delete is pushed.
+        zones.delete_zone(&zone).unwrap();
+        let report = engine.sync_zones(&mut zones).await.unwrap();
+        assert_eq!(report.deleted, vec![zone.clone()]);
+        assert!(fake.resource("zones", &zone_public).is_none());
+        assert!(engine
+            .public_id(Collection::Zones, &zone)
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_conflicts_follow_policy() {
+        let fake = FakeFleetbase::start().await;
+        let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+        let mut vendors = VendorManager::with_store(store.clone()).unwrap();
+        let engine = SyncEngine::new(fake.client(), store.clone());
+
+        let mut ids = Vec::new();
+        for name in ["Acme", "Bolt", "Cargo"] {
+            let vendor = vendors
+                .try_create_vendor(
+                    "ops@example.com".to_string(),
+                    name.to_string(),
+                    "5550100".to_string(),
+                    "logistics".to_string(),
+                    None,
+                )
+                .unwrap();
+            ids.push(vendor.id.clone());
+        }
+        engine.sync_vendors(&mut vendors).await.unwrap();
+        let public: Vec<String> = ids
+            .iter()
+            .map(|id| engine.public_id(Collection::Vendors, id).unwrap().unwrap())
+            .collect();
+
+        // Both sides edit Acme; Bolt is deleted remotely after a local edit.
+        fake.update_resource("vendors", &public[0], json!({"name": "Acme Remote"}));
+        vendors
+            .update_vendor(
+                &ids[0],
+                None,
+                Some("Acme Local".to_string()),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        fake.remove_resource("vendors", &public[1]);
+        vendors
+            .update_vendor(
+                &ids[1],
+                None,
+                Some("Bolt Local".to_string()),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        let report = engine.sync_vendors(&mut vendors).await.unwrap();
+        let kinds: Vec<ConflictKind> = report.conflicts.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![ConflictKind::BothChanged, ConflictKind::DeletedRemotely]
+        );
+        assert_eq!(vendors.get_vendor(&ids[0]).unwrap().name, "Acme Remote");
+        assert!(vendors.get_vendor(&ids[1]).is_none());
+
+        // With local wins, a local edit beats a remote one.
+        let engine = engine.conflict_policy(ConflictPolicy::LocalWins);
+        fake.update_resource("vendors", &public[2], json!({"name": "Cargo Remote"}));
+        vendors
+            .update_vendor(
+                &ids[2],
+                None,
+                Some("Cargo Local".to_string()),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        let report = engine.sync_vendors(&mut vendors).await.unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.pushed, vec![ids[2].clone()]);
+        assert_eq!(
+            fake.resource("vendors", &public[2]).unwrap()["name"],
+            "Cargo Local"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remote_deletes_reach_unchanged_records() {
+        let fake = FakeFleetbase::start().await;
+        let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+        let mut vendors = VendorManager::with_store(store.clone()).unwrap();
+        let engine = SyncEngine::new(fake.client(), store.clone());
+
+        let acme = fake.add_resource("vendors", json!({"name": "Acme", "phone": "5550100"}));
+        let bolt = fake.add_resource("vendors", json!({"name": "Bolt", "phone": "5550101"}));
+        engine.sync_vendors(&mut vendors).await.unwrap();
+        let acme_local = engine
+            .local_id(Collection::Vendors, &acme)
+            .unwrap()
+            .unwrap();
+        let bolt_local = engine
+            .local_id(Collection::Vendors, &bolt)
+            .unwrap()
+            .unwrap();
+
+        // One is removed outright, the other comes back soft-deleted.
+        fake.remove_resource("vendors", &acme);
+        fake.update_resource(
+            "vendors",
+            &bolt,
+            json!({"deleted_at": "2030-01-01 00:00:00"}),
+        );
+        let report = engine.sync_vendors(&mut vendors).await.unwrap();
+        assert!(report.conflicts.is_empty(), "{:?}", report);
+        let mut deleted = report.deleted.clone();
+        deleted.sort();
+        let mut expected = vec![acme_local.clone(), bolt_local.clone()];
+        expected.sort();
+        assert_eq!(deleted, expected);
+        assert!(vendors.list_vendors().is_empty());
+        assert!(engine
+            .local_id(Collection::Vendors, &acme)
+            .unwrap()
+            .is_none());
+
+        // The mappings are gone from the store too.
+        let engine = SyncEngine::new(fake.client(), store.clone());
+        assert!(engine
+            .public_id(Collection::Vendors, &bolt_local)
+            .unwrap()
+            .is_none());
+        assert!(engine.sync_vendors(&mut vendors).await.unwrap().is_empty());
+    }
+}
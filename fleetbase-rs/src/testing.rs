@@ -0,0 +1,1162 @@
+//! An in-process fake of the Fleetbase API for integration tests.
+//!
+//! `FakeFleetbase` binds to an ephemeral localhost port and keeps all state in
+//! memory, so tests can drive the real client code end to end without
+//! network access:
+//!
+//! ```ignore
+//! let fake = FakeFleetbase::start().await;
+//! let client = fake.client();
+//! let order: Order = client.orders().create(&json!({"payload": {}})).await?;
+//! ```
+
+use axum::body::Bytes;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+use crate::client::FleetbaseClient;
+use crate::retry::RetryPolicy;
+use crate::utils::timestamp;
+
+/// The code accepted by `drivers/verify-code` for every SMS login.
+pub const SMS_CODE: &str = "000000";
+
+/// Statuses an order walks through after `start`, as returned by
+/// `next-activity` and accepted by `update-activity`.
+const ACTIVITY_FLOW: [&str; 3] = ["driver_enroute", "enroute", "completed"];
+
+#[derive(Default)]
+struct FakeState {
+    orders: HashMap<String, Value>,
+    drivers: HashMap<String, Value>,
+    organizations: HashMap<String, Value>,
+    tracking_numbers: HashMap<String, Value>,
+    service_quotes: HashMap<String, Value>,
+    purchase_rates: HashMap<String, Value>,
+    /// Plain CRUD resources such as zones, keyed by path then id.
+    resources: HashMap<&'static str, HashMap<String, Value>>,
+    /// Phone numbers that requested an SMS code and have not verified yet.
+    pending_sms: Vec<String>,
+    quote_amount: f64,
+    currency: String,
+    requests: Vec<String>,
+}
+
+type SharedState = Arc<Mutex<FakeState>>;
+
+pub struct FakeFleetbase {
+    addr: SocketAddr,
+    state: SharedState,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl FakeFleetbase {
+    /// Starts the server on `127.0.0.1` with a random port. It stops when the
+    /// returned value is dropped.
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(FakeState {
+            quote_amount: 1500.0,
+            currency: "USD".to_string(),
+            ..FakeState::default()
+        }));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind fake Fleetbase server");
+        let addr = listener.local_addr().expect("listener has no address");
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let app = Router::new()
+            .nest("/v1", routes())
+            .layer(middleware::from_fn_with_state(state.clone(), authenticate))
+            .with_state(state.clone());
+        tokio::spawn(async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async {
+                    stopped.await.ok();
+                })
+                .await
+                .ok();
+        });
+
+        Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// Base URL without the API version, e.g. `http://127.0.0.1:49152`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A client pointed at this server. Retries are disabled so that failures
+    /// surface immediately.
+    pub fn client(&self) -> FleetbaseClient {
+        FleetbaseClient::builder("fake-api-key")
+            .base_url(self.url())
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .expect("failed to build client for fake Fleetbase server")
+    }
+
+    /// Seeds a driver. `attributes` may include `phone`, `email` and
+    /// `password`; an `id` is generated when missing. Returns the id.
+    pub fn add_driver(&self, attributes: Value) -> String {
+        let mut driver = into_object(attributes);
+        let id = driver
+            .get("id")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| public_id("driver"));
+        driver.insert("id".to_string(), json!(id));
+        driver.entry("online").or_insert(json!(false));
+        driver.entry("status").or_insert(json!("active"));
+        stamp(&mut driver, true);
+
+        self.lock()
+            .drivers
+            .insert(id.clone(), Value::Object(driver));
+        id
+    }
+
+    /// Seeds an organization and returns its id. Drivers belong to every
+    /// organization unless seeded with an `organizations` list of ids.
+    pub fn add_organization(&self, attributes: Value) -> String {
+        let mut organization = into_object(attributes);
+        let id = organization
+            .get("id")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| public_id("org"));
+        organization.insert("id".to_string(), json!(id));
+        stamp(&mut organization, true);
+
+        self.lock()
+            .organizations
+            .insert(id.clone(), Value::Object(organization));
+        id
+    }
+
+    /// Amount and currency returned for every service quote.
+    pub fn set_quote(&self, amount: f64, currency: &str) {
+        let mut state = self.lock();
+        state.quote_amount = amount;
+        state.currency = currency.to_string();
+    }
+
+    pub fn order(&self, id: &str) -> Option<Value> {
+        self.lock().orders.get(id).cloned()
+    }
+
+    pub fn driver(&self, id: &str) -> Option<Value> {
+        self.lock().drivers.get(id).cloned()
+    }
+
+    /// Seeds a `service-areas`, `zones` or `vendors` record and returns its
+    /// id.
+    pub fn add_resource(&self, kind: &str, attributes: Value) -> String {
+        let kind = resource_kind(kind);
+        let mut resource = into_object(attributes);
+        let id = public_id(resource_prefix(kind));
+        resource.insert("id".to_string(), json!(id));
+        stamp(&mut resource, true);
+        self.lock()
+            .resources
+            .entry(kind)
+            .or_default()
+            .insert(id.clone(), Value::Object(resource));
+        id
+    }
+
+    /// Changes a seeded or synced record as another API client would.
+    pub fn update_resource(&self, kind: &str, id: &str, changes: Value) {
+        let mut state = self.lock();
+        if let Some(Value::Object(resource)) = state
+            .resources
+            .get_mut(resource_kind(kind))
+            .and_then(|resources| resources.get_mut(id))
+        {
+            resource.extend(into_object(changes));
+            stamp(resource, false);
+        }
+    }
+
+    pub fn remove_resource(&self, kind: &str, id: &str) -> Option<Value> {
+        self.lock()
+            .resources
+            .get_mut(resource_kind(kind))
+            .and_then(|resources| resources.remove(id))
+    }
+
+    pub fn resource(&self, kind: &str, id: &str) -> Option<Value> {
+        self.lock()
+            .resources
+            .get(resource_kind(kind))
+            .and_then(|resources| resources.get(id))
+            .cloned()
+    }
+
+    pub fn resources(&self, kind: &str) -> Vec<Value> {
+        self.lock()
+            .resources
+            .get(resource_kind(kind))
+            .map(|resources| resources.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn tracking_number(&self, id: &str) -> Option<Value> {
+        self.lock().tracking_numbers.get(id).cloned()
+    }
+
+    /// Every request served so far as `METHOD /path`.
+    pub fn requests(&self) -> Vec<String> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Drop for FakeFleetbase {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/orders", get(list_orders).post(create_order))
+        .route(
+            "/orders/:id",
+            get(get_order).put(update_order).delete(delete_order),
+        )
+        .route("/orders/:id/dispatch", post(dispatch_order))
+        .route("/orders/:id/start", post(start_order))
+        .route("/orders/:id/next-activity", get(next_activity))
+        .route("/orders/:id/update-activity", post(update_activity))
+        .route("/orders/:id/complete", post(complete_order))
+        .route("/orders/:id/cancel", delete(cancel_order))
+        .route("/drivers/login", post(login))
+        .route("/drivers/login-with-sms", post(login_with_sms))
+        .route("/drivers/verify-code", post(verify_code))
+        .route("/drivers/:id", get(get_driver))
+        .route("/drivers/:id/track", post(track_driver))
+        .route("/drivers/:id/organizations", get(driver_organizations))
+        .route(
+            "/drivers/:id/switch-organization",
+            post(switch_driver_organization),
+        )
+        .route(
+            "/drivers/:id/current-organization",
+            get(current_driver_organization),
+        )
+        .merge(resource_routes("service-areas"))
+        .merge(resource_routes("zones"))
+        .merge(resource_routes("vendors"))
+        .route("/tracking-numbers", post(create_tracking_number))
+        .route("/tracking-numbers/:id", get(get_tracking_number))
+        .route("/service-quotes", get(query_service_quote))
+        .route("/service-quotes/:id", get(get_service_quote))
+        .route("/purchase-rates", post(create_purchase_rate))
+        .route("/purchase-rates/:id", get(get_purchase_rate))
+}
+
+async fn authenticate(State(state): State<SharedState>, request: Request, next: Next) -> Response {
+    state
+        .lock()
+        .unwrap()
+        .requests
+        .push(format!("{} {}", request.method(), request.uri().path()));
+
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("Bearer ") && v.len() > "Bearer ".len());
+    if !authorized {
+        return error(StatusCode::UNAUTHORIZED, "Unauthenticated.");
+    }
+    next.run(request).await
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+fn not_found(kind: &str, id: &str) -> Response {
+    error(
+        StatusCode::NOT_FOUND,
+        format!("{} resource not found: {}", kind, id),
+    )
+}
+
+/// Lenient JSON body: the client sends `{}`, form-like string maps or
+/// nothing at all depending on the action.
+fn body_object(body: &Bytes) -> Map<String, Value> {
+    if body.is_empty() {
+        return Map::new();
+    }
+    serde_json::from_slice(body)
+        .map(into_object)
+        .unwrap_or_default()
+}
+
+fn into_object(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(object) => object,
+        _ => Map::new(),
+    }
+}
+
+fn public_id(prefix: &str) -> String {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    format!("{}_{}", prefix, &id[..7])
+}
+
+fn now() -> Value {
+    json!(Utc::now().to_rfc3339())
+}
+
+fn stamp(object: &mut Map<String, Value>, created: bool) {
+    if created {
+        object.insert("created_at".to_string(), now());
+    }
+    object.insert("updated_at".to_string(), now());
+}
+
+fn new_tracking_number(state: &mut FakeState, owner: &str, region: &str) -> Value {
+    let id = public_id("track");
+    let code = uuid::Uuid::new_v4().simple().to_string()[..10].to_uppercase();
+    let mut tracking = Map::new();
+    tracking.insert("id".to_string(), json!(id));
+    tracking.insert("tracking_number".to_string(), json!(code));
+    tracking.insert("name".to_string(), json!(code));
+    tracking.insert("owner".to_string(), json!(owner));
+    tracking.insert("region".to_string(), json!(region));
+    tracking.insert("status".to_string(), json!("created"));
+    tracking.insert("type".to_string(), json!("order"));
+    tracking.insert("latitude".to_string(), Value::Null);
+    tracking.insert("longitude".to_string(), Value::Null);
+    stamp(&mut tracking, true);
+
+    let tracking = Value::Object(tracking);
+    state.tracking_numbers.insert(id, tracking.clone());
+    tracking
+}
+
+async fn list_orders(
+    State(state): State<SharedState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let state = state.lock().unwrap();
+    let mut orders: Vec<&Value> = state
+        .orders
+        .values()
+        .filter(|order| {
+            query
+                .get("status")
+                .is_none_or(|status| order["status"] == json!(status))
+        })
+        .collect();
+    orders.sort_by(|a, b| a["created_at"].as_str().cmp(&b["created_at"].as_str()));
+    Json(json!(orders)).into_response()
+}
+
+async fn create_order(State(state): State<SharedState>, body: Bytes) -> Response {
+    let mut state = state.lock().unwrap();
+    let mut order = body_object(&body);
+    let id = public_id("order");
+
+    if let Some(rate_id) = order.get("purchase_rate").and_then(Value::as_str) {
+        let Some(rate) = state.purchase_rates.get_mut(rate_id) else {
+            return not_found("PurchaseRate", rate_id);
+        };
+        if !rate["order"].is_null() {
+            return error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Purchase rate has already been used for an order.",
+            );
+        }
+        rate["order"] = json!(id);
+        rate["status"] = json!("purchased");
+    }
+
+    let tracking = new_tracking_number(&mut state, &id, "US");
+    order.insert("id".to_string(), json!(id));
+    order.insert("status".to_string(), json!("created"));
+    order.insert("dispatched".to_string(), json!(false));
+    order.insert(
+        "tracking_number".to_string(),
+        tracking["tracking_number"].clone(),
+    );
+    order.entry("meta").or_insert(json!({}));
+    stamp(&mut order, true);
+
+    let order = Value::Object(order);
+    state.orders.insert(id, order.clone());
+    (StatusCode::OK, Json(order)).into_response()
+}
+
+async fn get_order(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    match state.lock().unwrap().orders.get(&id) {
+        Some(order) => Json(order.clone()).into_response(),
+        None => not_found("Order", &id),
+    }
+}
+
+async fn update_order(
+    State(state): State<SharedState>,
+    Path(id): Path<String>,
+    body: Bytes,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    let Some(Value::Object(order)) = state.orders.get_mut(&id) else {
+        return not_found("Order", &id);
+    };
+    for (key, value) in body_object(&body) {
+        if key != "id" && key != "status" {
+            order.insert(key, value);
+        }
+    }
+    stamp(order, false);
+    Json(Value::Object(order.clone())).into_response()
+}
+
+async fn delete_order(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    match state.lock().unwrap().orders.remove(&id) {
+        Some(order) => Json(order).into_response(),
+        None => not_found("Order", &id),
+    }
+}
+
+/// Moves an order to `status` if its current status is one of `from`.
+fn transition(
+    state: &SharedState,
+    id: &str,
+    from: &[&str],
+    status: &str,
+    timestamp: Option<&str>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    let Some(Value::Object(order)) = state.orders.get_mut(id) else {
+        return not_found("Order", id);
+    };
+    let current = order
+        .get("status")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    if !from.contains(&current.as_str()) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            format!("Order cannot move from {} to {}.", current, status),
+        );
+    }
+
+    order.insert("status".to_string(), json!(status));
+    if let Some(field) = timestamp {
+        order.insert(field.to_string(), now());
+    }
+    stamp(order, false);
+    Json(Value::Object(order.clone())).into_response()
+}
+
+async fn dispatch_order(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    let response = transition(
+        &state,
+        &id,
+        &["created"],
+        "dispatched",
+        Some("dispatched_at"),
+    );
+    if response.status().is_success() {
+        if let Some(order) = state.lock().unwrap().orders.get_mut(&id) {
+            order["dispatched"] = json!(true);
+        }
+    }
+    response
+}
+
+async fn start_order(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    transition(
+        &state,
+        &id,
+        &["dispatched"],
+        ACTIVITY_FLOW[0],
+        Some("started_at"),
+    )
+}
+
+fn following_activity(status: &str) -> Option<&'static str> {
+    let position = ACTIVITY_FLOW.iter().position(|code| *code == status)?;
+    ACTIVITY_FLOW.get(position + 1).copied()
+}
+
+fn activity(code: &str) -> Value {
+    json!({
+        "code": code,
+        "status": code.replace('_', " "),
+        "details": format!("Order is now {}", code.replace('_', " ")),
+        "complete": code == "completed",
+    })
+}
+
+async fn next_activity(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    let state = state.lock().unwrap();
+    let Some(order) = state.orders.get(&id) else {
+        return not_found("Order", &id);
+    };
+    let status = order["status"].as_str().unwrap_or_default();
+    match following_activity(status) {
+        Some(code) => Json(activity(code)).into_response(),
+        None => error(
+            StatusCode::BAD_REQUEST,
+            format!("Order has no next activity while {}.", status),
+        ),
+    }
+}
+
+async fn update_activity(
+    State(state): State<SharedState>,
+    Path(id): Path<String>,
+    body: Bytes,
+) -> Response {
+    let current = match state.lock().unwrap().orders.get(&id) {
+        Some(order) => order["status"].as_str().unwrap_or_default().to_string(),
+        None => return not_found("Order", &id),
+    };
+    let Some(expected) = following_activity(&current) else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            format!("Order has no next activity while {}.", current),
+        );
+    };
+
+    let body = body_object(&body);
+    let code = body.get("code").and_then(Value::as_str).unwrap_or(expected);
+    if code != expected {
+        return error(
+            StatusCode::BAD_REQUEST,
+            format!("Expected activity {} but got {}.", expected, code),
+        );
+    }
+    transition(&state, &id, &[current.as_str()], code, None)
+}
+
+async fn complete_order(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    transition(&state, &id, &ACTIVITY_FLOW[..2], "completed", None)
+}
+
+async fn cancel_order(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    transition(
+        &state,
+        &id,
+        &["created", "dispatched", "driver_enroute", "enroute"],
+        "canceled",
+        None,
+    )
+}
+
+fn driver_with_token(driver: &mut Value) -> Value {
+    driver["token"] = json!(format!("driver-token-{}", uuid::Uuid::new_v4().simple()));
+    without_password(driver)
+}
+
+fn without_password(driver: &Value) -> Value {
+    let mut driver = driver.clone();
+    if let Value::Object(object) = &mut driver {
+        object.remove("password");
+    }
+    driver
+}
+
+async fn login(State(state): State<SharedState>, body: Bytes) -> Response {
+    let body = body_object(&body);
+    let identity = body.get("identity").cloned().unwrap_or_default();
+    let password = body.get("password").cloned().unwrap_or_default();
+
+    let mut state = state.lock().unwrap();
+    let driver = state.drivers.values_mut().find(|driver| {
+        (driver["email"] == identity || driver["phone"] == identity)
+            && !password.is_null()
+            && driver["password"] == password
+    });
+    match driver {
+        Some(driver) => Json(driver_with_token(driver)).into_response(),
+        None => error(StatusCode::UNAUTHORIZED, "Authentication failed."),
+    }
+}
+
+async fn login_with_sms(State(state): State<SharedState>, body: Bytes) -> Response {
+    let body = body_object(&body);
+    let Some(phone) = body.get("phone").and_then(Value::as_str) else {
+        return error(StatusCode::BAD_REQUEST, "A phone number is required.");
+    };
+
+    let mut state = state.lock().unwrap();
+    if !state
+        .drivers
+        .values()
+        .any(|driver| driver["phone"] == phone)
+    {
+        return error(StatusCode::NOT_FOUND, "No driver with this phone number.");
+    }
+    state.pending_sms.push(phone.to_string());
+    Json(json!({ "status": "OK", "method": "sms" })).into_response()
+}
+
+async fn verify_code(State(state): State<SharedState>, body: Bytes) -> Response {
+    let body = body_object(&body);
+    let identity = body
+        .get("identity")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let code = body.get("code").and_then(Value::as_str).unwrap_or_default();
+
+    let mut state = state.lock().unwrap();
+    let Some(position) = state.pending_sms.iter().position(|p| *p == identity) else {
+        return error(StatusCode::BAD_REQUEST, "No verification code was sent.");
+    };
+    if code != SMS_CODE {
+        return error(StatusCode::UNAUTHORIZED, "Invalid verification code.");
+    }
+    state.pending_sms.remove(position);
+
+    match state
+        .drivers
+        .values_mut()
+        .find(|driver| driver["phone"] == identity)
+    {
+        Some(driver) => Json(driver_with_token(driver)).into_response(),
+        None => error(StatusCode::NOT_FOUND, "No driver with this phone number."),
+    }
+}
+
+async fn get_driver(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    match state.lock().unwrap().drivers.get(&id) {
+        Some(driver) => Json(without_password(driver)).into_response(),
+        None => not_found("Driver", &id),
+    }
+}
+
+async fn track_driver(
+    State(state): State<SharedState>,
+    Path(id): Path<String>,
+    body: Bytes,
+) -> Response {
+    let body = body_object(&body);
+    let coordinate = |key: &str| match body.get(key) {
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.parse().ok(),
+        _ => None,
+    };
+    let (Some(latitude), Some(longitude)) = (coordinate("latitude"), coordinate("longitude"))
+    else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Latitude and longitude are required.",
+        );
+    };
+
+    let mut state = state.lock().unwrap();
+    let Some(Value::Object(driver)) = state.drivers.get_mut(&id) else {
+        return not_found("Driver", &id);
+    };
+    driver.insert(
+        "location".to_string(),
+        json!({ "type": "Point", "coordinates": [longitude, latitude] }),
+    );
+    for key in ["heading", "speed", "altitude"] {
+        if let Some(value) = body.get(key) {
+            driver.insert(key.to_string(), value.clone());
+        }
+    }
+    driver.insert("online".to_string(), json!(true));
+    stamp(driver, false);
+    Json(without_password(&Value::Object(driver.clone()))).into_response()
+}
+
+fn organizations_of(state: &FakeState, driver: &Value) -> Vec<Value> {
+    let mut organizations: Vec<Value> = match driver.get("organizations") {
+        Some(Value::Array(ids)) => ids
+            .iter()
+            .filter_map(|id| id.as_str())
+            .filter_map(|id| state.organizations.get(id).cloned())
+            .collect(),
+        _ => state.organizations.values().cloned().collect(),
+    };
+    organizations.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+    organizations
+}
+
+async fn driver_organizations(
+    State(state): State<SharedState>,
+    Path(id): Path<String>,
+) -> Response {
+    let state = state.lock().unwrap();
+    match state.drivers.get(&id) {
+        Some(driver) => Json(organizations_of(&state, driver)).into_response(),
+        None => not_found("Driver", &id),
+    }
+}
+
+async fn switch_driver_organization(
+    State(state): State<SharedState>,
+    Path(id): Path<String>,
+    body: Bytes,
+) -> Response {
+    let body = body_object(&body);
+    let next = body.get("next").and_then(Value::as_str).unwrap_or_default();
+
+    let mut state = state.lock().unwrap();
+    let Some(driver) = state.drivers.get(&id) else {
+        return not_found("Driver", &id);
+    };
+    let Some(organization) = organizations_of(&state, driver)
+        .into_iter()
+        .find(|org| org["id"] == next)
+    else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "Driver is not a member of this organization.",
+        );
+    };
+
+    let driver = state.drivers.get_mut(&id).expect("driver exists");
+    driver["current_organization"] = json!(next);
+    let mut organization = organization;
+    organization["token"] = driver_with_token(driver)["token"].clone();
+    Json(organization).into_response()
+}
+
+async fn current_driver_organization(
+    State(state): State<SharedState>,
+    Path(id): Path<String>,
+) -> Response {
+    let state = state.lock().unwrap();
+    let Some(driver) = state.drivers.get(&id) else {
+        return not_found("Driver", &id);
+    };
+    let organizations = organizations_of(&state, driver);
+    let current = match driver.get("current_organization") {
+        Some(current) => organizations.into_iter().find(|org| org["id"] == *current),
+        None => organizations.into_iter().next(),
+    };
+    match current {
+        Some(organization) => Json(organization).into_response(),
+        None => error(StatusCode::NOT_FOUND, "Driver has no organization."),
+    }
+}
+
+const RESOURCE_KINDS: [(&str, &str); 3] = [
+    ("service-areas", "service_area"),
+    ("zones", "zone"),
+    ("vendors", "vendor"),
+];
+
+fn resource_kind(kind: &str) -> &'static str {
+    RESOURCE_KINDS
+        .iter()
+        .find(|(path, _)| *path == kind)
+        .map(|(path, _)| *path)
+        .unwrap_or_else(|| panic!("the fake server has no {} resource", kind))
+}
+
+fn resource_prefix(kind: &str) -> &'static str {
+    RESOURCE_KINDS
+        .iter()
+        .find(|(path, _)| *path == kind)
+        .map(|(_, prefix)| *prefix)
+        .unwrap()
+}
+
+/// List, create, read, update and delete for a resource with no behavior of
+/// its own. Lists honor `updated_after`, `limit` and `page`, oldest update
+/// first.
+fn resource_routes(kind: &'static str) -> Router<SharedState> {
+    Router::new()
+        .route(
+            &format!("/{}", kind),
+            get(
+                move |State(state): State<SharedState>,
+                      Query(query): Query<HashMap<String, String>>| {
+                    list_resources(kind, state, query)
+                },
+            )
+            .post(move |State(state): State<SharedState>, body: Bytes| {
+                create_resource(kind, state, body)
+            }),
+        )
+        .route(
+            &format!("/{}/:id", kind),
+            get(
+                move |State(state): State<SharedState>, Path(id): Path<String>| async move {
+                    match state
+                        .lock()
+                        .unwrap()
+                        .resources
+                        .get(kind)
+                        .and_then(|r| r.get(&id))
+                    {
+                        Some(resource) => Json(resource.clone()).into_response(),
+                        None => not_found(kind, &id),
+                    }
+                },
+            )
+            .put(
+                move |State(state): State<SharedState>, Path(id): Path<String>, body: Bytes| {
+                    update_resource(kind, state, id, body)
+                },
+            )
+            .delete(
+                move |State(state): State<SharedState>, Path(id): Path<String>| async move {
+                    match state
+                        .lock()
+                        .unwrap()
+                        .resources
+                        .get_mut(kind)
+                        .and_then(|r| r.remove(&id))
+                    {
+                        Some(resource) => Json(resource).into_response(),
+                        None => not_found(kind, &id),
+                    }
+                },
+            ),
+        )
+}
+
+async fn list_resources(
+    kind: &'static str,
+    state: SharedState,
+    query: HashMap<String, String>,
+) -> Response {
+    let state = state.lock().unwrap();
+    let after = query
+        .get("updated_after")
+        .and_then(|at| timestamp::parse(at));
+    let mut resources: Vec<&Value> = state
+        .resources
+        .get(kind)
+        .map(|resources| resources.values().collect())
+        .unwrap_or_default();
+    resources.retain(|resource| {
+        after.is_none_or(|after| {
+            resource["updated_at"]
+                .as_str()
+                .and_then(timestamp::parse)
+                .is_some_and(|at| at > after)
+        })
+    });
+    resources.sort_by(|a, b| {
+        let at = |v: &Value| v["updated_at"].as_str().and_then(timestamp::parse);
+        at(a).cmp(&at(b))
+    });
+
+    let limit = query
+        .get("limit")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(usize::MAX);
+    let page = query
+        .get("page")
+        .and_then(|p| p.parse::<usize>().ok())
+        .unwrap_or(1);
+    let page: Vec<&Value> = resources
+        .into_iter()
+        .skip(limit.saturating_mul(page.saturating_sub(1)))
+        .take(limit)
+        .collect();
+    Json(json!(page)).into_response()
+}
+
+async fn create_resource(kind: &'static str, state: SharedState, body: Bytes) -> Response {
+    let mut resource = body_object(&body);
+    let id = public_id(resource_prefix(kind));
+    resource.insert("id".to_string(), json!(id));
+    stamp(&mut resource, true);
+
+    let resource = Value::Object(resource);
+    state
+        .lock()
+        .unwrap()
+        .resources
+        .entry(kind)
+        .or_default()
+        .insert(id, resource.clone());
+    Json(resource).into_response()
+}
+
+async fn update_resource(
+    kind: &'static str,
+    state: SharedState,
+    id: String,
+    body: Bytes,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    let Some(Value::Object(resource)) = state.resources.get_mut(kind).and_then(|r| r.get_mut(&id))
+    else {
+        return not_found(kind, &id);
+    };
+    for (key, value) in body_object(&body) {
+        if key != "id" {
+            resource.insert(key, value);
+        }
+    }
+    stamp(resource, false);
+    Json(Value::Object(resource.clone())).into_response()
+}
+
+async fn create_tracking_number(State(state): State<SharedState>, body: Bytes) -> Response {
+    let body = body_object(&body);
+    let Some(owner) = body.get("owner").and_then(Value::as_str) else {
+        return error(StatusCode::BAD_REQUEST, "An owner is required.");
+    };
+    let region = body.get("region").and_then(Value::as_str).unwrap_or("US");
+
+    let mut state = state.lock().unwrap();
+    Json(new_tracking_number(&mut state, owner, region)).into_response()
+}
+
+async fn get_tracking_number(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    match state.lock().unwrap().tracking_numbers.get(&id) {
+        Some(tracking) => Json(tracking.clone()).into_response(),
+        None => not_found("TrackingNumber", &id),
+    }
+}
+
+async fn query_service_quote(
+    State(state): State<SharedState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let has_route = query.contains_key("pickup") && query.contains_key("dropoff");
+    if !query.contains_key("payload") && !has_route {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "A payload or pickup and dropoff are required.",
+        );
+    }
+
+    let mut state = state.lock().unwrap();
+    let id = public_id("quote");
+    let mut quote = Map::new();
+    quote.insert("id".to_string(), json!(id));
+    quote.insert("request_id".to_string(), json!(public_id("request")));
+    quote.insert("service_rate".to_string(), json!(public_id("service")));
+    quote.insert("amount".to_string(), json!(state.quote_amount));
+    quote.insert("currency".to_string(), json!(state.currency));
+    for key in ["payload", "pickup", "dropoff", "service_type"] {
+        if let Some(value) = query.get(key) {
+            quote.insert(key.to_string(), json!(value));
+        }
+    }
+    stamp(&mut quote, true);
+
+    let quote = Value::Object(quote);
+    state.service_quotes.insert(id, quote.clone());
+    Json(quote).into_response()
+}
+
+async fn get_service_quote(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    match state.lock().unwrap().service_quotes.get(&id) {
+        Some(quote) => Json(quote.clone()).into_response(),
+        None => not_found("ServiceQuote", &id),
+    }
+}
+
+async fn create_purchase_rate(State(state): State<SharedState>, body: Bytes) -> Response {
+    let body = body_object(&body);
+    let Some(quote_id) = body.get("service_quote").and_then(Value::as_str) else {
+        return error(StatusCode::BAD_REQUEST, "A service quote is required.");
+    };
+
+    let mut state = state.lock().unwrap();
+    if !state.service_quotes.contains_key(quote_id) {
+        return not_found("ServiceQuote", quote_id);
+    }
+
+    let id = public_id("purchase_rate");
+    let mut rate = Map::new();
+    rate.insert("id".to_string(), json!(id));
+    rate.insert("service_quote".to_string(), json!(quote_id));
+    rate.insert(
+        "customer".to_string(),
+        body.get("customer").cloned().unwrap_or(Value::Null),
+    );
+    rate.insert("order".to_string(), Value::Null);
+    rate.insert("status".to_string(), json!("created"));
+    stamp(&mut rate, true);
+
+    let rate = Value::Object(rate);
+    state.purchase_rates.insert(id, rate.clone());
+    Json(rate).into_response()
+}
+
+async fn get_purchase_rate(State(state): State<SharedState>, Path(id): Path<String>) -> Response {
+    match state.lock().unwrap().purchase_rates.get(&id) {
+        Some(rate) => Json(rate.clone()).into_response(),
+        None => not_found("PurchaseRate", &id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::DriverActions;
+    use crate::error::FleetbaseError;
+    use crate::options::RequestOptions;
+    use crate::order::{Order, OrderActions, OrderStatus};
+    use crate::order_params::{DispatchParams, StartParams, UpdateActivityParams};
+    use crate::purchase_rate::create_purchase_rate;
+    use crate::service_quote::ServiceQuote;
+    use crate::utils::enpdpoints::{Endpoint, ServiceQuotes};
+    use crate::utils::StoreActions;
+
+    #[tokio::test]
+    async fn test_quote_to_dispatched_order() {
+        let fake = FakeFleetbase::start().await;
+        let client = fake.client();
+
+        let mut query = HashMap::new();
+        query.insert("pickup".to_string(), "place_a".to_string());
+        query.insert("dropoff".to_string(), "place_b".to_string());
+        let quote: ServiceQuote = client
+            .get_with_query(
+                Endpoint::ServiceQuotes(ServiceQuotes::ServiceQuotes),
+                &query,
+            )
+            .await
+            .unwrap();
+        assert_eq!(quote.amount, 1500.0);
+
+        let rate = create_purchase_rate(&client, quote.id.clone())
+            .await
+            .unwrap();
+        assert_eq!(rate.service_quote, quote.id);
+
+        let mut order: Order = client
+            .orders()
+            .create(&json!({ "purchase_rate": rate.id }))
+            .await
+            .unwrap();
+        order
+            .dispatch(&DispatchParams::new(), &RequestOptions::new())
+            .await
+            .unwrap();
+        order
+            .start(&StartParams::new(), &RequestOptions::new())
+            .await
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::DriverEnroute);
+
+        let stored = fake.order(order.id()).unwrap();
+        assert_eq!(stored["status"], "driver_enroute");
+        assert!(stored["dispatched_at"].is_string());
+
+        let activities = order
+            .get_next_activity(&RequestOptions::new())
+            .await
+            .unwrap();
+        assert_eq!(activities[0].order_status(), OrderStatus::Enroute);
+        order
+            .update_activity(&UpdateActivityParams::new(), &RequestOptions::new())
+            .await
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Enroute);
+
+        // Rejected locally, then by the server when the check is bypassed.
+        let requests = fake.requests().len();
+        let error = order
+            .dispatch(&DispatchParams::new(), &RequestOptions::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, FleetbaseError::InvalidTransition { .. }));
+        assert_eq!(fake.requests().len(), requests);
+
+        let error = OrderActions::new(client.clone())
+            .dispatch(order.id(), &DispatchParams::new(), &RequestOptions::new())
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), Some(reqwest::StatusCode::BAD_REQUEST));
+    }
+
+    #[tokio::test]
+    async fn test_driver_sms_login() {
+        let fake = FakeFleetbase::start().await;
+        let driver_id = fake.add_driver(json!({ "phone": "+15555550100", "name": "Sam" }));
+        let actions = DriverActions::new(fake.client());
+
+        actions
+            .login("+15555550100", None, HashMap::new())
+            .await
+            .unwrap();
+        let error = actions
+            .verify_code("+15555550100", "123456", HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(error.is_unauthorized());
+
+        let driver = actions
+            .verify_code("+15555550100", SMS_CODE, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(driver["id"], json!(driver_id));
+        assert!(driver["token"].is_string());
+        assert!(driver.get("password").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_driver_switches_organization() {
+        let fake = FakeFleetbase::start().await;
+        let first = fake.add_organization(json!({ "id": "org_a", "name": "Acme" }));
+        let second =
+            fake.add_organization(json!({ "id": "org_b", "name": "Beta", "slug": "beta" }));
+        fake.add_driver(json!({ "phone": "+15555550100" }));
+
+        let session = crate::session::DriverSession::new(fake.client());
+        session.request_sms_code("+15555550100").await.unwrap();
+        let mut authenticated = session.verify_code("+15555550100", SMS_CODE).await.unwrap();
+        let driver = &mut authenticated.driver;
+        let login_token = driver.token().unwrap().to_string();
+
+        let current = driver
+            .current_organization(HashMap::new(), HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(current.id, first);
+        assert_eq!(authenticated.client.organization(), Some(first));
+
+        let organizations = driver
+            .list_organizations(HashMap::new(), HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(organizations.len(), 2);
+
+        let switched = driver
+            .switch_organization(&second, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(switched.slug.as_deref(), Some("beta"));
+        assert!(!switched.extra.contains_key("token"));
+        assert_eq!(authenticated.client.organization(), Some(second));
+        assert_ne!(driver.token(), Some(login_token.as_str()));
+        assert_eq!(
+            authenticated.client.current_token(),
+            driver.token().unwrap()
+        );
+    }
+}
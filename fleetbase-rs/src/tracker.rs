@@ -0,0 +1,523 @@
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::driver::Driver;
+use crate::error::FleetbaseError;
+use crate::geo::{haversine, GeometryError, Point};
+
+/// One reading from a device's GPS.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpsFix {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Degrees clockwise from true north.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading: Option<f64>,
+    /// Meters per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+    /// Meters above sea level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+    /// Horizontal accuracy radius in meters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accuracy: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl GpsFix {
+    pub fn new(latitude: f64, longitude: f64, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            latitude,
+            longitude,
+            heading: None,
+            speed: None,
+            altitude: None,
+            accuracy: None,
+            timestamp,
+        }
+    }
+
+    pub fn heading(mut self, heading: f64) -> Self {
+        self.heading = Some(heading);
+        self
+    }
+
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = Some(speed);
+        self
+    }
+
+    pub fn altitude(mut self, altitude: f64) -> Self {
+        self.altitude = Some(altitude);
+        self
+    }
+
+    pub fn accuracy(mut self, accuracy: f64) -> Self {
+        self.accuracy = Some(accuracy);
+        self
+    }
+
+    pub fn point(&self) -> Result<Point, GeometryError> {
+        Point::from_lat_lng(self.latitude, self.longitude)
+    }
+
+    /// Great-circle distance to `other` in meters.
+    pub fn distance_to(&self, other: &GpsFix) -> f64 {
+        haversine(
+            self.latitude,
+            self.longitude,
+            other.latitude,
+            other.longitude,
+        )
+    }
+}
+
+/// What happened to a fix passed to `LocationTracker::record`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackOutcome {
+    /// Sent to Fleetbase, along with any fixes buffered before it.
+    Sent,
+    /// Accepted but kept in the buffer because Fleetbase is unreachable.
+    Buffered,
+    /// Dropped as a duplicate, out of order, inaccurate or too close to the
+    /// previous fix.
+    Skipped,
+}
+
+/// Turns a stream of GPS fixes into `drivers/{id}/track` calls.
+///
+/// A fix is sent when it has moved at least `min_distance` from the last
+/// accepted fix and `min_interval` has passed, or when `max_interval` has
+/// passed regardless of movement so the driver keeps showing as online.
+/// Fixes that cannot be delivered stay queued, on disk when `buffer_path` is
+/// set, and are replayed oldest first once a request gets through. With
+/// `replay_interval` set, a long backlog is thinned before it is replayed,
+/// so coming back online after hours costs hundreds of requests rather than
+/// thousands.
+#[derive(Debug)]
+pub struct LocationTracker {
+    driver: Driver,
+    min_distance: f64,
+    min_interval: Duration,
+    max_interval: Duration,
+    replay_interval: Option<Duration>,
+    max_accuracy: Option<f64>,
+    max_buffered: usize,
+    buffer_path: Option<PathBuf>,
+    queue: VecDeque<GpsFix>,
+    last_accepted: Option<GpsFix>,
+}
+
+impl LocationTracker {
+    /// `driver` must be attached to a client authenticated as the driver,
+    /// e.g. from `DriverSession`.
+    pub fn new(driver: Driver) -> Self {
+        Self {
+            driver,
+            min_distance: 10.0,
+            min_interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(60),
+            replay_interval: None,
+            max_accuracy: None,
+            max_buffered: 10_000,
+            buffer_path: None,
+            queue: VecDeque::new(),
+            last_accepted: None,
+        }
+    }
+
+    /// Meters a fix must move from the last accepted one to be sent.
+    pub fn min_distance(mut self, meters: f64) -> Self {
+        self.min_distance = meters;
+        self
+    }
+
+    pub fn min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    pub fn max_interval(mut self, interval: Duration) -> Self {
+        self.max_interval = interval;
+        self
+    }
+
+    /// Thins the queue to one fix per `interval` before replaying it,
+    /// always keeping the newest. Off by default: every queued fix is
+    /// replayed.
+    pub fn replay_interval(mut self, interval: Duration) -> Self {
+        self.replay_interval = Some(interval);
+        self
+    }
+
+    /// Drops fixes whose accuracy radius is larger than `meters`.
+    pub fn max_accuracy(mut self, meters: f64) -> Self {
+        self.max_accuracy = Some(meters);
+        self
+    }
+
+    /// Caps the offline queue; the oldest fixes are dropped first.
+    pub fn max_buffered(mut self, max: usize) -> Self {
+        self.max_buffered = max.max(1);
+        self
+    }
+
+    /// Persists the offline queue to `path`, loading anything left there by
+    /// a previous run.
+    pub fn buffer_path(mut self, path: impl Into<PathBuf>) -> Result<Self, FleetbaseError> {
+        let path = path.into();
+        let stored: Vec<GpsFix> = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        self.last_accepted = stored.last().cloned().or(self.last_accepted);
+        self.queue.extend(stored);
+        self.buffer_path = Some(path);
+        Ok(self)
+    }
+
+    pub fn driver(&self) -> &Driver {
+        &self.driver
+    }
+
+    pub fn into_driver(self) -> Driver {
+        self.driver
+    }
+
+    /// Fixes waiting to be sent, oldest first.
+    pub fn buffered(&self) -> impl Iterator<Item = &GpsFix> {
+        self.queue.iter()
+    }
+
+    /// Filters `fix`, queues it and tries to send everything queued.
+    ///
+    /// Errors are returned only for failures other than being offline; the
+    /// rejected fix is dropped so it cannot block the ones behind it.
+    pub async fn record(&mut self, fix: GpsFix) -> Result<TrackOutcome, FleetbaseError> {
+        if !self.accepts(&fix) {
+            return Ok(TrackOutcome::Skipped);
+        }
+        self.last_accepted = Some(fix.clone());
+        self.queue.push_back(fix);
+        while self.queue.len() > self.max_buffered {
+            self.queue.pop_front();
+        }
+        self.persist()?;
+        self.flush().await
+    }
+
+    /// Replays queued fixes in order until the queue is empty or Fleetbase
+    /// is unreachable. The queue on disk is rewritten once afterwards, so a
+    /// crash mid-flush can send some fixes twice but never loses one.
+    pub async fn flush(&mut self) -> Result<TrackOutcome, FleetbaseError> {
+        let before = self.queue.len();
+        let thinned = self.thin_backlog();
+        let outcome = self.send_queued().await;
+        if thinned || self.queue.len() != before {
+            self.persist()?;
+        }
+        outcome
+    }
+
+    async fn send_queued(&mut self) -> Result<TrackOutcome, FleetbaseError> {
+        while let Some(fix) = self.queue.front().cloned() {
+            match self.driver.track_fix(&fix).await {
+                Ok(()) => {
+                    self.queue.pop_front();
+                }
+                Err(e) if is_offline(&e) => return Ok(TrackOutcome::Buffered),
+                Err(e) => {
+                    self.queue.pop_front();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(TrackOutcome::Sent)
+    }
+
+    /// Records every fix from `fixes`, stopping at the first error.
+    pub async fn run<S>(&mut self, fixes: S) -> Result<(), FleetbaseError>
+    where
+        S: Stream<Item = GpsFix>,
+    {
+        futures::pin_mut!(fixes);
+        while let Some(fix) = fixes.next().await {
+            self.record(fix).await?;
+        }
+        Ok(())
+    }
+
+    /// Drops queued fixes within `replay_interval` of the previous kept one,
+    /// keeping the newest. Returns whether anything was dropped.
+    fn thin_backlog(&mut self) -> bool {
+        let Some(interval) = self.replay_interval else {
+            return false;
+        };
+        let Some(newest) = self.queue.pop_back() else {
+            return false;
+        };
+        let before = self.queue.len();
+        let mut kept: VecDeque<GpsFix> = VecDeque::with_capacity(before + 1);
+        for fix in self.queue.drain(..) {
+            let due = kept.back().is_none_or(|last| {
+                (fix.timestamp - last.timestamp)
+                    .to_std()
+                    .unwrap_or_default()
+                    >= interval
+            });
+            if due {
+                kept.push_back(fix);
+            }
+        }
+        let thinned = kept.len() < before;
+        kept.push_back(newest);
+        self.queue = kept;
+        thinned
+    }
+
+    fn accepts(&self, fix: &GpsFix) -> bool {
+        if fix.point().is_err() {
+            return false;
+        }
+        if let (Some(max), Some(accuracy)) = (self.max_accuracy, fix.accuracy) {
+            if accuracy > max {
+                return false;
+            }
+        }
+        let Some(last) = &self.last_accepted else {
+            return true;
+        };
+        if fix.timestamp <= last.timestamp {
+            return false;
+        }
+
+        let elapsed = (fix.timestamp - last.timestamp)
+            .to_std()
+            .unwrap_or_default();
+        if elapsed >= self.max_interval {
+            return true;
+        }
+        elapsed >= self.min_interval && fix.distance_to(last) >= self.min_distance
+    }
+
+    fn persist(&self) -> Result<(), FleetbaseError> {
+        let Some(path) = &self.buffer_path else {
+            return Ok(());
+        };
+        if self.queue.is_empty() {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let bytes = serde_json::to_vec(&self.queue)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Failures worth waiting out rather than dropping the fix for.
+fn is_offline(error: &FleetbaseError) -> bool {
+    match error {
+        FleetbaseError::Transport(_) => true,
+        _ => error.status().is_some_and(|status| {
+            status.is_server_error()
+                || status == StatusCode::TOO_MANY_REQUESTS
+                || status == StatusCode::REQUEST_TIMEOUT
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::FleetbaseClient;
+    use crate::testing::FakeFleetbase;
+    use crate::transport::{HttpResponse, MemoryTransport};
+    use chrono::TimeZone;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_throttles_and_updates_location() {
+        let fake = FakeFleetbase::start().await;
+        let id = fake.add_driver(json!({ "phone": "+15555550100" }));
+        let driver = Driver::new(fake.driver(&id).unwrap(), fake.client()).unwrap();
+        let mut tracker = LocationTracker::new(driver)
+            .min_distance(50.0)
+            .min_interval(Duration::from_secs(5));
+
+        let start = GpsFix::new(1.3521, 103.8198, at(0)).speed(8.0);
+        assert_eq!(
+            tracker.record(start.clone()).await.unwrap(),
+            TrackOutcome::Sent
+        );
+        // Same place, then too soon, then out of order.
+        let outcomes = [
+            GpsFix::new(1.3521, 103.8198, at(10)),
+            GpsFix::new(1.3621, 103.8198, at(2)),
+            GpsFix::new(1.3621, 103.8198, at(-5)),
+        ];
+        for fix in outcomes {
+            assert_eq!(tracker.record(fix).await.unwrap(), TrackOutcome::Skipped);
+        }
+        let moved = GpsFix::new(1.3621, 103.8198, at(10)).heading(90.0);
+        assert_eq!(tracker.record(moved).await.unwrap(), TrackOutcome::Sent);
+
+        assert_eq!(tracker.driver().coordinates(), Some((1.3621, 103.8198)));
+        assert_eq!(fake.driver(&id).unwrap()["heading"], json!(90.0));
+        let tracks = fake
+            .requests()
+            .iter()
+            .filter(|r| r.ends_with("/track"))
+            .count();
+        assert_eq!(tracks, 2);
+    }
+
+    #[tokio::test]
+    async fn test_buffers_offline_and_replays_in_order() {
+        let path = std::env::temp_dir()
+            .join(format!("fleetbase-rs-{}", uuid::Uuid::new_v4()))
+            .join("fixes.json");
+        let transport = Arc::new(MemoryTransport::new());
+        transport.push(HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE, ""));
+        let client = FleetbaseClient::builder("driver-token")
+            .retry_policy(crate::retry::RetryPolicy::none())
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        let driver = Driver::new(json!({ "id": "driver_1" }), client).unwrap();
+
+        let mut tracker = LocationTracker::new(driver.clone())
+            .min_interval(Duration::ZERO)
+            .buffer_path(&path)
+            .unwrap();
+        let first = GpsFix::new(1.30, 103.80, at(0));
+        assert_eq!(tracker.record(first).await.unwrap(), TrackOutcome::Buffered);
+        drop(tracker);
+
+        let mut tracker = LocationTracker::new(driver).buffer_path(&path).unwrap();
+        assert_eq!(tracker.buffered().count(), 1);
+        transport.on(
+            reqwest::Method::POST,
+            "/v1/drivers/driver_1/track",
+            HttpResponse::json(StatusCode::OK, json!({ "id": "driver_1" })),
+        );
+        let second = GpsFix::new(1.31, 103.80, at(60));
+        assert_eq!(tracker.record(second).await.unwrap(), TrackOutcome::Sent);
+        assert!(!path.exists());
+
+        let sent: Vec<f64> = transport
+            .requests()
+            .iter()
+            .skip(1)
+            .map(|r| r.json().unwrap()["latitude"].as_f64().unwrap())
+            .collect();
+        assert_eq!(sent, vec![1.30, 1.31]);
+        assert_eq!(tracker.driver().coordinates(), Some((1.31, 103.80)));
+
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_backlog_is_replayed_in_full_by_default() {
+        let path = std::env::temp_dir()
+            .join(format!("fleetbase-rs-{}", uuid::Uuid::new_v4()))
+            .join("fixes.json");
+        let backlog: Vec<GpsFix> = (0..50)
+            .map(|i| GpsFix::new(1.0 + i as f64 / 1024.0, 103.80, at(i * 5)))
+            .collect();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, serde_json::to_vec(&backlog).unwrap()).unwrap();
+
+        let transport = Arc::new(MemoryTransport::new());
+        transport.on(
+            reqwest::Method::POST,
+            "/v1/drivers/driver_1/track",
+            HttpResponse::json(StatusCode::OK, json!({ "id": "driver_1" })),
+        );
+        let client = FleetbaseClient::builder("driver-token")
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        let driver = Driver::new(json!({ "id": "driver_1" }), client).unwrap();
+        let mut tracker = LocationTracker::new(driver).buffer_path(&path).unwrap();
+        assert_eq!(tracker.flush().await.unwrap(), TrackOutcome::Sent);
+        assert!(!path.exists());
+
+        let sent: Vec<f64> = transport
+            .requests()
+            .iter()
+            .map(|r| r.json().unwrap()["latitude"].as_f64().unwrap())
+            .collect();
+        let expected: Vec<f64> = backlog.iter().map(|fix| fix.latitude).collect();
+        assert_eq!(sent, expected);
+
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_long_backlog_is_thinned_before_replay() {
+        let path = std::env::temp_dir()
+            .join(format!("fleetbase-rs-{}", uuid::Uuid::new_v4()))
+            .join("fixes.json");
+        // Almost three hours offline, a fix every five seconds.
+        let backlog: Vec<GpsFix> = (0..2_000)
+            .map(|i| GpsFix::new(1.0 + i as f64 / 1024.0, 103.80, at(i * 5)))
+            .collect();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, serde_json::to_vec(&backlog).unwrap()).unwrap();
+
+        let transport = Arc::new(MemoryTransport::new());
+        transport.on(
+            reqwest::Method::POST,
+            "/v1/drivers/driver_1/track",
+            HttpResponse::json(StatusCode::OK, json!({ "id": "driver_1" })),
+        );
+        let client = FleetbaseClient::builder("driver-token")
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        let driver = Driver::new(json!({ "id": "driver_1" }), client).unwrap();
+        let mut tracker = LocationTracker::new(driver)
+            .replay_interval(Duration::from_secs(60))
+            .buffer_path(&path)
+            .unwrap();
+        assert_eq!(tracker.flush().await.unwrap(), TrackOutcome::Sent);
+        assert!(!path.exists());
+
+        // One per minute from the first fix, plus the newest.
+        let sent: Vec<f64> = transport
+            .requests()
+            .iter()
+            .map(|r| r.json().unwrap()["latitude"].as_f64().unwrap())
+            .collect();
+        assert_eq!(sent.len(), 168);
+        assert_eq!(sent[0], backlog[0].latitude);
+        assert_eq!(sent[1], backlog[12].latitude);
+        assert_eq!(sent.last(), Some(&backlog[1_999].latitude));
+        assert_eq!(tracker.driver().latitude(), Some(backlog[1_999].latitude));
+
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
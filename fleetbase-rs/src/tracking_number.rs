@@ -1,13 +1,15 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value; // For handling the 'location' array
+
+use crate::geo::{self, Point};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TrackingPoint {
     // Renaming to "TrackingPoint" for clarity
     pub created_at: String, // You might want to consider using chrono::DateTime for date/time
     pub id: String,
-    pub latitude: f64,  // Latitude is typically a decimal value
-    pub longitude: f64, // Longitude is typically a decimal value
+    /// Sent by Fleetbase as flat `latitude` and `longitude` fields.
+    #[serde(flatten, with = "geo::lat_lng")]
+    pub location: Point,
     pub name: String,
     pub status: String, // You might consider using an enum for specific status values
     #[serde(rename = "type")] // Rename to avoid conflict with Rust's type keyword
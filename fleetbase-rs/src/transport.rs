@@ -0,0 +1,251 @@
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::{Client, Method, StatusCode};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::error::FleetbaseError;
+
+/// A fully prepared request: the URL already carries the query string and
+/// the headers include authentication.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<Vec<u8>>,
+    /// Overrides the transport's default timeout.
+    pub timeout: Option<Duration>,
+}
+
+impl HttpRequest {
+    pub fn new(method: Method, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            headers: HeaderMap::new(),
+            body: None,
+            timeout: None,
+        }
+    }
+
+    /// The URL without its scheme, host or query string.
+    pub fn path(&self) -> &str {
+        let without_query = self.url.split('?').next().unwrap_or_default();
+        match without_query.find("://") {
+            Some(scheme_end) => {
+                let rest = &without_query[scheme_end + 3..];
+                rest.find('/').map(|i| &rest[i..]).unwrap_or("/")
+            }
+            None => without_query,
+        }
+    }
+
+    pub fn query(&self) -> Option<&str> {
+        self.url.split_once('?').map(|(_, query)| query)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    pub fn json(&self) -> Option<serde_json::Value> {
+        self.body
+            .as_deref()
+            .and_then(|body| serde_json::from_slice(body).ok())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn new(status: StatusCode, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn json(status: StatusCode, value: serde_json::Value) -> Self {
+        let mut response = Self::new(status, value.to_string());
+        response
+            .headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        response
+    }
+
+    pub fn with_header(mut self, name: &'static str, value: &str) -> Self {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            self.headers.insert(name, value);
+        }
+        self
+    }
+}
+
+/// Sends requests on behalf of `FleetbaseClient`. Retries, rate limiting and
+/// response decoding happen in the client, so implementations only move bytes.
+#[async_trait]
+pub trait HttpTransport: fmt::Debug + Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, FleetbaseError>;
+}
+
+/// The default transport, backed by a configured `reqwest::Client`.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, FleetbaseError> {
+        let mut builder = self
+            .client
+            .request(request.method, &request.url)
+            .headers(request.headers);
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        let response = builder.send().await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await?;
+
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+struct Route {
+    method: Method,
+    path: String,
+    response: HttpResponse,
+}
+
+/// Records every request and answers with canned responses, for tests that
+/// should not open sockets. Routes registered with `on` match by method and
+/// path and can be hit repeatedly; responses queued with `push` are returned
+/// once each, in order, for requests no route matches. Anything else gets a
+/// 404.
+#[derive(Default)]
+pub struct MemoryTransport {
+    routes: Mutex<Vec<Route>>,
+    queue: Mutex<VecDeque<HttpResponse>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl fmt::Debug for MemoryTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryTransport")
+            .field("requests", &self.requests.lock().unwrap().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers `method path` (e.g. `GET /v1/orders`) with `response`. The
+    /// path is compared without the query string; later routes win.
+    pub fn on(&self, method: Method, path: impl Into<String>, response: HttpResponse) -> &Self {
+        self.routes.lock().unwrap().push(Route {
+            method,
+            path: path.into(),
+            response,
+        });
+        self
+    }
+
+    pub fn push(&self, response: HttpResponse) -> &Self {
+        self.queue.lock().unwrap().push_back(response);
+        self
+    }
+
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn last_request(&self) -> Option<HttpRequest> {
+        self.requests.lock().unwrap().last().cloned()
+    }
+}
+
+#[async_trait]
+impl HttpTransport for MemoryTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, FleetbaseError> {
+        let routed = self
+            .routes
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|route| route.method == request.method && route.path == request.path())
+            .map(|route| route.response.clone());
+        let response = routed
+            .or_else(|| self.queue.lock().unwrap().pop_front())
+            .unwrap_or_else(|| {
+                HttpResponse::json(
+                    StatusCode::NOT_FOUND,
+                    serde_json::json!({
+                        "error": format!("no canned response for {} {}", request.method, request.path())
+                    }),
+                )
+            });
+
+        self.requests.lock().unwrap().push(request);
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_transport_routes_then_queue() {
+        let transport = MemoryTransport::new();
+        transport
+            .on(
+                Method::GET,
+                "/v1/orders",
+                HttpResponse::json(StatusCode::OK, serde_json::json!([])),
+            )
+            .push(HttpResponse::new(StatusCode::CREATED, "{}"));
+
+        let get = HttpRequest::new(Method::GET, "https://api.fleetbase.io/v1/orders?limit=5");
+        assert_eq!(get.path(), "/v1/orders");
+        assert_eq!(get.query(), Some("limit=5"));
+        assert_eq!(transport.send(get.clone()).await.unwrap().body, "[]");
+        assert_eq!(transport.send(get).await.unwrap().body, "[]");
+
+        let post = HttpRequest::new(Method::POST, "https://api.fleetbase.io/v1/orders");
+        let response = transport.send(post.clone()).await.unwrap();
+        assert_eq!(response.status, StatusCode::CREATED);
+        let response = transport.send(post).await.unwrap();
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        assert_eq!(transport.requests().len(), 4);
+    }
+}
@@ -1,8 +1,9 @@
+use std::collections::HashMap;
 use std::fmt;
 
 // Parent enum
-#[derive(Debug)]
-enum Endpoint {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
     Places(Places),
     ServiceAreas(ServiceAreas),
     Zones(Zones),
@@ -21,88 +22,159 @@ enum Endpoint {
     TrackingStatuses(TrackingStatuses),
 }
 
-// Implement to_string for the parent enum
 impl Endpoint {
-    pub fn to_string(&self) -> String {
-        match self {
-            Endpoint::Places(e) => e.to_string(),
-            Endpoint::ServiceAreas(e) => e.to_string(),
-            Endpoint::Zones(e) => e.to_string(),
-            Endpoint::Contacts(e) => e.to_string(),
-            Endpoint::Vendors(e) => e.to_string(),
-            Endpoint::Vehicles(e) => e.to_string(),
-            Endpoint::Drivers(e) => e.to_string(),
-            Endpoint::Orders(e) => e.to_string(),
-            Endpoint::Fleets(e) => e.to_string(),
-            Endpoint::Payloads(e) => e.to_string(),
-            Endpoint::Entities(e) => e.to_string(),
-            Endpoint::ServiceRates(e) => e.to_string(),
-            Endpoint::ServiceQuotes(e) => e.to_string(),
-            Endpoint::PurchaseRates(e) => e.to_string(),
-            Endpoint::TrackingNumbers(e) => e.to_string(),
-            Endpoint::TrackingStatuses(e) => e.to_string(),
+    /// The path followed by a URL-encoded query string, e.g.
+    /// `orders?limit=10&status=active`. Keys are sorted for stable output.
+    pub fn with_query(&self, params: &HashMap<String, String>) -> String {
+        if params.is_empty() {
+            return self.to_string();
         }
-    }
-}
-
-#[derive(Debug)]
-enum Places {
-    Places,
-    PlacesById(String),
-}
-
-impl_to_string!(Places);
-
-#[derive(Debug)]
-enum ServiceAreas {
-    ServiceAreas,
-    ServiceAreasById(String),
-}
-
-impl_to_string!(ServiceAreas);
-
-#[derive(Debug)]
-enum Zones {
-    Zones,
-    ZonesById(String),
-}
 
-impl_to_string!(Zones);
+        let mut pairs: Vec<_> = params.iter().collect();
+        pairs.sort();
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish();
+        format!("{}?{}", self, query)
+    }
 
-#[derive(Debug)]
-enum Contacts {
-    Contacts,
-    ContactsById(String),
+    /// The key Fleetbase wraps a list of this endpoint's records in, e.g.
+    /// `service_areas` for `service-areas`.
+    pub fn resource_key(&self) -> String {
+        let path = self.to_string();
+        path.rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .replace('-', "_")
+    }
 }
 
-impl_to_string!(Contacts);
-
-#[derive(Debug)]
-enum Vendors {
-    Vendors,
-    VendorsById(String),
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Places(e) => e.fmt(f),
+            Endpoint::ServiceAreas(e) => e.fmt(f),
+            Endpoint::Zones(e) => e.fmt(f),
+            Endpoint::Contacts(e) => e.fmt(f),
+            Endpoint::Vendors(e) => e.fmt(f),
+            Endpoint::Vehicles(e) => e.fmt(f),
+            Endpoint::Drivers(e) => e.fmt(f),
+            Endpoint::Orders(e) => e.fmt(f),
+            Endpoint::Fleets(e) => e.fmt(f),
+            Endpoint::Payloads(e) => e.fmt(f),
+            Endpoint::Entities(e) => e.fmt(f),
+            Endpoint::ServiceRates(e) => e.fmt(f),
+            Endpoint::ServiceQuotes(e) => e.fmt(f),
+            Endpoint::PurchaseRates(e) => e.fmt(f),
+            Endpoint::TrackingNumbers(e) => e.fmt(f),
+            Endpoint::TrackingStatuses(e) => e.fmt(f),
+        }
+    }
 }
 
-impl_to_string!(Vendors);
+/// Declares a collection/member endpoint pair such as `places` and
+/// `places/{id}`.
+macro_rules! resource_endpoint {
+    ($enum_name:ident, $collection:ident, $member:ident, $path:literal) => {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum $enum_name {
+            $collection,
+            $member(String),
+        }
 
-#[derive(Debug)]
-enum Vehicles {
-    Vehicles,
-    VehiclesById(String),
+        impl fmt::Display for $enum_name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    Self::$collection => f.write_str($path),
+                    Self::$member(id) => write!(f, "{}/{}", $path, id),
+                }
+            }
+        }
+    };
 }
 
-impl_to_string!(Vehicles);
+resource_endpoint!(Places, Places, PlacesById, "places");
+resource_endpoint!(
+    ServiceAreas,
+    ServiceAreas,
+    ServiceAreasById,
+    "service-areas"
+);
+resource_endpoint!(Zones, Zones, ZonesById, "zones");
+resource_endpoint!(Contacts, Contacts, ContactsById, "contacts");
+resource_endpoint!(Vendors, Vendors, VendorsById, "vendors");
+resource_endpoint!(Vehicles, Vehicles, VehiclesById, "vehicles");
+resource_endpoint!(Fleets, Fleets, FleetsById, "fleets");
+resource_endpoint!(Payloads, Payloads, PayloadsById, "payloads");
+resource_endpoint!(Entities, Entities, EntitiesById, "entities");
+resource_endpoint!(
+    ServiceRates,
+    ServiceRates,
+    ServiceRatesById,
+    "service-rates"
+);
+resource_endpoint!(
+    ServiceQuotes,
+    ServiceQuotes,
+    ServiceQuotesById,
+    "service-quotes"
+);
+resource_endpoint!(
+    PurchaseRates,
+    PurchaseRates,
+    PurchaseRatesById,
+    "purchase-rates"
+);
+resource_endpoint!(
+    TrackingNumbers,
+    TrackingNumbers,
+    TrackingNumbersById,
+    "tracking-numbers"
+);
+resource_endpoint!(
+    TrackingStatuses,
+    TrackingStatuses,
+    TrackingStatusesById,
+    "tracking-statuses"
+);
 
-#[derive(Debug)]
-enum Drivers {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Drivers {
     Drivers,
     DriversById(String),
+    DriversLogin,
+    DriversLoginWithSms,
+    DriversVerifyCode,
+    DriversTrack(String),
+    DriversRegisterDevice(String),
+    DriversOrganizations(String),
+    DriversSwitchOrganization(String),
+    DriversCurrentOrganization(String),
 }
 
-impl_to_string!(Drivers);
+impl fmt::Display for Drivers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drivers::Drivers => f.write_str("drivers"),
+            Drivers::DriversById(id) => write!(f, "drivers/{}", id),
+            Drivers::DriversLogin => f.write_str("drivers/login"),
+            Drivers::DriversLoginWithSms => f.write_str("drivers/login-with-sms"),
+            Drivers::DriversVerifyCode => f.write_str("drivers/verify-code"),
+            Drivers::DriversTrack(id) => write!(f, "drivers/{}/track", id),
+            Drivers::DriversRegisterDevice(id) => write!(f, "drivers/{}/register-device", id),
+            Drivers::DriversOrganizations(id) => write!(f, "drivers/{}/organizations", id),
+            Drivers::DriversSwitchOrganization(id) => {
+                write!(f, "drivers/{}/switch-organization", id)
+            }
+            Drivers::DriversCurrentOrganization(id) => {
+                write!(f, "drivers/{}/current-organization", id)
+            }
+        }
+    }
+}
 
-#[derive(Debug)]
-enum Orders {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Orders {
     Orders,
     OrdersById(String),
     OrdersSchedule(String),
@@ -110,91 +182,44 @@ enum Orders {
     OrdersStart(String),
     OrdersUpdateActivity(String),
     OrdersNextActivity(String),
+    OrdersDistanceAndTime(String),
     OrdersSetDestination(String, String),
-    OrdersCaptureSignature(String, String),
-    OrdersCaptureQr(String, String),
-    OrdersOrderV(String),
+    OrdersCaptureSignature(String, Option<String>),
+    OrdersCaptureQr(String, Option<String>),
+    OrdersComplete(String),
+    OrdersCancel(String),
 }
 
-impl_to_string!(Orders);
-
-#[derive(Debug)]
-enum Fleets {
-    Fleets,
-    FleetsById(String),
-}
-
-impl_to_string!(Fleets);
-
-#[derive(Debug)]
-enum Payloads {
-    Payloads,
-    PayloadsById(String),
-}
-
-impl_to_string!(Payloads);
-
-#[derive(Debug)]
-enum Entities {
-    Entities,
-    EntitiesById(String),
-}
-
-impl_to_string!(Entities);
-
-#[derive(Debug)]
-enum ServiceRates {
-    ServiceRates,
-    ServiceRatesById(String),
-}
-
-impl_to_string!(ServiceRates);
-
-#[derive(Debug)]
-enum ServiceQuotes {
-    ServiceQuotes,
-}
-
-impl_to_string!(ServiceQuotes);
-
-#[derive(Debug)]
-enum PurchaseRates {
-    PurchaseRates,
-    PurchaseRatesById(String),
-}
-
-impl_to_string!(PurchaseRates);
-
-#[derive(Debug)]
-enum TrackingNumbers {
-    TrackingNumbers,
-    TrackingNumbersById(String),
-}
-
-impl_to_string!(TrackingNumbers);
-
-#[derive(Debug)]
-enum TrackingStatuses {
-    TrackingStatuses,
-    TrackingStatusesById(String),
-}
-
-impl_to_string!(TrackingStatuses);
-macro_rules! impl_to_string {
-    ($enum_name:ident) => {
-        impl $enum_name {
-            pub fn to_string(&self) -> String {
-                match self {
-                    $(Self::$variant $(($($param:ident),*))? => {
-                        let base = stringify!($variant).to_lowercase();
-                        $(format!("{}/{}", base, $($param),*))
-                        $($(.replace("_", "-")))?
-                    },)*
-                }
+impl fmt::Display for Orders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Orders::Orders => f.write_str("orders"),
+            Orders::OrdersById(id) => write!(f, "orders/{}", id),
+            Orders::OrdersSchedule(id) => write!(f, "orders/{}/schedule", id),
+            Orders::OrdersDispatch(id) => write!(f, "orders/{}/dispatch", id),
+            Orders::OrdersStart(id) => write!(f, "orders/{}/start", id),
+            Orders::OrdersUpdateActivity(id) => write!(f, "orders/{}/update-activity", id),
+            Orders::OrdersNextActivity(id) => write!(f, "orders/{}/next-activity", id),
+            Orders::OrdersDistanceAndTime(id) => write!(f, "orders/{}/distance-and-time", id),
+            Orders::OrdersSetDestination(id, place) => {
+                write!(f, "orders/{}/set-destination/{}", id, place)
+            }
+            Orders::OrdersCaptureSignature(id, None) => {
+                write!(f, "orders/{}/capture-signature", id)
+            }
+            Orders::OrdersCaptureSignature(id, Some(subject)) => {
+                write!(f, "orders/{}/capture-signature/{}", id, subject)
             }
+            Orders::OrdersCaptureQr(id, None) => write!(f, "orders/{}/capture-qr", id),
+            Orders::OrdersCaptureQr(id, Some(subject)) => {
+                write!(f, "orders/{}/capture-qr/{}", id, subject)
+            }
+            Orders::OrdersComplete(id) => write!(f, "orders/{}/complete", id),
+            Orders::OrdersCancel(id) => write!(f, "orders/{}/cancel", id),
         }
-    };
+    }
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -218,14 +243,34 @@ mod tests {
             (Endpoint::Orders(Orders::Orders), "orders"),
             (
                 Endpoint::Orders(Orders::OrdersUpdateActivity("789".to_string())),
-                "orders/update-activity/789",
+                "orders/789/update-activity",
             ),
             (
                 Endpoint::Orders(Orders::OrdersSetDestination(
                     "789".to_string(),
                     "waypoint1".to_string(),
                 )),
-                "orders/set-destination/789/waypoint1",
+                "orders/789/set-destination/waypoint1",
+            ),
+            (
+                Endpoint::Orders(Orders::OrdersDispatch("789".to_string())),
+                "orders/789/dispatch",
+            ),
+            (
+                Endpoint::Orders(Orders::OrdersCaptureQr("789".to_string(), None)),
+                "orders/789/capture-qr",
+            ),
+            (
+                Endpoint::Drivers(Drivers::DriversTrack("driver_1".to_string())),
+                "drivers/driver_1/track",
+            ),
+            (
+                Endpoint::Drivers(Drivers::DriversLoginWithSms),
+                "drivers/login-with-sms",
+            ),
+            (
+                Endpoint::TrackingStatuses(TrackingStatuses::TrackingStatuses),
+                "tracking-statuses",
             ),
         ];
 
@@ -233,4 +278,20 @@ mod tests {
             assert_eq!(endpoint.to_string(), expected, "Failed for {:?}", endpoint);
         }
     }
+
+    #[test]
+    fn test_endpoint_with_query() {
+        let mut params = HashMap::new();
+        params.insert("status".to_string(), "active".to_string());
+        params.insert("query".to_string(), "main st".to_string());
+
+        assert_eq!(
+            Endpoint::Orders(Orders::Orders).with_query(&params),
+            "orders?query=main+st&status=active"
+        );
+        assert_eq!(
+            Endpoint::Orders(Orders::Orders).with_query(&HashMap::new()),
+            "orders"
+        );
+    }
 }
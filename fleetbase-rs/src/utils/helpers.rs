@@ -1,6 +1,13 @@
 use regex::Regex;
-
 pub fn is_phone(s: &str) -> bool {
     let re = Regex::new(r"^\+?[\d\s-]+$").unwrap();
     re.is_match(s)
 }
+
+/// True when `s` is a serialized resource (a JSON object carrying an `id`)
+/// rather than a bare id.
+pub fn is_resource(s: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(s)
+        .map(|v| v.get("id").is_some())
+        .unwrap_or(false)
+}
@@ -1,6 +1,7 @@
 pub mod enpdpoints;
 pub mod helpers;
 pub mod store_actions;
+pub mod timestamp;
 
 pub use helpers::*;
 pub use store_actions::StoreActions;
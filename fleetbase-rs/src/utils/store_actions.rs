@@ -1,3 +1,4 @@
+use crate::error::FleetbaseError;
 use crate::organization::Organization;
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -9,42 +10,42 @@ pub trait StoreActions {
         identity: &str,
         password: Option<&str>,
         attributes: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>>;
+    ) -> Result<serde_json::Value, FleetbaseError>;
     async fn verify_code(
         &self,
         identity: &str,
         code: &str,
         attributes: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>>;
+    ) -> Result<serde_json::Value, FleetbaseError>;
     async fn track(
         &self,
         id: &str,
         params: HashMap<String, String>,
         options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>>;
+    ) -> Result<serde_json::Value, FleetbaseError>;
     async fn list_organizations(
         &self,
         id: &str,
         params: HashMap<String, String>,
         options: HashMap<String, String>,
-    ) -> Result<Vec<Organization>, Box<dyn std::error::Error>>;
+    ) -> Result<Vec<Organization>, FleetbaseError>;
     async fn switch_organization(
         &self,
         id: &str,
         params: HashMap<String, String>,
         options: HashMap<String, String>,
-    ) -> Result<Organization, Box<dyn std::error::Error>>;
+    ) -> Result<Organization, FleetbaseError>;
     async fn current_organization(
         &self,
         id: &str,
         params: HashMap<String, String>,
         options: HashMap<String, String>,
-    ) -> Result<Organization, Box<dyn std::error::Error>>;
-    async fn retrieve(&self, id: &str) -> Result<serde_json::Value, Box<dyn std::error::Error>>;
+    ) -> Result<Organization, FleetbaseError>;
+    async fn retrieve(&self, id: &str) -> Result<serde_json::Value, FleetbaseError>;
     async fn sync_device(
         &self,
         id: &str,
         params: HashMap<String, String>,
         options: HashMap<String, String>,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>>;
+    ) -> Result<serde_json::Value, FleetbaseError>;
 }
@@ -0,0 +1,47 @@
+//! Serde helpers for Fleetbase timestamps, which arrive either as RFC 3339
+//! strings or as `YYYY-MM-DD HH:MM:SS` in UTC.
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Some(at.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|at| at.and_utc())
+}
+
+pub fn format(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// For `Option<DateTime<Utc>>` fields: `null`, missing and empty strings
+/// become `None`.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(at) => serializer.serialize_str(&format(at)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(value) if value.is_empty() => Ok(None),
+            Some(value) => parse(&value)
+                .map(Some)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid timestamp {:?}", value))),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Vehicle {
     pub id: String,
     pub make: String,
@@ -15,12 +15,13 @@ pub struct Vehicle {
     pub model_data: Option<ModelData>,
     pub name: Option<String>,
     pub photo_url: Option<String>,
+    #[serde(rename = "type")]
     pub type_: Option<String>, // Renamed from "type" to avoid keyword conflict
     pub updated_at: Option<String>,
     pub vin_data: Option<VinData>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ModelData {
     #[serde(rename = "0_to_100_kph")]
     pub zero_to_100_kph: String,
@@ -55,7 +56,7 @@ pub struct ModelData {
     pub width_mm: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VinData {
     pub body: String,
     pub check_digit: String,
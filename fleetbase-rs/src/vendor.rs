@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::error::FleetbaseError;
+use crate::store::{load_records, Collection, MemoryStore, Store, Write};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vendor {
     pub id: String,
     pub created_at: DateTime<Utc>,
@@ -25,6 +29,7 @@ impl Vendor {
         internal_id: Option<String>,
     ) -> Self {
         let now = Utc::now();
+        let slug = name.to_lowercase().replace(" ", "-");
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             created_at: now,
@@ -35,7 +40,7 @@ impl Vendor {
             name,
             phone_country_code: "+1".to_string(), // Default to +1, you might want to parse this from the phone number
             phone_number: phone,
-            slug: name.to_lowercase().replace(" ", "-"),
+            slug,
             r#type,
         }
     }
@@ -52,8 +57,8 @@ impl Vendor {
             self.email = email;
         }
         if let Some(name) = name {
-            self.name = name;
             self.slug = name.to_lowercase().replace(" ", "-");
+            self.name = name;
         }
         if let Some(phone) = phone {
             self.phone_number = phone;
@@ -70,15 +75,62 @@ impl Vendor {
 
 pub struct VendorManager {
     vendors: Vec<Vendor>,
+    store: Arc<dyn Store>,
 }
 
-impl VendorManager {
-    pub fn new() -> Self {
+impl Default for VendorManager {
+    fn default() -> Self {
         Self {
             vendors: Vec::new(),
+            store: Arc::new(MemoryStore::new()),
         }
     }
+}
 
+impl VendorManager {
+    /// Keeps vendors in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the vendors already in `store` and writes every change to it.
+    pub fn with_store(store: Arc<dyn Store>) -> Result<Self, FleetbaseError> {
+        let vendors = load_records(store.as_ref(), Collection::Vendors)?;
+        Ok(Self { vendors, store })
+    }
+
+    pub fn store(&self) -> &Arc<dyn Store> {
+        &self.store
+    }
+
+    fn persist(&self, write: Write) -> Result<(), String> {
+        self.store.apply(&[write]).map_err(|e| e.to_string())
+    }
+
+    fn put(&self, vendor: &Vendor) -> Result<(), String> {
+        let write =
+            Write::put(Collection::Vendors, &vendor.id, vendor).map_err(|e| e.to_string())?;
+        self.persist(write)
+    }
+
+    /// Stores `vendor` as is, replacing any vendor with the same id.
+    pub(crate) fn put_vendor(&mut self, vendor: Vendor) -> Result<&Vendor, String> {
+        self.put(&vendor)?;
+        let index = match self.vendors.iter().position(|v| v.id == vendor.id) {
+            Some(index) => {
+                self.vendors[index] = vendor;
+                index
+            }
+            None => {
+                self.vendors.push(vendor);
+                self.vendors.len() - 1
+            }
+        };
+        Ok(&self.vendors[index])
+    }
+
+    /// Panics if the store rejects the write; `try_create_vendor` returns
+    /// the error instead.
     pub fn create_vendor(
         &mut self,
         email: String,
@@ -87,15 +139,30 @@ impl VendorManager {
         r#type: String,
         internal_id: Option<String>,
     ) -> &Vendor {
+        self.try_create_vendor(email, name, phone, r#type, internal_id)
+            .unwrap_or_else(|e| panic!("failed to store vendor: {}", e))
+    }
+
+    pub fn try_create_vendor(
+        &mut self,
+        email: String,
+        name: String,
+        phone: String,
+        r#type: String,
+        internal_id: Option<String>,
+    ) -> Result<&Vendor, String> {
         let vendor = Vendor::new(email, name, phone, r#type, internal_id);
+        self.put(&vendor)?;
         self.vendors.push(vendor);
-        self.vendors.last().unwrap()
+        Ok(self.vendors.last().unwrap())
     }
 
     pub fn get_vendor(&self, id: &str) -> Option<&Vendor> {
         self.vendors.iter().find(|v| v.id == id)
     }
 
+    /// `None` if there is no such vendor. Panics if the store rejects the
+    /// write; `try_update_vendor` returns the error instead.
     pub fn update_vendor(
         &mut self,
         id: &str,
@@ -105,20 +172,52 @@ impl VendorManager {
         r#type: Option<String>,
         internal_id: Option<String>,
     ) -> Option<&Vendor> {
-        if let Some(vendor) = self.vendors.iter_mut().find(|v| v.id == id) {
-            vendor.update(email, name, phone, r#type, internal_id);
-            Some(vendor)
-        } else {
-            None
-        }
+        self.get_vendor(id)?;
+        Some(
+            self.try_update_vendor(id, email, name, phone, r#type, internal_id)
+                .unwrap_or_else(|e| panic!("failed to store vendor: {}", e)),
+        )
+    }
+
+    pub fn try_update_vendor(
+        &mut self,
+        id: &str,
+        email: Option<String>,
+        name: Option<String>,
+        phone: Option<String>,
+        r#type: Option<String>,
+        internal_id: Option<String>,
+    ) -> Result<&Vendor, String> {
+        let index = self
+            .vendors
+            .iter()
+            .position(|v| v.id == id)
+            .ok_or("Vendor not found")?;
+        let mut vendor = self.vendors[index].clone();
+        vendor.update(email, name, phone, r#type, internal_id);
+        self.put(&vendor)?;
+        self.vendors[index] = vendor;
+        Ok(&self.vendors[index])
     }
 
+    /// `None` if there is no such vendor. Panics if the store rejects the
+    /// write; `try_delete_vendor` returns the error instead.
     pub fn delete_vendor(&mut self, id: &str) -> Option<Vendor> {
-        if let Some(index) = self.vendors.iter().position(|v| v.id == id) {
-            Some(self.vendors.remove(index))
-        } else {
-            None
-        }
+        self.get_vendor(id)?;
+        Some(
+            self.try_delete_vendor(id)
+                .unwrap_or_else(|e| panic!("failed to delete vendor: {}", e)),
+        )
+    }
+
+    pub fn try_delete_vendor(&mut self, id: &str) -> Result<Vendor, String> {
+        let index = self
+            .vendors
+            .iter()
+            .position(|v| v.id == id)
+            .ok_or("Vendor not found")?;
+        self.persist(Write::delete(Collection::Vendors, id))?;
+        Ok(self.vendors.remove(index))
     }
 
     pub fn list_vendors(&self) -> &[Vendor] {
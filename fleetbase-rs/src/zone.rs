@@ -1,8 +1,15 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::error::FleetbaseError;
+use crate::geo::{BoundingBox, GeometryError, Point, Polygon};
+use crate::service_area::ServiceArea;
+use crate::spatial::SpatialIndex;
+use crate::store::{load_records, Collection, MemoryStore, Store, Write};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Zone {
     pub id: String,
     pub created_at: DateTime<Utc>,
@@ -11,28 +18,73 @@ pub struct Zone {
     pub description: Option<String>,
     pub color: String,
     pub stroke_color: String,
-    pub coordinates: Vec<Vec<[f64; 2]>>,
+    pub border: Border,
     pub service_area: Option<String>,
     pub status: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A zone's outline: a GeoJSON Polygon with a `bbox` member, computed from
+/// the polygon when the source leaves it out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RawBorder")]
 pub struct Border {
-    pub r#type: String,
-    pub coordinates: Vec<Vec<[f64; 2]>>,
-    pub bbox: Option<[f64; 4]>,
+    #[serde(flatten)]
+    pub polygon: Polygon,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<BoundingBox>,
+}
+
+#[derive(Deserialize)]
+struct RawBorder {
+    #[serde(flatten)]
+    polygon: Polygon,
+    #[serde(default)]
+    bbox: Option<BoundingBox>,
+}
+
+impl From<RawBorder> for Border {
+    fn from(raw: RawBorder) -> Self {
+        let bbox = raw.bbox.or_else(|| raw.polygon.bbox());
+        Self {
+            polygon: raw.polygon,
+            bbox,
+        }
+    }
+}
+
+impl Border {
+    pub fn new(polygon: Polygon) -> Self {
+        Self {
+            bbox: polygon.bbox(),
+            polygon,
+        }
+    }
+
+    /// Checks the polygon against RFC 7946; see `Polygon::problems`.
+    pub fn validate(&self) -> Result<(), GeometryError> {
+        self.polygon.validate()
+    }
+
+    /// Closes open rings and fixes their winding, then recomputes `bbox`.
+    pub fn repair(&mut self) {
+        self.polygon = self.polygon.repair();
+        self.bbox = self.polygon.bbox();
+    }
 }
 
 impl Zone {
+    /// Fails with every problem found in `border`. Call `Border::repair`
+    /// first to accept open or wrongly wound rings.
     pub fn new(
         name: String,
         border: Border,
         color: String,
         stroke_color: String,
         service_area: Option<String>,
-    ) -> Self {
+    ) -> Result<Self, GeometryError> {
+        border.validate()?;
         let now = Utc::now();
-        Self {
+        Ok(Self {
             id: uuid::Uuid::new_v4().to_string(),
             created_at: now,
             updated_at: now,
@@ -40,10 +92,15 @@ impl Zone {
             description: None,
             color,
             stroke_color,
-            coordinates: border.coordinates,
+            border,
             service_area,
             status: "active".to_string(),
-        }
+        })
+    }
+
+    /// Whether `point` lies inside the border, holes excluded.
+    pub fn contains(&self, point: &Point) -> bool {
+        self.border.polygon.contains(point)
     }
 
     pub fn update(&mut self, color: Option<String>, name: Option<String>, status: Option<String>) {
@@ -62,27 +119,98 @@ impl Zone {
 
 pub struct ZoneManager {
     zones: HashMap<String, Zone>,
+    index: SpatialIndex,
+    store: Arc<dyn Store>,
 }
 
-impl ZoneManager {
-    pub fn new() -> Self {
+impl Default for ZoneManager {
+    fn default() -> Self {
         Self {
             zones: HashMap::new(),
+            index: SpatialIndex::new(),
+            store: Arc::new(MemoryStore::new()),
         }
     }
+}
+
+impl ZoneManager {
+    /// Keeps zones in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
+    /// Loads the zones already in `store` and writes every change to it.
+    pub fn with_store(store: Arc<dyn Store>) -> Result<Self, FleetbaseError> {
+        let mut manager = Self {
+            store,
+            ..Self::default()
+        };
+        for zone in load_records::<Zone>(manager.store.as_ref(), Collection::Zones)? {
+            manager.cache(zone);
+        }
+        Ok(manager)
+    }
+
+    pub fn store(&self) -> &Arc<dyn Store> {
+        &self.store
+    }
+
+    fn cache(&mut self, zone: Zone) -> &Zone {
+        let id = zone.id.clone();
+        if let Some(bbox) = zone.border.polygon.bbox() {
+            self.index.insert(id.clone(), &bbox);
+        }
+        self.zones.insert(id.clone(), zone);
+        self.zones.get(&id).unwrap()
+    }
+
+    fn persist(&self, writes: &[Write]) -> Result<(), String> {
+        self.store.apply(writes).map_err(|e| e.to_string())
+    }
+
+    /// Stores `zone` as is, replacing any zone with the same id.
+    pub(crate) fn put_zone(&mut self, zone: Zone) -> Result<&Zone, String> {
+        let write = Write::put(Collection::Zones, &zone.id, &zone).map_err(|e| e.to_string())?;
+        self.persist(&[write])?;
+        Ok(self.cache(zone))
+    }
+
+    /// Drops a zone from memory after its deletion was written elsewhere.
+    pub(crate) fn forget(&mut self, id: &str) {
+        self.zones.remove(id);
+        self.index.remove(id);
+    }
+
+    /// Fails if the border is invalid or, when `service_area` is given,
+    /// extends outside its radius.
     pub fn create_zone(
         &mut self,
         name: String,
         border: Border,
         color: String,
         stroke_color: String,
-        service_area: Option<String>,
-    ) -> &Zone {
-        let zone = Zone::new(name, border, color, stroke_color, service_area);
-        let id = zone.id.clone();
-        self.zones.insert(id.clone(), zone);
-        self.zones.get(&id).unwrap()
+        service_area: Option<&ServiceArea>,
+    ) -> Result<&Zone, String> {
+        let zone = Zone::new(name, border, color, stroke_color, None).map_err(|e| e.to_string())?;
+        self.insert_zone(zone, service_area)
+    }
+
+    /// Adds an already validated zone, assigning it to `service_area`.
+    pub(crate) fn insert_zone(
+        &mut self,
+        mut zone: Zone,
+        service_area: Option<&ServiceArea>,
+    ) -> Result<&Zone, String> {
+        if let Some(area) = service_area {
+            if !area.contains_polygon(&zone.border.polygon) {
+                return Err(format!(
+                    "Zone border extends outside service area {}",
+                    area.name
+                ));
+            }
+        }
+        zone.service_area = service_area.map(|area| area.id.clone());
+        self.put_zone(zone)
     }
 
     pub fn get_zone(&self, id: &str) -> Option<&Zone> {
@@ -96,26 +224,102 @@ impl ZoneManager {
         name: Option<String>,
         status: Option<String>,
     ) -> Result<&Zone, String> {
-        if let Some(zone) = self.zones.get_mut(id) {
-            zone.update(color, name, status);
-            Ok(zone)
-        } else {
-            Err("Zone not found".to_string())
-        }
+        let mut zone = self.zones.get(id).ok_or("Zone not found")?.clone();
+        zone.update(color, name, status);
+        self.put_zone(zone)
     }
 
     pub fn delete_zone(&mut self, id: &str) -> Result<bool, String> {
-        if self.zones.remove(id).is_some() {
-            Ok(true)
-        } else {
-            Err("Zone not found".to_string())
+        if !self.zones.contains_key(id) {
+            return Err("Zone not found".to_string());
         }
+        self.persist(&[Write::delete(Collection::Zones, id)])?;
+        self.forget(id);
+        Ok(true)
+    }
+
+    /// Zones whose border contains `point`, smallest first so the most
+    /// specific zone leads when zones overlap.
+    pub fn zones_containing(&self, point: &Point) -> Vec<&Zone> {
+        let mut zones: Vec<&Zone> = self
+            .index
+            .containing(point)
+            .filter_map(|id| self.zones.get(id))
+            .filter(|z| z.contains(point))
+            .collect();
+        zones.sort_by(|a, b| bbox_area(a).total_cmp(&bbox_area(b)).then(a.id.cmp(&b.id)));
+        zones
+    }
+
+    /// Zones whose bounding box intersects `bbox`, e.g. a map viewport.
+    pub fn zones_in_bbox(&self, bbox: &BoundingBox) -> Vec<&Zone> {
+        self.index
+            .intersecting(bbox)
+            .filter_map(|id| self.zones.get(id))
+            .collect()
+    }
+
+    pub fn zones_in_service_area(&self, service_area: &str) -> Vec<&Zone> {
+        self.zones
+            .values()
+            .filter(|z| z.service_area.as_deref() == Some(service_area))
+            .collect()
     }
 
     pub fn list_zones(&self, name: Option<&str>) -> Vec<&Zone> {
         self.zones
             .values()
-            .filter(|&z| name.map_or(true, |n| z.name.contains(n)))
+            .filter(|&z| name.is_none_or(|n| z.name.contains(n)))
             .collect()
     }
 }
+
+fn bbox_area(zone: &Zone) -> f64 {
+    zone.border.polygon.bbox().map_or(0.0, |bbox| {
+        (bbox.max().longitude() - bbox.min().longitude())
+            * (bbox.max().latitude() - bbox.min().latitude())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_border_geojson_round_trip() {
+        let value = json!({
+            "type": "Polygon",
+            "coordinates": [[[103.6, 1.2], [104.0, 1.2], [104.0, 1.5], [103.6, 1.2]]],
+            "bbox": [103.6, 1.2, 104.0, 1.5]
+        });
+        let border: Border = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(border.bbox, border.polygon.bbox());
+        assert_eq!(serde_json::to_value(&border).unwrap(), value);
+
+        let swapped = json!({
+            "type": "Polygon",
+            "coordinates": [[[1.2, 103.6], [1.2, 104.0], [1.5, 104.0], [1.2, 103.6]]]
+        });
+        assert!(serde_json::from_value::<Border>(swapped).is_err());
+
+        let without_bbox = json!({
+            "type": "Polygon",
+            "coordinates": [[[103.6, 1.2], [104.0, 1.5], [104.0, 1.2]]]
+        });
+        let mut border: Border = serde_json::from_value(without_bbox).unwrap();
+        assert_eq!(border.bbox, border.polygon.bbox());
+        let err = Zone::new(
+            "Marina".to_string(),
+            border.clone(),
+            "#00ff00".to_string(),
+            "#008800".to_string(),
+            None,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("does not end where it starts"));
+
+        border.repair();
+        assert!(border.validate().is_ok());
+    }
+}